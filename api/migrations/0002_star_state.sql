@@ -0,0 +1,3 @@
+-- desired state of the star, kept across reconciliations of the deployment
+ALTER TABLE stars
+  ADD COLUMN state TEXT NOT NULL DEFAULT 'running' CHECK(state IN ('running', 'stopped'));
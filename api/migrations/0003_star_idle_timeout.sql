@@ -0,0 +1,3 @@
+-- minutes without requests before the star is scaled to zero, NULL disables scale to zero
+ALTER TABLE stars
+  ADD COLUMN idle_timeout INT CHECK(idle_timeout > 0);
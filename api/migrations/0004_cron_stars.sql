@@ -0,0 +1,8 @@
+-- cron stars run on a schedule with a kubernetes cronjob instead of a deployment
+ALTER TABLE stars
+  ADD COLUMN kind TEXT NOT NULL DEFAULT 'service' CHECK(kind IN ('service', 'cron')),
+  ADD COLUMN schedule TEXT,
+  ADD COLUMN timezone TEXT,
+  ADD COLUMN concurrency_policy TEXT NOT NULL DEFAULT 'allow' CHECK(concurrency_policy IN ('allow', 'forbid', 'replace')),
+  ADD COLUMN successful_jobs_history_limit INT NOT NULL DEFAULT 3 CHECK(successful_jobs_history_limit >= 0),
+  ADD COLUMN failed_jobs_history_limit INT NOT NULL DEFAULT 1 CHECK(failed_jobs_history_limit >= 0);
@@ -0,0 +1,12 @@
+-- one-off commands executed with the image, variables and planets of a star
+CREATE TABLE IF NOT EXISTS runs (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  command TEXT[] NOT NULL,
+  status TEXT NOT NULL DEFAULT 'running' CHECK(status IN ('running', 'succeeded', 'failed')),
+  exit_code INT, -- missing while running or when the job disappeared before finishing
+  started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  finished_at TIMESTAMPTZ,
+  duration INT GENERATED ALWAYS AS (EXTRACT(EPOCH FROM finished_at - started_at)::INT) STORED, -- unit is seconds
+  star_id UUID NOT NULL,
+  FOREIGN KEY (star_id) REFERENCES stars(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+-- ordered init and sidecar containers sharing the pod of the star
+ALTER TABLE stars
+  ADD COLUMN containers JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,16 @@
+-- deployment strategy of service stars, blue-green and canary updates go through a rollout
+ALTER TABLE stars
+  ADD COLUMN strategy TEXT NOT NULL DEFAULT 'rolling_update' CHECK(strategy IN ('rolling_update', 'recreate', 'blue_green', 'canary')),
+  ADD COLUMN max_surge TEXT, -- number of pods or percentage, kubernetes default when missing
+  ADD COLUMN max_unavailable TEXT, -- number of pods or percentage, kubernetes default when missing
+  ADD COLUMN canary_weight INT NOT NULL DEFAULT 10 CHECK(canary_weight >= 0 AND canary_weight <= 100); -- percentage of traffic
+
+-- a star has at most one rollout waiting for promotion or abort
+CREATE TABLE IF NOT EXISTS rollouts (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  strategy TEXT NOT NULL CHECK(strategy IN ('blue_green', 'canary')),
+  previous JSONB NOT NULL, -- star as it was before the rollout, restored on abort
+  started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  star_id UUID NOT NULL UNIQUE,
+  FOREIGN KEY (star_id) REFERENCES stars(id) ON DELETE CASCADE
+);
@@ -0,0 +1,14 @@
+-- resource usage of the stars sampled from the metrics api, kept for a week
+CREATE TABLE IF NOT EXISTS star_metrics (
+  sampled_at TIMESTAMPTZ NOT NULL,
+  replicas INT NOT NULL,
+  cpu_millicores DOUBLE PRECISION NOT NULL,
+  memory_bytes BIGINT NOT NULL,
+  network_rx_rate DOUBLE PRECISION, -- bytes per second, missing on the first sample of a star
+  network_tx_rate DOUBLE PRECISION, -- bytes per second, missing on the first sample of a star
+  star_id UUID NOT NULL,
+  FOREIGN KEY (star_id) REFERENCES stars(id) ON DELETE CASCADE,
+  PRIMARY KEY (star_id, sampled_at)
+);
+
+CREATE INDEX IF NOT EXISTS star_metrics_sampled_at ON star_metrics(sampled_at);
@@ -0,0 +1,5 @@
+-- exceptions to the hardened security defaults of the star pods
+ALTER TABLE stars
+  ADD COLUMN run_as_user INT CHECK(run_as_user > 0), -- image user when missing, root is never allowed
+  ADD COLUMN run_as_group INT CHECK(run_as_group >= 0), -- image group when missing
+  ADD COLUMN read_only_root_filesystem BOOLEAN NOT NULL DEFAULT false;
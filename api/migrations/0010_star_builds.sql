@@ -0,0 +1,12 @@
+-- images built by the platform from the source uploaded for a star
+CREATE TABLE IF NOT EXISTS builds (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  status TEXT NOT NULL DEFAULT 'running' CHECK(status IN ('running', 'succeeded', 'failed')),
+  image TEXT NOT NULL, -- tagged reference pushed to the platform registry
+  digest TEXT, -- digest of the pushed image, missing until the build succeeds
+  started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  finished_at TIMESTAMPTZ,
+  duration INT GENERATED ALWAYS AS (EXTRACT(EPOCH FROM finished_at - started_at)::INT) STORED, -- unit is seconds
+  star_id UUID NOT NULL,
+  FOREIGN KEY (star_id) REFERENCES stars(id) ON DELETE CASCADE
+);
@@ -0,0 +1,19 @@
+-- nebulae pinned to the digest their tag pointed to when they were set
+ALTER TABLE stars
+  ADD COLUMN pin_digest BOOLEAN NOT NULL DEFAULT false,
+  ADD COLUMN nebula_digest TEXT; -- digest the star is deployed with, only when pinned
+
+-- the nebulae are validated from now on and repositories only use lowercase letters,
+-- the names of the existing nebulae are lowercased while their tags and digests are kept
+UPDATE stars
+SET nebula = lower(left(parts.name, length(parts.name) - length(COALESCE(parts.tag, ''))))
+  || COALESCE(parts.tag, '')
+  || COALESCE(parts.digest, '')
+FROM (
+  SELECT id,
+    split_part(nebula, '@', 1) AS name,
+    substring(split_part(nebula, '@', 1) from ':[^/:]*$') AS tag,
+    substring(nebula from '@.*$') AS digest
+  FROM stars
+) AS parts
+WHERE stars.id = parts.id;
@@ -0,0 +1,2 @@
+-- placement profile of the platform selected by the star
+ALTER TABLE stars ADD COLUMN placement TEXT;
@@ -0,0 +1,13 @@
+-- plain variables go to a config map, secret ones stay in the secret and are never read back
+ALTER TABLE variables
+  ADD COLUMN kind TEXT NOT NULL DEFAULT 'secret' CHECK(kind IN ('secret', 'plain'));
+
+-- every read of a secret value through the api
+CREATE TABLE IF NOT EXISTS variable_reveals (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  revealed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  variable_id UUID NOT NULL,
+  user_id UUID NOT NULL,
+  FOREIGN KEY (variable_id) REFERENCES variables(id) ON DELETE CASCADE,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
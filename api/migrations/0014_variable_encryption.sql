@@ -0,0 +1,6 @@
+-- values sealed with their own data key, the data key is sealed with the master key,
+-- rows without a data key were written in clear and are sealed by the api when it starts
+ALTER TABLE variables
+  ALTER COLUMN value TYPE BYTEA USING convert_to(value, 'UTF8'),
+  ADD COLUMN data_key BYTEA,
+  ADD COLUMN key_id TEXT; -- fingerprint of the master key sealing the data key
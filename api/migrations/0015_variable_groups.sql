@@ -0,0 +1,23 @@
+-- variables shared by several stars of a galaxy
+CREATE TABLE IF NOT EXISTS variable_groups (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  name TEXT NOT NULL,
+  galaxy_id UUID NOT NULL,
+  FOREIGN KEY (galaxy_id) REFERENCES galaxies(id) ON DELETE CASCADE,
+  CONSTRAINT variable_group_name_galaxy UNIQUE (name, galaxy_id) -- unique group name inside a galaxy
+);
+
+-- values are sealed like the star variables
+CREATE TABLE IF NOT EXISTS group_variables (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  name TEXT NOT NULL,
+  value BYTEA NOT NULL,
+  data_key BYTEA,
+  key_id TEXT,
+  group_id UUID NOT NULL,
+  FOREIGN KEY (group_id) REFERENCES variable_groups(id) ON DELETE CASCADE,
+  CONSTRAINT group_variable_name_group UNIQUE (name, group_id) -- unique variable name for a group
+);
+
+-- groups attached to the star, the later ones override the earlier ones
+ALTER TABLE stars ADD COLUMN variable_groups UUID[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,16 @@
+-- previous states of a variable, written before each change
+CREATE TABLE IF NOT EXISTS variable_versions (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  name TEXT NOT NULL,
+  value BYTEA NOT NULL, -- sealed with the data key of the version
+  data_key BYTEA,
+  key_id TEXT,
+  kind TEXT NOT NULL CHECK(kind IN ('secret', 'plain')),
+  variable_id UUID, -- the versions of a deleted variable stay in the history of its star
+  star_id UUID NOT NULL,
+  user_id UUID, -- user making the change
+  FOREIGN KEY (variable_id) REFERENCES variables(id) ON DELETE SET NULL,
+  FOREIGN KEY (star_id) REFERENCES stars(id) ON DELETE CASCADE,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
@@ -0,0 +1,17 @@
+-- files mounted into the star containers, contents are sealed like the variables
+CREATE TABLE IF NOT EXISTS config_files (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  mount_path TEXT NOT NULL,
+  mode INT NOT NULL DEFAULT 420 CHECK(mode >= 0 AND mode <= 511), -- 0644
+  kind TEXT NOT NULL DEFAULT 'secret' CHECK(kind IN ('secret', 'plain')),
+  mount_directory BOOLEAN NOT NULL DEFAULT false, -- mounted with its whole directory to update in place
+  contents BYTEA NOT NULL,
+  data_key BYTEA,
+  key_id TEXT,
+  star_id UUID NOT NULL,
+  FOREIGN KEY (star_id) REFERENCES stars(id) ON DELETE CASCADE,
+  CONSTRAINT config_file_path_star UNIQUE (mount_path, star_id) -- one file per path in a star
+);
+
+-- mounts of the config files, rendered in the pod spec of the star
+ALTER TABLE stars ADD COLUMN config_files JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,10 @@
+-- variables reading their value from an external secret store,
+-- the value column keeps the last value read from it
+ALTER TABLE variables
+  ADD COLUMN external_provider TEXT CHECK(external_provider IN ('vault')),
+  ADD COLUMN external_path TEXT,
+  ADD COLUMN external_key TEXT,
+  ADD CONSTRAINT variable_external_reference CHECK(
+    (external_provider IS NULL) = (external_path IS NULL)
+    AND (external_provider IS NULL) = (external_key IS NULL)
+  );
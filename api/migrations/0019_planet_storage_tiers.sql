@@ -0,0 +1,4 @@
+-- the claims of the existing planets were created on local-path, the api moves the planets
+-- to the tier provisioning their claim at startup when the platform defines other tiers
+ALTER TABLE planets ADD COLUMN storage_tier TEXT NOT NULL DEFAULT 'local-path';
+ALTER TABLE planets ALTER COLUMN storage_tier DROP DEFAULT;
@@ -0,0 +1,3 @@
+-- progress of the expansion of the volume after the capacity of the planet grew
+ALTER TABLE planets ADD COLUMN resize_state TEXT NOT NULL DEFAULT 'done'
+  CHECK (resize_state IN ('done', 'resizing', 'file_system_resize_pending'));
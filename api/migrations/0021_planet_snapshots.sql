@@ -0,0 +1,15 @@
+-- volume snapshots of the planets, the planets restored from them are new planets
+CREATE TABLE IF NOT EXISTS planet_snapshots (
+  id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+  name TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'pending' CHECK(status IN ('pending', 'ready', 'failed')),
+  error TEXT, -- reported by the snapshot controller when the snapshot failed
+  capacity INT NOT NULL, -- capacity of the planet when the snapshot was taken, unit is megabytes
+  storage_tier TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  ready_at TIMESTAMPTZ,
+  planet_id UUID, -- the snapshots outlive their planet, they are deleted on their own
+  galaxy_id UUID NOT NULL,
+  FOREIGN KEY (planet_id) REFERENCES planets(id) ON DELETE SET NULL,
+  FOREIGN KEY (galaxy_id) REFERENCES galaxies(id) ON DELETE CASCADE
+);
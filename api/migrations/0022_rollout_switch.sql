@@ -0,0 +1,3 @@
+-- a blue-green rollout first moves the traffic to the preview, the previous version
+-- keeps running in the stable deployment until the promotion is confirmed
+ALTER TABLE rollouts ADD COLUMN switched_at TIMESTAMPTZ;
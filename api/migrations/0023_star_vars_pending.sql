@@ -0,0 +1,2 @@
+-- variable changes staged without a restart wait for an explicit apply of the star
+ALTER TABLE stars ADD COLUMN vars_pending BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+-- reason given by kubernetes when the job of a run failed, e.g. its deadline or its backoff limit
+ALTER TABLE runs ADD COLUMN error TEXT;
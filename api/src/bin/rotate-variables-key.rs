@@ -0,0 +1,124 @@
+//! seal the data keys of every variable, group variable, variable version
+//! and config file with the current master key,
+//! the variables written in clear before the encryption are sealed too.
+//! the rotation is a single transaction, when it fails nothing changed and it is run again.
+//!
+//! the keyring is read like the api does. the api is first restarted with the new key
+//! in VARIABLES_KEY and the old one in PREVIOUS_VARIABLES_KEYS, so it seals with the new key
+//! and still opens the values of the old one, then the rotation runs with the same
+//! configuration and the old key is dropped from PREVIOUS_VARIABLES_KEYS once it succeeded
+
+use api::{
+  database::create_pool,
+  encryption::keyring,
+  models::{
+    file::ConfigFile,
+    group::VariableGroup,
+    var::{Variable, VariableVersion},
+  },
+};
+use dotenv::dotenv;
+use std::env;
+
+#[actix_web::main]
+async fn main() {
+  dotenv().ok();
+
+  let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+
+  let keyring = keyring();
+
+  let pool = create_pool(&database_url, 1)
+    .await
+    .expect("Unable connect to database");
+  let mut tx = pool.begin().await.expect("Unable to start the transaction");
+
+  let vars = Variable::all_sealed(&mut tx)
+    .await
+    .expect("Unable to read the variables");
+
+  let mut rotated = 0;
+
+  // rows already sealed with the current key are left alone, running the rotation twice changes nothing
+  for var in vars.iter().filter(|var| !var.value.is_current(keyring)) {
+    let value = var
+      .value
+      .rewrap(keyring)
+      .unwrap_or_else(|err| panic!("Unable to seal variable {}: {}", var.id, err));
+
+    Variable::set_sealed_value(&mut tx, &var.id, &value)
+      .await
+      .expect("Unable to write the variable");
+
+    rotated += 1;
+  }
+
+  let group_vars = VariableGroup::all_sealed(&mut tx)
+    .await
+    .expect("Unable to read the group variables");
+
+  for var in group_vars
+    .iter()
+    .filter(|var| !var.value.is_current(keyring))
+  {
+    let value = var
+      .value
+      .rewrap(keyring)
+      .unwrap_or_else(|err| panic!("Unable to seal group variable {}: {}", var.id, err));
+
+    VariableGroup::set_sealed_value(&mut tx, &var.id, &value)
+      .await
+      .expect("Unable to write the group variable");
+
+    rotated += 1;
+  }
+
+  let versions = VariableVersion::all_sealed(&mut tx)
+    .await
+    .expect("Unable to read the variable versions");
+
+  for version in versions
+    .iter()
+    .filter(|version| !version.value.is_current(keyring))
+  {
+    let value = version
+      .value
+      .rewrap(keyring)
+      .unwrap_or_else(|err| panic!("Unable to seal variable version {}: {}", version.id, err));
+
+    VariableVersion::set_sealed_value(&mut tx, &version.id, &value)
+      .await
+      .expect("Unable to write the variable version");
+
+    rotated += 1;
+  }
+
+  let files = ConfigFile::all_sealed(&mut tx)
+    .await
+    .expect("Unable to read the config files");
+
+  for file in files
+    .iter()
+    .filter(|file| !file.contents.is_current(keyring))
+  {
+    let contents = file
+      .contents
+      .rewrap(keyring)
+      .unwrap_or_else(|err| panic!("Unable to seal config file {}: {}", file.id, err));
+
+    ConfigFile::set_sealed_contents(&mut tx, &file.id, &contents)
+      .await
+      .expect("Unable to write the config file");
+
+    rotated += 1;
+  }
+
+  tx.commit().await.expect("Unable to commit the rotation");
+
+  println!(
+    "{} of {} variables and files sealed with key {}",
+    rotated,
+    vars.len() + group_vars.len() + versions.len() + files.len(),
+    keyring.current().id()
+  );
+}
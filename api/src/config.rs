@@ -0,0 +1,77 @@
+use std::{env, sync::OnceLock};
+
+const DEFAULT_ACTIVATOR_IMAGE: &str = "gws-activator:latest";
+const DEFAULT_BUILDER_IMAGE: &str = "gcr.io/kaniko-project/executor:v1.23.2";
+const DEFAULT_REGISTRY_HOST: &str = "k3d-gws-registry:5000";
+const DEFAULT_VAULT_MOUNT: &str = "secret";
+const DEFAULT_EXTERNAL_SECRETS_INTERVAL: u64 = 60;
+
+#[derive(Debug)]
+pub struct Config {
+  /// image of the activator deployed in the galaxies with idle stars
+  pub activator_image: String,
+  /// kaniko executor building the images of the stars from their source
+  pub builder_image: String,
+  /// in-cluster registry receiving the images built by the platform
+  pub registry_host: String,
+  /// the registry is reached over plain http
+  pub registry_insecure: bool,
+  /// json file with the placement profiles the stars can select
+  pub placement_profiles: Option<String>,
+  /// json file with the storage tiers the planets can select
+  pub storage_tiers: Option<String>,
+  /// base64 master key sealing the data keys of the variables
+  pub variables_key: Option<String>,
+  /// file holding the master key, used when the key is not given directly
+  pub variables_key_file: Option<String>,
+  /// base64 master keys replaced by the current one, they only open the values they sealed
+  pub previous_variables_keys: Option<String>,
+  /// file holding the previous master keys, used when they are not given directly
+  pub previous_variables_keys_file: Option<String>,
+  /// address of the vault compatible store holding the external variables
+  pub vault_addr: Option<String>,
+  /// token reading the external variables from the store
+  pub vault_token: Option<String>,
+  /// file holding the vault token, used when the token is not given directly
+  pub vault_token_file: Option<String>,
+  /// mount of the kv v2 secrets engine
+  pub vault_mount: String,
+  /// seconds between two reads of the external variables
+  pub external_secrets_interval: u64,
+}
+
+impl Config {
+  fn from_env() -> Self {
+    Self {
+      activator_image: env::var("ACTIVATOR_IMAGE")
+        .unwrap_or_else(|_| DEFAULT_ACTIVATOR_IMAGE.to_string()),
+      builder_image: env::var("BUILDER_IMAGE")
+        .unwrap_or_else(|_| DEFAULT_BUILDER_IMAGE.to_string()),
+      registry_host: env::var("REGISTRY_HOST")
+        .unwrap_or_else(|_| DEFAULT_REGISTRY_HOST.to_string()),
+      registry_insecure: env::var("REGISTRY_INSECURE")
+        .map(|insecure| insecure == "true")
+        .unwrap_or(true),
+      placement_profiles: env::var("PLACEMENT_PROFILES").ok(),
+      storage_tiers: env::var("STORAGE_TIERS").ok(),
+      variables_key: env::var("VARIABLES_KEY").ok(),
+      variables_key_file: env::var("VARIABLES_KEY_FILE").ok(),
+      previous_variables_keys: env::var("PREVIOUS_VARIABLES_KEYS").ok(),
+      previous_variables_keys_file: env::var("PREVIOUS_VARIABLES_KEYS_FILE").ok(),
+      vault_addr: env::var("VAULT_ADDR").ok(),
+      vault_token: env::var("VAULT_TOKEN").ok(),
+      vault_token_file: env::var("VAULT_TOKEN_FILE").ok(),
+      vault_mount: env::var("VAULT_MOUNT").unwrap_or_else(|_| DEFAULT_VAULT_MOUNT.to_string()),
+      external_secrets_interval: env::var("EXTERNAL_SECRETS_INTERVAL")
+        .ok()
+        .and_then(|interval| interval.parse().ok())
+        .unwrap_or(DEFAULT_EXTERNAL_SECRETS_INTERVAL),
+    }
+  }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+pub fn config() -> &'static Config {
+  CONFIG.get_or_init(Config::from_env)
+}
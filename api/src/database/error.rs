@@ -1,4 +1,4 @@
-use crate::auth;
+use crate::{auth, encryption};
 
 #[derive(Debug)]
 pub enum Operation {
@@ -16,6 +16,7 @@ pub enum DbError {
   OperationNotImplemented(Operation),
   Internal(sqlx::Error),
   Auth(auth::AuthError),
+  Encryption(encryption::EncryptionError),
 }
 
 impl From<sqlx::Error> for DbError {
@@ -32,6 +33,12 @@ impl From<sqlx::Error> for DbError {
   }
 }
 
+impl From<encryption::EncryptionError> for DbError {
+  fn from(value: encryption::EncryptionError) -> Self {
+    DbError::Encryption(value)
+  }
+}
+
 impl From<auth::AuthError> for DbError {
   fn from(value: auth::AuthError) -> Self {
     DbError::Auth(value)
@@ -0,0 +1,349 @@
+//! envelope encryption of the values stored in the database,
+//! each value has its own data key sealed with the master key of the platform.
+//! the previous master keys stay in the keyring to open the values they sealed,
+//! so the api keeps reading them while the rotation seals them with the current key
+
+use std::{fs, sync::OnceLock};
+
+use aes_gcm::{
+  aead::{Aead, AeadCore, KeyInit, OsRng},
+  Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use derive_more::{Display, Error};
+use sha2::{Digest, Sha256};
+
+use crate::config::config;
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Debug, Display, Error)]
+pub enum EncryptionError {
+  #[display(fmt = "The master key must be {} base64 encoded bytes", KEY_LENGTH)]
+  InvalidKey,
+  #[display(fmt = "Unable to read the master key file")]
+  KeyFile,
+  #[display(fmt = "The value was sealed with a master key missing from the keyring")]
+  UnknownKey,
+  #[display(fmt = "Unable to seal the value")]
+  Seal,
+  #[display(fmt = "Unable to open the value, it is corrupted")]
+  Open,
+}
+
+pub type EncryptionResult<T> = Result<T, EncryptionError>;
+
+pub struct MasterKey {
+  /// fingerprint stored next to the values to know which key sealed them
+  id: String,
+  cipher: Aes256Gcm,
+}
+
+impl MasterKey {
+  pub fn parse(encoded: &str) -> EncryptionResult<Self> {
+    let key = STANDARD
+      .decode(encoded.trim())
+      .map_err(|_| EncryptionError::InvalidKey)?;
+
+    if key.len() != KEY_LENGTH {
+      return Err(EncryptionError::InvalidKey);
+    }
+
+    Ok(Self {
+      id: hex::encode(&Sha256::digest(&key)[..8]),
+      cipher: Aes256Gcm::new_from_slice(&key).map_err(|_| EncryptionError::InvalidKey)?,
+    })
+  }
+
+  /// key of the environment variable, or read from the file the other variable points to
+  pub fn from_env(key: Option<&str>, key_file: Option<&str>) -> EncryptionResult<Self> {
+    match (key, key_file) {
+      (Some(key), _) => Self::parse(key),
+      (None, Some(path)) => {
+        Self::parse(&fs::read_to_string(path).map_err(|_| EncryptionError::KeyFile)?)
+      }
+      (None, None) => Err(EncryptionError::InvalidKey),
+    }
+  }
+
+  pub fn id(&self) -> &str {
+    &self.id
+  }
+}
+
+/// current master key sealing the values and previous ones only opening them
+pub struct Keyring {
+  current: MasterKey,
+  previous: Vec<MasterKey>,
+}
+
+impl Keyring {
+  pub fn new(current: MasterKey, previous: Vec<MasterKey>) -> Self {
+    Self { current, previous }
+  }
+
+  /// previous keys are separated by commas or new lines, in the variable or in the file
+  pub fn from_env(
+    key: Option<&str>,
+    key_file: Option<&str>,
+    previous_keys: Option<&str>,
+    previous_keys_file: Option<&str>,
+  ) -> EncryptionResult<Self> {
+    let previous_keys = match (previous_keys, previous_keys_file) {
+      (Some(keys), _) => keys.to_string(),
+      (None, Some(path)) => fs::read_to_string(path).map_err(|_| EncryptionError::KeyFile)?,
+      (None, None) => String::new(),
+    };
+
+    let previous = previous_keys
+      .split([',', '\n'])
+      .filter(|key| !key.trim().is_empty())
+      .map(MasterKey::parse)
+      .collect::<EncryptionResult<Vec<_>>>()?;
+
+    Ok(Self::new(MasterKey::from_env(key, key_file)?, previous))
+  }
+
+  pub fn current(&self) -> &MasterKey {
+    &self.current
+  }
+
+  fn find(&self, key_id: Option<&str>) -> EncryptionResult<&MasterKey> {
+    [&self.current]
+      .into_iter()
+      .chain(&self.previous)
+      .find(|key| Some(key.id()) == key_id)
+      .ok_or(EncryptionError::UnknownKey)
+  }
+}
+
+/// nonce followed by the ciphertext
+fn encrypt(cipher: &Aes256Gcm, plaintext: &[u8]) -> EncryptionResult<Vec<u8>> {
+  let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+  let ciphertext = cipher
+    .encrypt(&nonce, plaintext)
+    .map_err(|_| EncryptionError::Seal)?;
+
+  Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+fn decrypt(cipher: &Aes256Gcm, sealed: &[u8]) -> EncryptionResult<Vec<u8>> {
+  if sealed.len() < NONCE_LENGTH {
+    return Err(EncryptionError::Open);
+  }
+
+  let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+
+  cipher
+    .decrypt(Nonce::from_slice(nonce), ciphertext)
+    .map_err(|_| EncryptionError::Open)
+}
+
+/// value encrypted with its own data key, the data key is encrypted with the master key,
+/// values written before the encryption have no data key and are still in clear
+#[derive(Debug, Clone)]
+pub struct SealedValue {
+  pub ciphertext: Vec<u8>,
+  pub data_key: Option<Vec<u8>>,
+  pub key_id: Option<String>,
+}
+
+impl SealedValue {
+  pub fn seal_with(value: &str, master_key: &MasterKey) -> EncryptionResult<Self> {
+    let data_key = Aes256Gcm::generate_key(&mut OsRng);
+    let cipher = Aes256Gcm::new(&data_key);
+
+    Ok(Self {
+      ciphertext: encrypt(&cipher, value.as_bytes())?,
+      data_key: Some(encrypt(&master_key.cipher, &data_key)?),
+      key_id: Some(master_key.id.clone()),
+    })
+  }
+
+  pub fn seal(value: &str) -> EncryptionResult<Self> {
+    Self::seal_with(value, keyring().current())
+  }
+
+  fn data_cipher(&self, keyring: &Keyring) -> EncryptionResult<Option<Aes256Gcm>> {
+    let Some(data_key) = &self.data_key else {
+      return Ok(None);
+    };
+
+    let master_key = keyring.find(self.key_id.as_deref())?;
+    let data_key = decrypt(&master_key.cipher, data_key)?;
+
+    Ok(Some(
+      Aes256Gcm::new_from_slice(&data_key).map_err(|_| EncryptionError::Open)?,
+    ))
+  }
+
+  pub fn open_with(&self, keyring: &Keyring) -> EncryptionResult<String> {
+    let plaintext = match self.data_cipher(keyring)? {
+      Some(cipher) => decrypt(&cipher, &self.ciphertext)?,
+      None => self.ciphertext.clone(),
+    };
+
+    String::from_utf8(plaintext).map_err(|_| EncryptionError::Open)
+  }
+
+  pub fn open(&self) -> EncryptionResult<String> {
+    self.open_with(keyring())
+  }
+
+  /// the value is sealed with the current key of the keyring
+  pub fn is_current(&self, keyring: &Keyring) -> bool {
+    self.key_id.as_deref() == Some(keyring.current().id())
+  }
+
+  /// same value with its data key sealed by the current master key of the keyring,
+  /// values still in clear are sealed with a new data key
+  pub fn rewrap(&self, keyring: &Keyring) -> EncryptionResult<Self> {
+    let Some(data_key) = &self.data_key else {
+      return Self::seal_with(&self.open_with(keyring)?, keyring.current());
+    };
+
+    let master_key = keyring.find(self.key_id.as_deref())?;
+    let data_key = decrypt(&master_key.cipher, data_key)?;
+
+    Ok(Self {
+      ciphertext: self.ciphertext.clone(),
+      data_key: Some(encrypt(&keyring.current().cipher, &data_key)?),
+      key_id: Some(keyring.current().id.clone()),
+    })
+  }
+}
+
+static KEYRING: OnceLock<Keyring> = OnceLock::new();
+
+/// master keys of the platform configuration
+pub fn keyring() -> &'static Keyring {
+  KEYRING.get_or_init(|| {
+    Keyring::from_env(
+      config().variables_key.as_deref(),
+      config().variables_key_file.as_deref(),
+      config().previous_variables_keys.as_deref(),
+      config().previous_variables_keys_file.as_deref(),
+    )
+    .expect(
+      "VARIABLES_KEY or VARIABLES_KEY_FILE and PREVIOUS_VARIABLES_KEYS must be valid master keys",
+    )
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const OLD_KEY: &str = "15pFPnkNPufP6U+/DiPD7YNCexjKgtPV9IURyZfgIiU=";
+  const NEW_KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+
+  fn key(encoded: &str) -> MasterKey {
+    MasterKey::parse(encoded).unwrap()
+  }
+
+  #[test]
+  fn opens_a_sealed_value() {
+    let keyring = Keyring::new(key(OLD_KEY), Vec::new());
+    let sealed = SealedValue::seal_with("hunter2", keyring.current()).unwrap();
+
+    assert_ne!(sealed.ciphertext, b"hunter2");
+    assert_eq!(sealed.key_id.as_deref(), Some(keyring.current().id()));
+    assert_eq!(sealed.open_with(&keyring).unwrap(), "hunter2");
+  }
+
+  #[test]
+  fn opens_a_value_in_clear() {
+    let keyring = Keyring::new(key(OLD_KEY), Vec::new());
+    let clear = SealedValue {
+      ciphertext: b"hunter2".to_vec(),
+      data_key: None,
+      key_id: None,
+    };
+
+    assert_eq!(clear.open_with(&keyring).unwrap(), "hunter2");
+  }
+
+  #[test]
+  fn rejects_a_value_of_an_unknown_key() {
+    let sealed = SealedValue::seal_with("hunter2", &key(OLD_KEY)).unwrap();
+    let keyring = Keyring::new(key(NEW_KEY), Vec::new());
+
+    assert!(matches!(
+      sealed.open_with(&keyring),
+      Err(EncryptionError::UnknownKey)
+    ));
+    assert!(matches!(
+      sealed.rewrap(&keyring),
+      Err(EncryptionError::UnknownKey)
+    ));
+  }
+
+  #[test]
+  fn rejects_a_key_id_not_matching_the_data_key() {
+    let keyring = Keyring::new(key(NEW_KEY), vec![key(OLD_KEY)]);
+    let mut sealed = SealedValue::seal_with("hunter2", &key(OLD_KEY)).unwrap();
+    sealed.key_id = Some(keyring.current().id().to_string());
+
+    assert!(matches!(
+      sealed.open_with(&keyring),
+      Err(EncryptionError::Open)
+    ));
+  }
+
+  #[test]
+  fn opens_a_value_of_a_previous_key() {
+    let sealed = SealedValue::seal_with("hunter2", &key(OLD_KEY)).unwrap();
+    let keyring = Keyring::new(key(NEW_KEY), vec![key(OLD_KEY)]);
+
+    assert!(!sealed.is_current(&keyring));
+    assert_eq!(sealed.open_with(&keyring).unwrap(), "hunter2");
+  }
+
+  #[test]
+  fn rewraps_with_the_current_key() {
+    let sealed = SealedValue::seal_with("hunter2", &key(OLD_KEY)).unwrap();
+    let keyring = Keyring::new(key(NEW_KEY), vec![key(OLD_KEY)]);
+
+    let rewrapped = sealed.rewrap(&keyring).unwrap();
+
+    assert!(rewrapped.is_current(&keyring));
+    assert_eq!(rewrapped.ciphertext, sealed.ciphertext);
+
+    // the old key is no longer needed once the rotation is done
+    let keyring = Keyring::new(key(NEW_KEY), Vec::new());
+    assert_eq!(rewrapped.open_with(&keyring).unwrap(), "hunter2");
+  }
+
+  #[test]
+  fn rewrap_seals_a_value_in_clear() {
+    let keyring = Keyring::new(key(NEW_KEY), Vec::new());
+    let clear = SealedValue {
+      ciphertext: b"hunter2".to_vec(),
+      data_key: None,
+      key_id: None,
+    };
+
+    let sealed = clear.rewrap(&keyring).unwrap();
+
+    assert!(sealed.is_current(&keyring));
+    assert_eq!(sealed.open_with(&keyring).unwrap(), "hunter2");
+  }
+
+  #[test]
+  fn reads_the_previous_keys() {
+    let keyring = Keyring::from_env(
+      Some(NEW_KEY),
+      None,
+      Some(&format!("{},\n{}\n", OLD_KEY, NEW_KEY)),
+      None,
+    )
+    .unwrap();
+
+    assert_eq!(keyring.previous.len(), 2);
+    assert!(matches!(
+      Keyring::from_env(Some(NEW_KEY), None, Some("not a key"), None),
+      Err(EncryptionError::InvalidKey)
+    ));
+  }
+}
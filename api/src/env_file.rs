@@ -0,0 +1,114 @@
+//! dotenv files used to import and export the variables of a star
+
+use derive_more::Display;
+
+#[derive(Debug, Display)]
+#[display(fmt = "line {}: {}", line, message)]
+pub struct EnvFileError {
+  pub line: usize,
+  pub message: &'static str,
+}
+
+pub fn valid_name(name: &str) -> bool {
+  !name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c == '=' || c == '#')
+}
+
+/// value between double quotes, with `\n`, `\"` and `\\` escapes
+fn unescape(quoted: &str) -> Option<String> {
+  let mut value = String::new();
+  let mut chars = quoted.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '"' => {
+        // only a comment can follow the closing quote
+        let rest = chars.as_str().trim();
+        return (rest.is_empty() || rest.starts_with('#')).then_some(value);
+      }
+      '\\' => match chars.next()? {
+        'n' => value.push('\n'),
+        'r' => value.push('\r'),
+        't' => value.push('\t'),
+        other => value.push(other),
+      },
+      c => value.push(c),
+    }
+  }
+
+  None
+}
+
+fn parse_value(raw: &str) -> Option<String> {
+  let raw = raw.trim();
+
+  if let Some(quoted) = raw.strip_prefix('"') {
+    return unescape(quoted);
+  }
+
+  if let Some(quoted) = raw.strip_prefix('\'') {
+    let (value, rest) = quoted.split_once('\'')?;
+    let rest = rest.trim();
+
+    return (rest.is_empty() || rest.starts_with('#')).then(|| value.to_string());
+  }
+
+  // unquoted values end at the first comment
+  let value = match raw.split_once(" #") {
+    Some((value, _)) => value,
+    None => raw,
+  };
+
+  Some(value.trim_end().to_string())
+}
+
+/// variables of a dotenv file in file order, comments and blank lines are skipped
+pub fn parse(content: &str) -> Result<Vec<(String, String)>, EnvFileError> {
+  let mut vars = Vec::new();
+
+  for (index, line) in content.lines().enumerate() {
+    let line = line.trim();
+
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let error = |message| EnvFileError {
+      line: index + 1,
+      message,
+    };
+
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (name, value) = line.split_once('=').ok_or(error("missing `=`"))?;
+    let name = name.trim();
+
+    if !valid_name(name) {
+      return Err(error("invalid variable name"));
+    }
+
+    let value = parse_value(value).ok_or(error("unterminated quoted value"))?;
+
+    if value.is_empty() {
+      return Err(error("variables cannot be empty"));
+    }
+
+    vars.push((name.to_string(), value));
+  }
+
+  Ok(vars)
+}
+
+/// dotenv file with every value double quoted
+pub fn render<'a>(vars: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+  vars
+    .into_iter()
+    .map(|(name, value)| {
+      let value = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+
+      format!("{}=\"{}\"\n", name, value)
+    })
+    .collect()
+}
@@ -4,10 +4,11 @@ use actix_web::{
 };
 use derive_more::{Display, Error, From};
 use serde::Serialize;
+use std::borrow::Cow;
 use utoipa::{ToResponse, ToSchema};
-use validator::ValidationErrors;
+use validator::{ValidationError, ValidationErrors};
 
-use crate::{auth, database};
+use crate::{auth, database, interpolation::InterpolationError, registry, secrets};
 
 pub type ApiResult<T, E = ApiError> = std::result::Result<T, E>;
 
@@ -15,6 +16,8 @@ pub type ApiResult<T, E = ApiError> = std::result::Result<T, E>;
 pub enum ApiError {
   #[display(fmt = "User not authorized")]
   Unauthorize,
+  #[display(fmt = "User not allowed to access the resource")]
+  Forbidden,
   #[display(fmt = "Requested resources not found")]
   NotFound,
   #[display(fmt = "The resource already exists")]
@@ -57,6 +60,72 @@ impl From<kube::Error> for ApiError {
   }
 }
 
+impl From<registry::RegistryError> for ApiError {
+  fn from(value: registry::RegistryError) -> Self {
+    // a registry refusing deletions is told to the user instead of failing silently
+    if let registry::RegistryError::DeleteDisabled = value {
+      let mut errors = ValidationErrors::new();
+      errors.add(
+        "registry",
+        ValidationError::new("registry").with_message(Cow::from(value.to_string())),
+      );
+
+      return ApiError::Validation(errors);
+    }
+
+    ApiError::InternalError
+  }
+}
+
+impl From<registry::ArchiveError> for ApiError {
+  fn from(value: registry::ArchiveError) -> Self {
+    let mut errors = ValidationErrors::new();
+    errors.add(
+      "archive",
+      ValidationError::new("archive").with_message(Cow::from(value.to_string())),
+    );
+
+    ApiError::Validation(errors)
+  }
+}
+
+impl From<InterpolationError> for ApiError {
+  fn from(value: InterpolationError) -> Self {
+    if let InterpolationError::Encryption(_) = value {
+      return ApiError::InternalError;
+    }
+
+    let mut errors = ValidationErrors::new();
+    errors.add(
+      "value",
+      ValidationError::new("reference").with_message(Cow::from(value.to_string())),
+    );
+
+    ApiError::Validation(errors)
+  }
+}
+
+impl From<secrets::SecretStoreError> for ApiError {
+  fn from(value: secrets::SecretStoreError) -> Self {
+    // only a reference the store cannot answer is the fault of the user
+    match value {
+      secrets::SecretStoreError::Http(_) => return ApiError::InternalError,
+      secrets::SecretStoreError::Status(status, _) if status != StatusCode::NOT_FOUND => {
+        return ApiError::InternalError
+      }
+      _ => {}
+    }
+
+    let mut errors = ValidationErrors::new();
+    errors.add(
+      "external",
+      ValidationError::new("external").with_message(Cow::from(value.to_string())),
+    );
+
+    ApiError::Validation(errors)
+  }
+}
+
 impl ResponseError for ApiError {
   fn error_response(&self) -> HttpResponse {
     let status_code = self.status_code();
@@ -72,6 +141,7 @@ impl ResponseError for ApiError {
   fn status_code(&self) -> actix_web::http::StatusCode {
     match self {
       ApiError::Unauthorize => StatusCode::UNAUTHORIZED,
+      ApiError::Forbidden => StatusCode::FORBIDDEN,
       ApiError::NotFound => StatusCode::NOT_FOUND,
       ApiError::AlreadyExists => StatusCode::CONFLICT,
       ApiError::Validation(_) => StatusCode::BAD_REQUEST,
@@ -91,6 +161,14 @@ pub struct ErrorMessage {
 #[response(description = "User not authorized", content_type = "application/json")]
 pub struct UnauthorizeResponse(ErrorMessage);
 
+#[allow(dead_code)]
+#[derive(ToResponse)]
+#[response(
+  description = "User not allowed to access the resource",
+  content_type = "application/json"
+)]
+pub struct ForbiddenResponse(ErrorMessage);
+
 #[allow(dead_code)]
 #[derive(ToResponse)]
 #[response(
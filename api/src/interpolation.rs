@@ -0,0 +1,596 @@
+//! references between the variables and the stars of a galaxy,
+//! `${{ db.PRIVATE_DOMAIN }}`, `${{ db.PORT }}` or `${{ db.vars.POSTGRES_PASSWORD }}`
+//! are replaced when the variables of a star are rendered
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use derive_more::Display;
+use uuid::Uuid;
+
+use crate::{
+  encryption::EncryptionError,
+  k8s::{private_host, public_host},
+  models::{
+    star::Star,
+    var::{Variable, VariableKind},
+  },
+};
+
+const OPENING: &str = "${{";
+const CLOSING: &str = "}}";
+
+#[derive(Debug, Display)]
+pub enum InterpolationError {
+  #[display(fmt = "unterminated reference in {}", _0)]
+  Unterminated(String),
+  #[display(fmt = "invalid reference `{}`", _0)]
+  Invalid(String),
+  #[display(fmt = "unknown star `{}`", _0)]
+  UnknownStar(String),
+  #[display(fmt = "star `{}` has no {}", _0, _1)]
+  MissingField(String, &'static str),
+  #[display(fmt = "unknown variable `{}` of star `{}`", _1, _0)]
+  UnknownVariable(String, String),
+  #[display(fmt = "reference cycle {}", _0)]
+  Cycle(String),
+  #[display(fmt = "{}", _0)]
+  Encryption(EncryptionError),
+}
+
+impl From<EncryptionError> for InterpolationError {
+  fn from(value: EncryptionError) -> Self {
+    InterpolationError::Encryption(value)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceTarget {
+  PrivateDomain,
+  PublicDomain,
+  Port,
+  Var(String),
+}
+
+/// star name and what is read from it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+  pub star: String,
+  pub target: ReferenceTarget,
+}
+
+impl Reference {
+  fn parse(inner: &str) -> Result<Self, InterpolationError> {
+    let invalid = || InterpolationError::Invalid(inner.to_string());
+    let (star, path) = inner.split_once('.').ok_or_else(invalid)?;
+
+    let target = match path {
+      "PRIVATE_DOMAIN" => ReferenceTarget::PrivateDomain,
+      "PUBLIC_DOMAIN" => ReferenceTarget::PublicDomain,
+      "PORT" => ReferenceTarget::Port,
+      path => match path.strip_prefix("vars.") {
+        Some(name) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+          ReferenceTarget::Var(name.to_string())
+        }
+        _ => return Err(invalid()),
+      },
+    };
+
+    if star.is_empty() || star.contains(char::is_whitespace) {
+      return Err(invalid());
+    }
+
+    Ok(Self {
+      star: star.to_string(),
+      target,
+    })
+  }
+}
+
+enum Segment<'a> {
+  Text(&'a str),
+  Reference(Reference),
+}
+
+fn segments(value: &str) -> Result<Vec<Segment<'_>>, InterpolationError> {
+  let mut segments = Vec::new();
+  let mut rest = value;
+
+  while let Some(start) = rest.find(OPENING) {
+    segments.push(Segment::Text(&rest[..start]));
+
+    let after = &rest[start + OPENING.len()..];
+    let end = after
+      .find(CLOSING)
+      .ok_or_else(|| InterpolationError::Unterminated(value.to_string()))?;
+
+    segments.push(Segment::Reference(Reference::parse(after[..end].trim())?));
+    rest = &after[end + CLOSING.len()..];
+  }
+
+  segments.push(Segment::Text(rest));
+
+  Ok(segments)
+}
+
+/// references of a value in order
+pub fn references(value: &str) -> Result<Vec<Reference>, InterpolationError> {
+  Ok(
+    segments(value)?
+      .into_iter()
+      .filter_map(|segment| match segment {
+        Segment::Reference(reference) => Some(reference),
+        Segment::Text(_) => None,
+      })
+      .collect(),
+  )
+}
+
+/// variable with its references replaced, ready to be written in the secret or the config map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedVariable {
+  pub name: String,
+  pub value: String,
+  pub kind: VariableKind,
+}
+
+struct StarNode {
+  id: Uuid,
+  private_domain: Option<String>,
+  public_domain: Option<String>,
+  port: i32,
+}
+
+struct VariableNode {
+  kind: VariableKind,
+  value: String,
+}
+
+/// stars and opened variables of a galaxy
+pub struct VariableGraph {
+  stars: HashMap<String, StarNode>,
+  names: HashMap<Uuid, String>,
+  vars: HashMap<Uuid, BTreeMap<String, VariableNode>>,
+}
+
+impl VariableGraph {
+  pub fn new(stars: &[Star], vars: &[Variable]) -> Result<Self, InterpolationError> {
+    let mut graph = Self {
+      stars: HashMap::new(),
+      names: HashMap::new(),
+      vars: HashMap::new(),
+    };
+
+    for star in stars {
+      graph.names.insert(star.id, star.name.clone());
+      graph.vars.insert(star.id, BTreeMap::new());
+      graph.stars.insert(
+        star.name.clone(),
+        StarNode {
+          id: star.id,
+          private_domain: star.private_domain.clone(),
+          public_domain: star.public_domain.clone(),
+          port: star.port,
+        },
+      );
+    }
+
+    for var in vars {
+      let node = VariableNode {
+        kind: var.kind,
+        value: var.value.open()?,
+      };
+
+      graph
+        .vars
+        .entry(var.star_id)
+        .or_default()
+        .insert(var.name.clone(), node);
+    }
+
+    Ok(graph)
+  }
+
+  fn star(&self, name: &str) -> Result<&StarNode, InterpolationError> {
+    self
+      .stars
+      .get(name)
+      .ok_or_else(|| InterpolationError::UnknownStar(name.to_string()))
+  }
+
+  /// value with its references replaced and whether it reads a secret,
+  /// `stack` holds the variables being rendered
+  fn render_value(
+    &self,
+    value: &str,
+    stack: &mut Vec<(Uuid, String)>,
+  ) -> Result<(String, bool), InterpolationError> {
+    let mut rendered = String::new();
+    let mut reads_secret = false;
+
+    for segment in segments(value)? {
+      let reference = match segment {
+        Segment::Text(text) => {
+          rendered.push_str(text);
+          continue;
+        }
+        Segment::Reference(reference) => reference,
+      };
+
+      let star = self.star(&reference.star)?;
+
+      match &reference.target {
+        // the hosts the cluster answers for, not the bare domains of the star
+        ReferenceTarget::PrivateDomain => {
+          rendered.push_str(&private_host(star.private_domain.as_deref().ok_or(
+            InterpolationError::MissingField(reference.star, "private domain"),
+          )?))
+        }
+        ReferenceTarget::PublicDomain => {
+          rendered.push_str(&public_host(star.public_domain.as_deref().ok_or(
+            InterpolationError::MissingField(reference.star, "public domain"),
+          )?))
+        }
+        ReferenceTarget::Port => rendered.push_str(&star.port.to_string()),
+        ReferenceTarget::Var(name) => {
+          let (value, secret) = self.render_var(star.id, name, &reference.star, stack)?;
+          rendered.push_str(&value);
+          reads_secret |= secret;
+        }
+      }
+    }
+
+    Ok((rendered, reads_secret))
+  }
+
+  fn render_var(
+    &self,
+    star_id: Uuid,
+    name: &str,
+    star_name: &str,
+    stack: &mut Vec<(Uuid, String)>,
+  ) -> Result<(String, bool), InterpolationError> {
+    let node = self
+      .vars
+      .get(&star_id)
+      .and_then(|vars| vars.get(name))
+      .ok_or_else(|| {
+        InterpolationError::UnknownVariable(star_name.to_string(), name.to_string())
+      })?;
+
+    if let Some(start) = stack
+      .iter()
+      .position(|(id, var)| *id == star_id && var == name)
+    {
+      let cycle = stack[start..]
+        .iter()
+        .chain([&(star_id, name.to_string())])
+        .map(|(id, var)| format!("{}.vars.{}", self.names[id], var))
+        .collect::<Vec<_>>();
+
+      return Err(InterpolationError::Cycle(cycle.join(" -> ")));
+    }
+
+    stack.push((star_id, name.to_string()));
+    let (value, reads_secret) = self.render_value(&node.value, stack)?;
+    stack.pop();
+
+    Ok((value, reads_secret || node.kind == VariableKind::Secret))
+  }
+
+  /// all the variables of a star with their references replaced,
+  /// a variable reading a secret is a secret too so its value never lands in the config map
+  pub fn render(&self, star_id: &Uuid) -> Result<Vec<RenderedVariable>, InterpolationError> {
+    let Some(vars) = self.vars.get(star_id) else {
+      return Ok(Vec::new());
+    };
+    let star_name = self.names.get(star_id).cloned().unwrap_or_default();
+
+    vars
+      .keys()
+      .map(|name| {
+        let (value, secret) = self.render_var(*star_id, name, &star_name, &mut Vec::new())?;
+
+        Ok(RenderedVariable {
+          name: name.clone(),
+          value,
+          kind: if secret {
+            VariableKind::Secret
+          } else {
+            VariableKind::Plain
+          },
+        })
+      })
+      .collect()
+  }
+
+  /// the references of the stars resolve and none of them loops
+  pub fn check(&self, star_ids: &[Uuid]) -> Result<(), InterpolationError> {
+    for star_id in star_ids {
+      self.render(star_id)?;
+    }
+
+    Ok(())
+  }
+
+  /// stars reading a value of the star with this name, directly or through other stars,
+  /// the name may be one the galaxy no longer has
+  pub fn readers(&self, star_name: &str) -> Vec<Uuid> {
+    // names of the stars each star references
+    let mut dependencies: HashMap<Uuid, HashSet<String>> = HashMap::new();
+
+    for (id, vars) in &self.vars {
+      for node in vars.values() {
+        for reference in references(&node.value).unwrap_or_default() {
+          dependencies.entry(*id).or_default().insert(reference.star);
+        }
+      }
+    }
+
+    let mut found = HashSet::new();
+    let mut pending = vec![star_name.to_string()];
+
+    while let Some(current) = pending.pop() {
+      for (id, stars) in &dependencies {
+        if stars.contains(&current) && found.insert(*id) {
+          if let Some(name) = self.names.get(id) {
+            pending.push(name.clone());
+          }
+        }
+      }
+    }
+
+    found.into_iter().collect()
+  }
+
+  /// stars reading a value of the star, directly or through other stars
+  pub fn dependents(&self, star_id: &Uuid) -> Vec<Uuid> {
+    let Some(name) = self.names.get(star_id) else {
+      return Vec::new();
+    };
+
+    self
+      .readers(name)
+      .into_iter()
+      .filter(|id| id != star_id)
+      .collect()
+  }
+
+  /// the star and the stars reading it, the ones an edit of the star renders again
+  pub fn affected(&self, star_id: &Uuid) -> Vec<Uuid> {
+    [*star_id]
+      .into_iter()
+      .chain(self.dependents(star_id))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn graph(stars: &[(&str, Option<&str>, Option<&str>, i32)]) -> VariableGraph {
+    let mut graph = VariableGraph {
+      stars: HashMap::new(),
+      names: HashMap::new(),
+      vars: HashMap::new(),
+    };
+
+    for (index, (name, private_domain, public_domain, port)) in stars.iter().enumerate() {
+      let id = Uuid::from_u128(index as u128 + 1);
+      graph.names.insert(id, name.to_string());
+      graph.vars.insert(id, BTreeMap::new());
+      graph.stars.insert(
+        name.to_string(),
+        StarNode {
+          id,
+          private_domain: private_domain.map(str::to_string),
+          public_domain: public_domain.map(str::to_string),
+          port: *port,
+        },
+      );
+    }
+
+    graph
+  }
+
+  fn set_var(graph: &mut VariableGraph, star: &str, name: &str, value: &str, kind: VariableKind) {
+    let id = graph.stars[star].id;
+    graph.vars.get_mut(&id).unwrap().insert(
+      name.to_string(),
+      VariableNode {
+        kind,
+        value: value.to_string(),
+      },
+    );
+  }
+
+  fn rendered(graph: &VariableGraph, star: &str) -> Vec<RenderedVariable> {
+    graph.render(&graph.stars[star].id).unwrap()
+  }
+
+  #[test]
+  fn renders_the_hosts_and_the_port_of_a_star() {
+    let mut graph = graph(&[
+      ("db", Some("db"), Some("admin"), 5432),
+      ("web", None, None, 80),
+    ]);
+    set_var(
+      &mut graph,
+      "web",
+      "DATABASE_URL",
+      "${{ db.PRIVATE_DOMAIN }}:${{db.PORT}}",
+      VariableKind::Plain,
+    );
+    set_var(
+      &mut graph,
+      "web",
+      "ADMIN_URL",
+      "https://${{ db.PUBLIC_DOMAIN }}/",
+      VariableKind::Plain,
+    );
+
+    assert_eq!(
+      rendered(&graph, "web"),
+      vec![
+        RenderedVariable {
+          name: "ADMIN_URL".to_string(),
+          value: format!("https://{}/", public_host("admin")),
+          kind: VariableKind::Plain,
+        },
+        RenderedVariable {
+          name: "DATABASE_URL".to_string(),
+          value: format!("{}:5432", private_host("db")),
+          kind: VariableKind::Plain,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn a_variable_reading_a_secret_is_a_secret() {
+    let mut graph = graph(&[("db", None, None, 5432), ("web", None, None, 80)]);
+    set_var(
+      &mut graph,
+      "db",
+      "PASSWORD",
+      "hunter2",
+      VariableKind::Secret,
+    );
+    set_var(
+      &mut graph,
+      "web",
+      "DATABASE_PASSWORD",
+      "${{ db.vars.PASSWORD }}",
+      VariableKind::Plain,
+    );
+
+    assert_eq!(
+      rendered(&graph, "web"),
+      vec![RenderedVariable {
+        name: "DATABASE_PASSWORD".to_string(),
+        value: "hunter2".to_string(),
+        kind: VariableKind::Secret,
+      }]
+    );
+  }
+
+  #[test]
+  fn rejects_a_missing_domain() {
+    let mut graph = graph(&[("db", None, None, 5432), ("web", None, None, 80)]);
+    set_var(
+      &mut graph,
+      "web",
+      "DATABASE_HOST",
+      "${{ db.PRIVATE_DOMAIN }}",
+      VariableKind::Plain,
+    );
+
+    assert!(matches!(
+      graph.render(&graph.stars["web"].id),
+      Err(InterpolationError::MissingField(star, "private domain")) if star == "db"
+    ));
+  }
+
+  #[test]
+  fn rejects_unknown_references() {
+    let mut graph = graph(&[("web", None, None, 80)]);
+    set_var(
+      &mut graph,
+      "web",
+      "A",
+      "${{ db.PORT }}",
+      VariableKind::Plain,
+    );
+    set_var(
+      &mut graph,
+      "web",
+      "B",
+      "${{ web.vars.C }}",
+      VariableKind::Plain,
+    );
+
+    let web = graph.stars["web"].id;
+    assert!(matches!(
+      graph.check(&[web]),
+      Err(InterpolationError::UnknownStar(star)) if star == "db"
+    ));
+
+    set_var(
+      &mut graph,
+      "web",
+      "A",
+      "${{ web.PORT }}",
+      VariableKind::Plain,
+    );
+    assert!(matches!(
+      graph.check(&[web]),
+      Err(InterpolationError::UnknownVariable(star, name)) if star == "web" && name == "C"
+    ));
+  }
+
+  #[test]
+  fn rejects_a_cycle_between_stars() {
+    let mut graph = graph(&[("api", None, None, 80), ("web", None, None, 80)]);
+    set_var(
+      &mut graph,
+      "api",
+      "A",
+      "${{ web.vars.B }}",
+      VariableKind::Plain,
+    );
+    set_var(
+      &mut graph,
+      "web",
+      "B",
+      "x${{ api.vars.A }}",
+      VariableKind::Plain,
+    );
+
+    assert!(matches!(
+      graph.check(&[graph.stars["api"].id]),
+      Err(InterpolationError::Cycle(cycle)) if cycle == "api.vars.A -> web.vars.B -> api.vars.A"
+    ));
+  }
+
+  #[test]
+  fn rejects_a_variable_reading_itself() {
+    let mut graph = graph(&[("web", None, None, 80)]);
+    set_var(
+      &mut graph,
+      "web",
+      "A",
+      "${{ web.vars.A }}",
+      VariableKind::Plain,
+    );
+
+    assert!(matches!(
+      graph.check(&[graph.stars["web"].id]),
+      Err(InterpolationError::Cycle(cycle)) if cycle == "web.vars.A -> web.vars.A"
+    ));
+  }
+
+  #[test]
+  fn parses_references_in_order() {
+    assert_eq!(
+      references("${{ db.PORT }} and ${{web.vars.TOKEN}}").unwrap(),
+      vec![
+        Reference {
+          star: "db".to_string(),
+          target: ReferenceTarget::Port,
+        },
+        Reference {
+          star: "web".to_string(),
+          target: ReferenceTarget::Var("TOKEN".to_string()),
+        },
+      ]
+    );
+    assert!(matches!(
+      references("${{ db.PORT"),
+      Err(InterpolationError::Unterminated(_))
+    ));
+    assert!(matches!(
+      references("${{ db.NAME }}"),
+      Err(InterpolationError::Invalid(_))
+    ));
+  }
+}
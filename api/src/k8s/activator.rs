@@ -0,0 +1,387 @@
+use k8s_openapi::api::{
+  apps::v1::Deployment,
+  core::v1::{ConfigMap, Service, ServiceAccount},
+  rbac::v1::{Role, RoleBinding},
+};
+use kube::{
+  api::{DeleteParams, Patch, PatchParams},
+  Api, Client, Result,
+};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{config::config, models::star::Star};
+
+use super::{
+  private_host, public_host,
+  security::{container_security_context, pod_security_context},
+};
+
+pub const ACTIVATOR_NAME: &str = "gws-activator";
+pub const ACTIVATOR_PORT: i32 = 8012;
+/// set by the activator on the deployments it scaled to zero
+pub const IDLE_ANNOTATION: &str = "gws.io/idle";
+const ROUTES_CONFIG_MAP: &str = "gws-activator-routes";
+
+/// proxy in front of the idle stars of a galaxy, it wakes the stars on the first request
+pub struct Activator {
+  galaxy_id: Uuid,
+}
+
+pub struct ActivatorRequestResolver {
+  sa: Api<ServiceAccount>,
+  role: Api<Role>,
+  role_binding: Api<RoleBinding>,
+  deploy: Api<Deployment>,
+  svc: Api<Service>,
+  routes: Api<ConfigMap>,
+}
+
+impl ActivatorRequestResolver {
+  pub fn new(client: Client, galaxy_ns: &str) -> Self {
+    Self {
+      sa: Api::namespaced(client.clone(), galaxy_ns),
+      role: Api::namespaced(client.clone(), galaxy_ns),
+      role_binding: Api::namespaced(client.clone(), galaxy_ns),
+      deploy: Api::namespaced(client.clone(), galaxy_ns),
+      svc: Api::namespaced(client.clone(), galaxy_ns),
+      routes: Api::namespaced(client, galaxy_ns),
+    }
+  }
+}
+
+/// entry of the activator routes, keep in sync with the activator crate
+#[derive(Serialize)]
+struct ActivatorRoute {
+  hosts: Vec<String>,
+  service: String,
+  port: i32,
+  deployment: String,
+  idle_timeout: i32,
+}
+
+impl From<&Star> for ActivatorRoute {
+  fn from(star: &Star) -> Self {
+    let mut hosts = vec![format!("star-{}", star.id)];
+
+    if let Some(public_domain) = &star.public_domain {
+      hosts.push(public_host(public_domain));
+    }
+
+    if let Some(private_domain) = &star.private_domain {
+      hosts.push(private_host(private_domain));
+    }
+
+    Self {
+      hosts,
+      service: format!("star-{}-origin", star.id),
+      port: star.port,
+      deployment: format!("star-{}", star.id),
+      idle_timeout: star
+        .idle_timeout
+        .expect("Idle timeout not found when creating activator route"),
+    }
+  }
+}
+
+impl From<&Activator> for ServiceAccount {
+  fn from(activator: &Activator) -> Self {
+    let sa = json!({
+      "apiVersion": "v1",
+      "kind": "ServiceAccount",
+      "metadata": {
+        "name": ACTIVATOR_NAME,
+        "namespace": format!("galaxy-{}", activator.galaxy_id),
+      }
+    });
+
+    serde_json::from_value(sa).expect("Invalid activator service account")
+  }
+}
+
+impl From<&Activator> for Role {
+  fn from(activator: &Activator) -> Self {
+    let role = json!({
+      "apiVersion": "rbac.authorization.k8s.io/v1",
+      "kind": "Role",
+      "metadata": {
+        "name": ACTIVATOR_NAME,
+        "namespace": format!("galaxy-{}", activator.galaxy_id),
+      },
+      "rules": [
+        {
+          "apiGroups": ["apps"],
+          "resources": ["deployments"],
+          "verbs": ["get", "list", "watch", "patch"]
+        },
+        {
+          "apiGroups": [""],
+          "resources": ["configmaps"],
+          "resourceNames": [ROUTES_CONFIG_MAP],
+          "verbs": ["get", "list", "watch"]
+        }
+      ]
+    });
+
+    serde_json::from_value(role).expect("Invalid activator role")
+  }
+}
+
+impl From<&Activator> for RoleBinding {
+  fn from(activator: &Activator) -> Self {
+    let ns = format!("galaxy-{}", activator.galaxy_id);
+
+    let role_binding = json!({
+      "apiVersion": "rbac.authorization.k8s.io/v1",
+      "kind": "RoleBinding",
+      "metadata": {
+        "name": ACTIVATOR_NAME,
+        "namespace": &ns,
+      },
+      "roleRef": {
+        "apiGroup": "rbac.authorization.k8s.io",
+        "kind": "Role",
+        "name": ACTIVATOR_NAME
+      },
+      "subjects": [
+        {
+          "kind": "ServiceAccount",
+          "name": ACTIVATOR_NAME,
+          "namespace": &ns
+        }
+      ]
+    });
+
+    serde_json::from_value(role_binding).expect("Invalid activator role binding")
+  }
+}
+
+impl From<&Activator> for Deployment {
+  fn from(activator: &Activator) -> Self {
+    let deployment = json!({
+      "apiVersion": "apps/v1",
+      "kind": "Deployment",
+      "metadata": {
+        "name": ACTIVATOR_NAME,
+        "namespace": format!("galaxy-{}", activator.galaxy_id),
+        "labels": {
+          "app": ACTIVATOR_NAME,
+          "galaxy_id": activator.galaxy_id,
+        }
+      },
+      "spec": {
+        "replicas": 1,
+        "selector": {
+          "matchLabels": {
+            "app": ACTIVATOR_NAME,
+          },
+        },
+        "template": {
+          "metadata": {
+            "labels": {
+              "app": ACTIVATOR_NAME,
+              "galaxy_id": activator.galaxy_id,
+            },
+          },
+          "spec": {
+            "serviceAccountName": ACTIVATOR_NAME,
+            "enableServiceLinks": false,
+            "securityContext": pod_security_context(None, None),
+            "containers": [
+              {
+                "name": ACTIVATOR_NAME,
+                "image": config().activator_image,
+                "env": [
+                  {
+                    "name": "POD_NAMESPACE",
+                    "valueFrom": {
+                      "fieldRef": {
+                        "fieldPath": "metadata.namespace"
+                      }
+                    }
+                  }
+                ],
+                "ports": [
+                  {
+                    "containerPort": ACTIVATOR_PORT
+                  }
+                ],
+                "securityContext": container_security_context(true)
+              }
+            ]
+          }
+        }
+      }
+    });
+
+    serde_json::from_value(deployment).expect("Invalid activator deployment")
+  }
+}
+
+impl Activator {
+  pub fn new(galaxy_id: Uuid) -> Self {
+    Self { galaxy_id }
+  }
+
+  /// field manager owning the route of a single star inside the shared routes config map
+  fn route_manager(star: &Star) -> PatchParams {
+    PatchParams::apply(&format!("gws-api-star-{}", star.id))
+  }
+
+  fn routes(&self, data: serde_json::Value) -> ConfigMap {
+    let routes = json!({
+      "apiVersion": "v1",
+      "kind": "ConfigMap",
+      "metadata": {
+        "name": ROUTES_CONFIG_MAP,
+        "namespace": format!("galaxy-{}", self.galaxy_id),
+      },
+      "data": data
+    });
+
+    serde_json::from_value(routes).expect("Invalid activator routes")
+  }
+
+  /// route the star traffic through the activator
+  pub async fn bind(&self, star: &Star, api: &ActivatorRequestResolver) -> Result<()> {
+    let pp = PatchParams::apply("gws-api");
+
+    let _ = api
+      .sa
+      .patch(
+        ACTIVATOR_NAME,
+        &pp,
+        &Patch::Apply(ServiceAccount::from(self)),
+      )
+      .await?;
+
+    let _ = api
+      .role
+      .patch(ACTIVATOR_NAME, &pp, &Patch::Apply(Role::from(self)))
+      .await?;
+
+    let _ = api
+      .role_binding
+      .patch(ACTIVATOR_NAME, &pp, &Patch::Apply(RoleBinding::from(self)))
+      .await?;
+
+    let _ = api
+      .deploy
+      .patch(ACTIVATOR_NAME, &pp, &Patch::Apply(Deployment::from(self)))
+      .await?;
+
+    let _ = api
+      .svc
+      .patch(
+        &format!("star-{}-origin", star.id),
+        &pp,
+        &Patch::Apply(origin_service(star)),
+      )
+      .await?;
+
+    let route =
+      serde_json::to_string(&ActivatorRoute::from(star)).expect("Invalid activator route");
+
+    let _ = api
+      .routes
+      .patch(
+        ROUTES_CONFIG_MAP,
+        &Self::route_manager(star),
+        &Patch::Apply(self.routes(json!({ format!("star-{}", star.id): route }))),
+      )
+      .await?;
+
+    Ok(())
+  }
+
+  /// send the star traffic straight to the star pods
+  pub async fn unbind(&self, star: &Star, api: &ActivatorRequestResolver) -> Result<()> {
+    let origin_name = format!("star-{}-origin", star.id);
+
+    if api.svc.get_opt(&origin_name).await?.is_some() {
+      let _ = api
+        .svc
+        .delete(&origin_name, &DeleteParams::default())
+        .await?;
+    }
+
+    if api.routes.get_opt(ROUTES_CONFIG_MAP).await?.is_none() {
+      return Ok(());
+    }
+
+    // applying no data drops the route owned by the star field manager
+    let routes = api
+      .routes
+      .patch(
+        ROUTES_CONFIG_MAP,
+        &Self::route_manager(star),
+        &Patch::Apply(self.routes(json!({}))),
+      )
+      .await?;
+
+    // the activator of the galaxy goes away with its last idle star
+    if routes.data.unwrap_or_default().is_empty() {
+      self.remove(api).await?;
+    }
+
+    Ok(())
+  }
+
+  /// delete the activator of the galaxy, the next idle star creates it again
+  async fn remove(&self, api: &ActivatorRequestResolver) -> Result<()> {
+    let dp = DeleteParams::default();
+
+    if api.deploy.get_opt(ACTIVATOR_NAME).await?.is_some() {
+      let _ = api.deploy.delete(ACTIVATOR_NAME, &dp).await?;
+    }
+
+    if api.role_binding.get_opt(ACTIVATOR_NAME).await?.is_some() {
+      let _ = api.role_binding.delete(ACTIVATOR_NAME, &dp).await?;
+    }
+
+    if api.role.get_opt(ACTIVATOR_NAME).await?.is_some() {
+      let _ = api.role.delete(ACTIVATOR_NAME, &dp).await?;
+    }
+
+    if api.sa.get_opt(ACTIVATOR_NAME).await?.is_some() {
+      let _ = api.sa.delete(ACTIVATOR_NAME, &dp).await?;
+    }
+
+    if api.routes.get_opt(ROUTES_CONFIG_MAP).await?.is_some() {
+      let _ = api.routes.delete(ROUTES_CONFIG_MAP, &dp).await?;
+    }
+
+    Ok(())
+  }
+}
+
+/// service reaching the star pods, used by the activator to forward the requests
+fn origin_service(star: &Star) -> Service {
+  let svc = json!({
+    "apiVersion": "v1",
+    "kind": "Service",
+    "metadata": {
+      "name": format!("star-{}-origin", star.id),
+      "namespace": format!("galaxy-{}", star.galaxy_id),
+      "labels": {
+        "star_name": star.name,
+        "star_id": star.id,
+        "galaxy_id": star.galaxy_id,
+      },
+    },
+    "spec": {
+      "selector": {
+        "star_id": star.id,
+      },
+      "ports": [
+        {
+          "port": star.port,
+          "targetPort": star.port,
+        },
+      ],
+    },
+  });
+
+  serde_json::from_value(svc).expect("Invalid origin service")
+}
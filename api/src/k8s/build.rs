@@ -0,0 +1,341 @@
+use std::time::Duration;
+
+use actix_web::web::Bytes;
+use chrono::Utc;
+use derive_more::{Display, Error, From};
+use futures_util::AsyncBufRead;
+use k8s_openapi::api::{
+  batch::v1::Job,
+  core::v1::{Namespace, Pod},
+  networking::v1::NetworkPolicy,
+};
+use kube::{
+  api::{AttachParams, DeleteParams, ListParams, LogParams, Patch, PatchParams, PostParams},
+  Api, Client, Result,
+};
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use crate::config::config;
+use crate::models::{
+  build::{Build, BuildOutcome, BuildStatus},
+  star::Star,
+};
+
+/// finished jobs are kept for a week, enough to read back their outcome and logs
+const JOB_TTL_SECONDS: i32 = 7 * 24 * 60 * 60;
+/// builds still running after an hour are stopped
+const BUILD_DEADLINE_SECONDS: i32 = 60 * 60;
+const POD_START_ATTEMPTS: u32 = 120;
+
+/// the builder runs as root so it cannot share the restricted namespace of the galaxy,
+/// each galaxy builds in its own namespace next to it
+fn build_namespace(galaxy_id: &Uuid) -> String {
+  format!("galaxy-{}-builds", galaxy_id)
+}
+
+pub struct BuildRequestResolver {
+  galaxy_id: Uuid,
+  namespace: Api<Namespace>,
+  net_policy: Api<NetworkPolicy>,
+  job: Api<Job>,
+  pod: Api<Pod>,
+}
+
+impl BuildRequestResolver {
+  pub fn new(client: Client, galaxy_id: &Uuid) -> Self {
+    let build_ns = build_namespace(galaxy_id);
+
+    Self {
+      galaxy_id: *galaxy_id,
+      namespace: Api::all(client.clone()),
+      net_policy: Api::namespaced(client.clone(), &build_ns),
+      job: Api::namespaced(client.clone(), &build_ns),
+      pod: Api::namespaced(client, &build_ns),
+    }
+  }
+
+  pub async fn try_default(galaxy_id: &Uuid) -> Result<Self> {
+    Ok(Self::new(Client::try_default().await?, galaxy_id))
+  }
+
+  /// build namespace of the galaxy, created with its first build,
+  /// nothing reaches the builders and they only push to the registry
+  async fn apply_namespace(&self) -> Result<()> {
+    let build_ns = build_namespace(&self.galaxy_id);
+    let pp = PatchParams::apply("gws-api");
+
+    let namespace = json!({
+      "apiVersion": "v1",
+      "kind": "Namespace",
+      "metadata": {
+        "name": build_ns,
+        "labels": {
+          "galaxy_id": self.galaxy_id,
+          "pod-security.kubernetes.io/enforce": "baseline",
+          "pod-security.kubernetes.io/enforce-version": "latest",
+        }
+      }
+    });
+
+    let _ = self
+      .namespace
+      .patch(&build_ns, &pp, &Patch::Apply(namespace))
+      .await?;
+
+    let net_policy = json!({
+      "apiVersion": "networking.k8s.io/v1",
+      "kind": "NetworkPolicy",
+      "metadata": {
+        "name": "deny-ingress",
+        "namespace": build_ns,
+      },
+      "spec": {
+        "podSelector": {},
+        "policyTypes": ["Ingress"],
+      }
+    });
+
+    let _ = self
+      .net_policy
+      .patch("deny-ingress", &pp, &Patch::Apply(net_policy))
+      .await?;
+
+    Ok(())
+  }
+
+  /// remove the build namespace of a deleted galaxy with its jobs
+  pub async fn delete_namespace(&self) -> Result<()> {
+    let build_ns = build_namespace(&self.galaxy_id);
+
+    if self.namespace.get_opt(&build_ns).await?.is_some() {
+      let _ = self
+        .namespace
+        .delete(&build_ns, &DeleteParams::default())
+        .await?;
+    }
+
+    Ok(())
+  }
+}
+
+#[derive(Debug, Display, Error, From)]
+pub enum UploadError {
+  #[display(fmt = "The builder did not start in time")]
+  NotStarted,
+  #[display(fmt = "Kubernetes error: {_0}")]
+  Kube(kube::Error),
+  #[display(fmt = "Unable to write the source: {_0}")]
+  Write(std::io::Error),
+  #[display(fmt = "Attach session failed: {_0}")]
+  #[from(ignore)]
+  Attach(#[error(not(source))] String),
+}
+
+/// kaniko job reading the gzipped source from its stdin and pushing the image,
+/// the digest of the pushed image is written as termination message of the pod
+fn job(build: &Build, star: &Star) -> Job {
+  let mut args = vec![
+    "--context=tar://stdin".to_string(),
+    format!("--destination={}", build.image),
+    "--digest-file=/dev/termination-log".to_string(),
+  ];
+
+  if config().registry_insecure {
+    args.push("--insecure".to_string());
+  }
+
+  let labels = json!({
+    "build_id": build.id,
+    "galaxy_id": star.galaxy_id,
+  });
+
+  let job = json!({
+    "apiVersion": "batch/v1",
+    "kind": "Job",
+    "metadata": {
+      "name": format!("build-{}", build.id),
+      "namespace": build_namespace(&star.galaxy_id),
+      "labels": labels,
+    },
+    "spec": {
+      "backoffLimit": 0,
+      "activeDeadlineSeconds": BUILD_DEADLINE_SECONDS,
+      "ttlSecondsAfterFinished": JOB_TTL_SECONDS,
+      "template": {
+        "metadata": {
+          "labels": labels,
+        },
+        "spec": {
+          "restartPolicy": "Never",
+          "enableServiceLinks": false,
+          "automountServiceAccountToken": false,
+          "containers": [
+            {
+              "name": "builder",
+              "image": config().builder_image,
+              "args": args,
+              "stdin": true,
+              "stdinOnce": true,
+            }
+          ]
+        }
+      }
+    }
+  });
+
+  serde_json::from_value(job).expect("Invalid build job")
+}
+
+impl Build {
+  fn job_name(&self) -> String {
+    format!("build-{}", self.id)
+  }
+
+  fn job_selector(&self) -> ListParams {
+    ListParams::default().labels(&format!("job-name={}", self.job_name()))
+  }
+
+  /// name of the pod of the build once it left the pending phase
+  async fn started_pod(&self, api: &BuildRequestResolver) -> Result<Option<String>> {
+    for _ in 0..POD_START_ATTEMPTS {
+      let pods = api.pod.list(&self.job_selector()).await?.items;
+
+      let started = pods.into_iter().find(|pod| {
+        pod
+          .status
+          .as_ref()
+          .and_then(|status| status.phase.as_deref())
+          .is_some_and(|phase| phase != "Pending")
+      });
+
+      if let Some(pod_name) = started.and_then(|pod| pod.metadata.name) {
+        return Ok(Some(pod_name));
+      }
+
+      actix_web::rt::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    Ok(None)
+  }
+
+  pub async fn launch(&self, star: &Star, api: &BuildRequestResolver) -> Result<()> {
+    api.apply_namespace().await?;
+
+    let _ = api
+      .job
+      .create(&PostParams::default(), &job(self, star))
+      .await?;
+
+    Ok(())
+  }
+
+  /// send the source to the builder once its pod is running,
+  /// the job is removed when the source cannot be delivered so the build fails
+  pub async fn upload(&self, source: Bytes, api: BuildRequestResolver) -> Result<(), UploadError> {
+    let uploaded = self.send_source(source, &api).await;
+
+    if uploaded.is_err() {
+      let _ = api
+        .job
+        .delete(&self.job_name(), &DeleteParams::background())
+        .await?;
+    }
+
+    uploaded
+  }
+
+  async fn send_source(
+    &self,
+    source: Bytes,
+    api: &BuildRequestResolver,
+  ) -> Result<(), UploadError> {
+    let pod_name = self
+      .started_pod(api)
+      .await?
+      .ok_or(UploadError::NotStarted)?;
+
+    let ap = AttachParams::default()
+      .container("builder")
+      .stdin(true)
+      .stdout(false)
+      .stderr(false);
+
+    let mut attached = api.pod.attach(&pod_name, &ap).await?;
+
+    let mut stdin = attached.stdin().expect("Stdin of the builder not attached");
+    stdin.write_all(&source).await?;
+    // closing stdin ends the session and the builder reads the end of the archive
+    drop(stdin);
+
+    attached
+      .join()
+      .await
+      .map_err(|err| UploadError::Attach(err.to_string()))
+  }
+
+  /// outcome of the build if its job is over
+  pub async fn outcome(&self, api: &BuildRequestResolver) -> Result<Option<BuildOutcome>> {
+    let failed = BuildOutcome {
+      status: BuildStatus::Failed,
+      digest: None,
+      finished_at: Utc::now(),
+    };
+
+    // the job was removed before its outcome was recorded
+    let Some(job) = api.job.get_opt(&self.job_name()).await? else {
+      return Ok(Some(failed));
+    };
+
+    let pods = api.pod.list(&self.job_selector()).await?.items;
+
+    let terminated = pods
+      .into_iter()
+      .filter_map(|pod| pod.status)
+      .flat_map(|status| status.container_statuses.unwrap_or_default())
+      .find_map(|status| status.state.and_then(|state| state.terminated));
+
+    // the pod is removed when the build goes over its deadline
+    let Some(terminated) = terminated else {
+      let job_failed = job
+        .status
+        .and_then(|status| status.failed)
+        .is_some_and(|failed| failed > 0);
+
+      return Ok(job_failed.then_some(failed));
+    };
+
+    let digest = terminated
+      .message
+      .map(|message| message.trim().to_string())
+      .filter(|digest| digest.starts_with("sha256:"));
+
+    Ok(Some(BuildOutcome {
+      status: match (terminated.exit_code, &digest) {
+        (0, Some(_)) => BuildStatus::Succeeded,
+        _ => BuildStatus::Failed,
+      },
+      digest,
+      finished_at: terminated
+        .finished_at
+        .map(|time| time.0)
+        .unwrap_or_else(Utc::now),
+    }))
+  }
+
+  /// wait for the pod of the build to start and follow its output,
+  /// nothing is returned if the pod does not start in time
+  pub async fn logs(&self, api: BuildRequestResolver) -> Result<Option<impl AsyncBufRead>> {
+    let Some(pod_name) = self.started_pod(&api).await? else {
+      return Ok(None);
+    };
+
+    let lp = LogParams {
+      follow: true,
+      ..Default::default()
+    };
+
+    Ok(Some(api.pod.log_stream(&pod_name, &lp).await?))
+  }
+}
@@ -0,0 +1,82 @@
+use k8s_openapi::api::{batch::v1::Job, core::v1::Pod};
+use kube::{
+  api::{ListParams, LogParams},
+  Api, Client, Result,
+};
+use uuid::Uuid;
+
+use crate::models::star::Star;
+
+const LOG_TAIL_LINES: i64 = 100;
+
+pub struct CronRequestResolver {
+  job: Api<Job>,
+  pod: Api<Pod>,
+}
+
+impl CronRequestResolver {
+  pub async fn try_default(galaxy_id: &Uuid) -> Result<Self> {
+    let client = Client::try_default().await?;
+    let galaxy_ns = format!("galaxy-{}", galaxy_id);
+
+    Ok(Self {
+      job: Api::namespaced(client.clone(), &galaxy_ns),
+      pod: Api::namespaced(client, &galaxy_ns),
+    })
+  }
+}
+
+impl Star {
+  /// jobs spawned by the cronjob of the star, newest first, with the logs of their last pod
+  pub async fn cron_runs(&self, api: CronRequestResolver) -> Result<Vec<(Job, Option<String>)>> {
+    let lp = ListParams::default().labels(&format!("star_id={}", self.id));
+
+    let mut jobs = api
+      .job
+      .list(&lp)
+      .await?
+      .items
+      .into_iter()
+      .filter(|job| {
+        job
+          .metadata
+          .owner_references
+          .iter()
+          .flatten()
+          .any(|owner| owner.kind == "CronJob")
+      })
+      .collect::<Vec<_>>();
+
+    jobs.sort_by(|a, b| {
+      b.metadata
+        .creation_timestamp
+        .cmp(&a.metadata.creation_timestamp)
+    });
+
+    let mut runs = Vec::with_capacity(jobs.len());
+
+    for job in jobs {
+      let job_name = job.metadata.name.clone().unwrap_or_default();
+      let lp = ListParams::default().labels(&format!("job-name={}", job_name));
+
+      let pods = api.pod.list(&lp).await?.items;
+
+      let logs = match pods.last().and_then(|pod| pod.metadata.name.as_ref()) {
+        Some(pod_name) => {
+          let lp = LogParams {
+            tail_lines: Some(LOG_TAIL_LINES),
+            ..Default::default()
+          };
+
+          // pods still waiting for their container have no logs yet
+          api.pod.logs(pod_name, &lp).await.ok()
+        }
+        None => None,
+      };
+
+      runs.push((job, logs));
+    }
+
+    Ok(runs)
+  }
+}
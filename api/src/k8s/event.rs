@@ -0,0 +1,96 @@
+use futures_util::{Stream, TryStreamExt};
+use k8s_openapi::api::core::v1::Event;
+use kube::{
+  api::ListParams,
+  runtime::{watcher, WatchStreamExt},
+  Api, Client, Result,
+};
+use uuid::Uuid;
+
+use crate::models::{planet::Planet, star::Star};
+
+pub struct EventRequestResolver {
+  event: Api<Event>,
+}
+
+impl EventRequestResolver {
+  pub async fn try_default(galaxy_id: &Uuid) -> Result<Self> {
+    let client = Client::try_default().await?;
+    let galaxy_ns = format!("galaxy-{}", galaxy_id);
+
+    Ok(Self {
+      event: Api::namespaced(client, &galaxy_ns),
+    })
+  }
+}
+
+/// selects the events of the kubernetes objects owned by a resource
+pub struct EventFilter {
+  name: String,
+  /// objects created by the owner carry its name followed by a dash
+  with_children: bool,
+}
+
+impl EventFilter {
+  fn matches(&self, event: &Event) -> bool {
+    let Some(name) = event.involved_object.name.as_deref() else {
+      return false;
+    };
+
+    name == self.name
+      || (self.with_children
+        && name
+          .strip_prefix(self.name.as_str())
+          .is_some_and(|suffix| suffix.starts_with('-')))
+  }
+}
+
+impl From<&Star> for EventFilter {
+  /// deployment, replica sets and pods of the star, or the jobs and pods of cron stars
+  fn from(star: &Star) -> Self {
+    Self {
+      name: format!("star-{}", star.id),
+      with_children: true,
+    }
+  }
+}
+
+impl From<&Planet> for EventFilter {
+  fn from(planet: &Planet) -> Self {
+    Self {
+      name: format!("planet-{}", planet.id),
+      with_children: false,
+    }
+  }
+}
+
+impl EventRequestResolver {
+  /// events of the resource, oldest first
+  pub async fn list(&self, filter: &EventFilter) -> Result<Vec<Event>> {
+    let mut events = self
+      .event
+      .list(&ListParams::default())
+      .await?
+      .items
+      .into_iter()
+      .filter(|event| filter.matches(event))
+      .collect::<Vec<_>>();
+
+    events.sort_by_key(|event| {
+      event
+        .last_timestamp
+        .as_ref()
+        .map(|time| time.0)
+        .or(event.event_time.as_ref().map(|time| time.0))
+    });
+
+    Ok(events)
+  }
+
+  /// current events of the resource followed by the new ones
+  pub fn watch(self, filter: EventFilter) -> impl Stream<Item = Result<Event, watcher::Error>> {
+    watcher(self.event, watcher::Config::default())
+      .applied_objects()
+      .try_filter(move |event| futures_util::future::ready(filter.matches(event)))
+  }
+}
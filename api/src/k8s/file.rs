@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::{
+  api::core::v1::{ConfigMap, Secret},
+  ByteString,
+};
+use kube::{
+  api::{DeleteParams, PostParams},
+  Api, Client, Result,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::{
+  file::{ConfigFile, ConfigFileMount},
+  star::Star,
+  var::VariableKind,
+};
+
+pub struct ConfigFileRequestResolver {
+  secret: Api<Secret>,
+  config_map: Api<ConfigMap>,
+  galaxy_id: Uuid,
+}
+
+impl ConfigFileRequestResolver {
+  pub async fn try_default(galaxy_id: &Uuid) -> Result<Self> {
+    let client = Client::try_default().await?;
+    let galaxy_ns = format!("galaxy-{}", galaxy_id);
+
+    Ok(Self {
+      secret: Api::namespaced(client.clone(), &galaxy_ns),
+      config_map: Api::namespaced(client, &galaxy_ns),
+      galaxy_id: *galaxy_id,
+    })
+  }
+}
+
+/// the secret and the config map holding the files of a star share the same name,
+/// the contents of each file are stored under its id
+fn files_name(star_id: &Uuid) -> String {
+  format!("star-{}-files", star_id)
+}
+
+/// name of the pod volume of the files mounted one by one
+const FILES_VOLUME: &str = "star-files";
+
+fn metadata(star_id: &Uuid, galaxy_id: &Uuid) -> Value {
+  json!({
+    "name": files_name(star_id),
+    "namespace": format!("galaxy-{}", galaxy_id),
+    "labels": {
+      "star_id": star_id,
+      "galaxy_id": galaxy_id
+    }
+  })
+}
+
+/// projected volume with the files read from the secret and the config map
+fn projected_volume(name: String, star_id: &Uuid, items: &[(&ConfigFileMount, &str)]) -> Value {
+  let items_of = |kind: VariableKind| {
+    items
+      .iter()
+      .filter(|(file, _)| file.kind == kind)
+      .map(|(file, path)| json!({ "key": file.id, "path": path, "mode": file.mode }))
+      .collect::<Vec<_>>()
+  };
+
+  // a source without items would project all the keys of the object
+  let mut sources = Vec::new();
+  let plain_items = items_of(VariableKind::Plain);
+  if !plain_items.is_empty() {
+    sources.push(json!({
+      "configMap": {
+        "name": files_name(star_id),
+        "optional": true,
+        "items": plain_items
+      }
+    }));
+  }
+  let secret_items = items_of(VariableKind::Secret);
+  if !secret_items.is_empty() {
+    sources.push(json!({
+      "secret": {
+        "name": files_name(star_id),
+        "optional": true,
+        "items": secret_items
+      }
+    }));
+  }
+
+  json!({
+    "name": name,
+    "projected": {
+      "sources": sources
+    }
+  })
+}
+
+/// volumes and mounts of the config files of the star,
+/// a file mounted alone never sees new contents until the pods restart
+/// while a mounted directory is refreshed by the kubelet
+pub(super) fn file_volumes(star: &Star) -> (Vec<Value>, Vec<Value>) {
+  let mut volumes = Vec::new();
+  let mut mounts = Vec::new();
+
+  let mut single_files = Vec::new();
+  let mut directories: BTreeMap<&str, Vec<(&ConfigFileMount, &str)>> = BTreeMap::new();
+
+  for file in star.config_files.iter() {
+    if file.mount_directory {
+      let (directory, name) = file.split_path();
+      directories.entry(directory).or_default().push((file, name));
+    } else {
+      single_files.push((file, file.id.to_string()));
+
+      mounts.push(json!({
+        "name": FILES_VOLUME,
+        "mountPath": file.mount_path,
+        "subPath": file.id,
+        "readOnly": true
+      }));
+    }
+  }
+
+  if !single_files.is_empty() {
+    let items = single_files
+      .iter()
+      .map(|(file, path)| (*file, path.as_str()))
+      .collect::<Vec<_>>();
+
+    volumes.push(projected_volume(FILES_VOLUME.to_string(), &star.id, &items));
+  }
+
+  for (index, (directory, items)) in directories.into_iter().enumerate() {
+    let name = format!("{}-{}", FILES_VOLUME, index);
+
+    mounts.push(json!({
+      "name": name,
+      "mountPath": directory,
+      "readOnly": true
+    }));
+    volumes.push(projected_volume(name, &star.id, &items));
+  }
+
+  (volumes, mounts)
+}
+
+impl ConfigFile {
+  /// write the contents in the object of the kind of the file and take them out of the other one
+  pub async fn apply(&self, contents: &str, api: &ConfigFileRequestResolver) -> Result<()> {
+    let name = files_name(&self.star_id);
+    let key = self.id.to_string();
+
+    match self.kind {
+      VariableKind::Secret => {
+        let value = ByteString(contents.as_bytes().to_vec());
+
+        match api.secret.get_opt(&name).await? {
+          Some(mut secret) => {
+            secret
+              .data
+              .get_or_insert_with(BTreeMap::new)
+              .insert(key, value);
+
+            let _ = api
+              .secret
+              .replace(&name, &PostParams::default(), &secret)
+              .await?;
+          }
+          None => {
+            let secret = json!({
+              "apiVersion": "v1",
+              "kind": "Secret",
+              "metadata": metadata(&self.star_id, &api.galaxy_id),
+              "data": { key: value }
+            });
+
+            let secret: Secret = serde_json::from_value(secret).expect("Invalid secret");
+            let _ = api.secret.create(&PostParams::default(), &secret).await?;
+          }
+        }
+
+        self.remove_from_config_map(api).await
+      }
+      VariableKind::Plain => {
+        match api.config_map.get_opt(&name).await? {
+          Some(mut config_map) => {
+            config_map
+              .data
+              .get_or_insert_with(BTreeMap::new)
+              .insert(key, contents.to_string());
+
+            let _ = api
+              .config_map
+              .replace(&name, &PostParams::default(), &config_map)
+              .await?;
+          }
+          None => {
+            let config_map = json!({
+              "apiVersion": "v1",
+              "kind": "ConfigMap",
+              "metadata": metadata(&self.star_id, &api.galaxy_id),
+              "data": { key: contents }
+            });
+
+            let config_map: ConfigMap =
+              serde_json::from_value(config_map).expect("Invalid config map");
+            let _ = api
+              .config_map
+              .create(&PostParams::default(), &config_map)
+              .await?;
+          }
+        }
+
+        self.remove_from_secret(api).await
+      }
+    }
+  }
+
+  async fn remove_from_secret(&self, api: &ConfigFileRequestResolver) -> Result<()> {
+    let name = files_name(&self.star_id);
+
+    if let Some(mut secret) = api.secret.get_opt(&name).await? {
+      let removed = secret
+        .data
+        .as_mut()
+        .and_then(|data| data.remove(&self.id.to_string()));
+
+      if removed.is_some() {
+        let _ = api
+          .secret
+          .replace(&name, &PostParams::default(), &secret)
+          .await?;
+      }
+    }
+
+    Ok(())
+  }
+
+  async fn remove_from_config_map(&self, api: &ConfigFileRequestResolver) -> Result<()> {
+    let name = files_name(&self.star_id);
+
+    if let Some(mut config_map) = api.config_map.get_opt(&name).await? {
+      let removed = config_map
+        .data
+        .as_mut()
+        .and_then(|data| data.remove(&self.id.to_string()));
+
+      if removed.is_some() {
+        let _ = api
+          .config_map
+          .replace(&name, &PostParams::default(), &config_map)
+          .await?;
+      }
+    }
+
+    Ok(())
+  }
+
+  pub async fn remove(&self, api: &ConfigFileRequestResolver) -> Result<()> {
+    self.remove_from_secret(api).await?;
+    self.remove_from_config_map(api).await
+  }
+}
+
+/// the files of a deleted star
+pub(super) async fn remove_star_files(
+  star_id: &Uuid,
+  secret: &Api<Secret>,
+  config_map: &Api<ConfigMap>,
+) -> Result<()> {
+  let name = files_name(star_id);
+  let dp = DeleteParams::default();
+
+  if secret.get_opt(&name).await?.is_some() {
+    let _ = secret.delete(&name, &dp).await?;
+  }
+
+  if config_map.get_opt(&name).await?.is_some() {
+    let _ = config_map.delete(&name, &dp).await?;
+  }
+
+  Ok(())
+}
@@ -7,20 +7,21 @@ use serde_json::{json, Value};
 
 use crate::models::galaxy::Galaxy;
 
-use super::ResourceBind;
+use super::{security::pod_security_labels, BuildRequestResolver, ResourceBind};
 
-const NAMESPACE_BASE_PATH: &'static str = "/api/v1/namespaces";
+const NAMESPACE_BASE_PATH: &str = "/api/v1/namespaces";
 
 impl From<&Galaxy> for Namespace {
   fn from(value: &Galaxy) -> Self {
+    let mut labels = pod_security_labels();
+    labels["name"] = json!(value.name);
+    labels["galaxy_id"] = json!(value.id);
+
     let namespace = json!({
       "version": "v1",
       "metadata": {
         "name": format!("galaxy-{}", value.id),
-        "labels": {
-          "name": value.name,
-          "galaxy_id": value.id
-        }
+        "labels": labels
       }
     });
 
@@ -97,7 +98,7 @@ impl ResourceBind for Galaxy {
 
     let req = Request::new(NAMESPACE_BASE_PATH)
       .create(&Default::default(), ns_buf)
-      .map_err(|err| Error::BuildRequest(err))?;
+      .map_err(Error::BuildRequest)?;
 
     let _: Namespace = client.request(req).await?;
 
@@ -123,7 +124,7 @@ impl ResourceBind for Galaxy {
 
     let req = Request::new(NAMESPACE_BASE_PATH)
       .replace(&format!("galaxy-{}", self.id), &Default::default(), ns_buf)
-      .map_err(|err| Error::BuildRequest(err))?;
+      .map_err(Error::BuildRequest)?;
 
     let _: Value = client.request(req).await?;
 
@@ -135,7 +136,7 @@ impl ResourceBind for Galaxy {
 
     let req = Request::new(NAMESPACE_BASE_PATH)
       .delete(&name, &Default::default())
-      .map_err(|err| Error::BuildRequest(err))?;
+      .map_err(Error::BuildRequest)?;
 
     let _: Value = client.request(req).await?;
 
@@ -154,6 +155,10 @@ impl ResourceBind for Galaxy {
       )
       .await?;
 
+    BuildRequestResolver::new(client, &self.id)
+      .delete_namespace()
+      .await?;
+
     Ok(())
   }
 }
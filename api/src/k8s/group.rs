@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::{
+  api::{apps::v1::Deployment, core::v1::Secret},
+  ByteString,
+};
+use kube::{
+  api::{DeleteParams, PostParams},
+  Api, Client, Result,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::group::VariableGroup;
+
+pub struct VariableGroupRequestResolver {
+  secret: Api<Secret>,
+  deploy: Api<Deployment>,
+}
+
+impl VariableGroupRequestResolver {
+  pub async fn try_default(galaxy_id: &Uuid) -> Result<Self> {
+    let client = Client::try_default().await?;
+    let galaxy_ns = format!("galaxy-{}", galaxy_id);
+
+    Ok(Self {
+      secret: Api::namespaced(client.clone(), &galaxy_ns),
+      deploy: Api::namespaced(client, &galaxy_ns),
+    })
+  }
+}
+
+impl VariableGroup {
+  fn secret_name(&self) -> String {
+    format!("group-{}-vars", self.id)
+  }
+
+  /// write the values of the group in its secret,
+  /// the stars reading it restart only when a value changed
+  pub async fn apply(
+    &self,
+    values: &BTreeMap<String, String>,
+    stars: &[Uuid],
+    api: VariableGroupRequestResolver,
+  ) -> Result<()> {
+    let secret_name = self.secret_name();
+    let data = values
+      .iter()
+      .map(|(name, value)| (name.clone(), ByteString(value.clone().into_bytes())))
+      .collect::<BTreeMap<_, _>>();
+
+    match api.secret.get_opt(&secret_name).await? {
+      Some(secret) if secret.data.clone().unwrap_or_default() == data => return Ok(()),
+      Some(mut secret) => {
+        secret.data = Some(data);
+
+        let _ = api
+          .secret
+          .replace(&secret_name, &PostParams::default(), &secret)
+          .await?;
+      }
+      None => {
+        let secret = json!({
+          "apiVersion": "v1",
+          "kind": "Secret",
+          "metadata": {
+            "name": &secret_name,
+            "namespace": format!("galaxy-{}", self.galaxy_id),
+            "labels": {
+              "group_id": self.id,
+              "galaxy_id": self.galaxy_id
+            }
+          },
+          "data": data
+        });
+
+        let secret: Secret = serde_json::from_value(secret).expect("Invalid secret");
+        let _ = api.secret.create(&PostParams::default(), &secret).await?;
+      }
+    }
+
+    for star_id in stars {
+      let deploy_name = format!("star-{}", star_id);
+
+      // cron stars read the variables on the next run
+      if api.deploy.get_opt(&deploy_name).await?.is_some() {
+        let _ = api.deploy.restart(&deploy_name).await?;
+      }
+    }
+
+    Ok(())
+  }
+
+  pub async fn remove(&self, api: VariableGroupRequestResolver) -> Result<()> {
+    let secret_name = self.secret_name();
+
+    if api.secret.get_opt(&secret_name).await?.is_some() {
+      let _ = api
+        .secret
+        .delete(&secret_name, &DeleteParams::default())
+        .await?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+  api::{ApiResource, DynamicObject, GroupVersionKind, ListParams},
+  Api, Client, Result,
+};
+use log::warn;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::models::{metrics::ReplicaUsage, star::Star};
+
+fn pod_metrics_resource() -> ApiResource {
+  ApiResource::from_gvk(&GroupVersionKind::gvk(
+    "metrics.k8s.io",
+    "v1beta1",
+    "PodMetrics",
+  ))
+}
+
+/// cpu quantity like `250m` or `12345n` in millicores
+fn parse_cpu(quantity: &str) -> f64 {
+  let (value, scale) = match quantity {
+    q if q.ends_with('n') => (&q[..q.len() - 1], 1e-6),
+    q if q.ends_with('u') => (&q[..q.len() - 1], 1e-3),
+    q if q.ends_with('m') => (&q[..q.len() - 1], 1.0),
+    q => (q, 1e3),
+  };
+
+  value.parse::<f64>().unwrap_or_default() * scale
+}
+
+/// memory or storage quantity like `128Mi` or `1G` in bytes
+pub(super) fn parse_memory(quantity: &str) -> i64 {
+  const SUFFIXES: [(&str, f64); 12] = [
+    ("Ki", 1024.0),
+    ("Mi", 1024.0 * 1024.0),
+    ("Gi", 1024.0 * 1024.0 * 1024.0),
+    ("Ti", 1024.0 * 1024.0 * 1024.0 * 1024.0),
+    ("Pi", 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0),
+    ("Ei", 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0),
+    ("k", 1e3),
+    ("M", 1e6),
+    ("G", 1e9),
+    ("T", 1e12),
+    ("P", 1e15),
+    ("E", 1e18),
+  ];
+
+  let (value, scale) = SUFFIXES
+    .iter()
+    .find_map(|(suffix, scale)| quantity.strip_suffix(suffix).map(|value| (value, *scale)))
+    .unwrap_or((quantity, 1.0));
+
+  (value.parse::<f64>().unwrap_or_default() * scale) as i64
+}
+
+/// cpu and memory of all the containers of a pod
+fn pod_usage(metrics: &DynamicObject) -> (f64, i64) {
+  let containers = metrics.data["containers"]
+    .as_array()
+    .cloned()
+    .unwrap_or_default();
+
+  containers
+    .iter()
+    .fold((0.0, 0), |(cpu, memory), container| {
+      let usage = &container["usage"];
+
+      (
+        cpu + usage["cpu"].as_str().map(parse_cpu).unwrap_or_default(),
+        memory
+          + usage["memory"]
+            .as_str()
+            .map(parse_memory)
+            .unwrap_or_default(),
+      )
+    })
+}
+
+pub struct MetricsRequestResolver {
+  pod: Api<Pod>,
+  pod_metrics: Api<DynamicObject>,
+}
+
+impl MetricsRequestResolver {
+  pub async fn try_default(galaxy_id: &Uuid) -> Result<Self> {
+    let client = Client::try_default().await?;
+    let galaxy_ns = format!("galaxy-{}", galaxy_id);
+
+    Ok(Self {
+      pod: Api::namespaced(client.clone(), &galaxy_ns),
+      pod_metrics: Api::namespaced_with(client, &galaxy_ns, &pod_metrics_resource()),
+    })
+  }
+}
+
+impl Star {
+  /// current usage of the replicas of the star from the metrics api
+  pub async fn usage(&self, api: MetricsRequestResolver) -> Result<Vec<ReplicaUsage>> {
+    let lp = ListParams::default().labels(&format!("star_id={}", self.id));
+
+    let pods = api.pod.list(&lp).await?.items;
+    let metrics = api.pod_metrics.list(&ListParams::default()).await?.items;
+
+    let usage = pods
+      .into_iter()
+      .filter_map(|pod| pod.metadata.name)
+      .filter_map(|pod| {
+        let pod_metrics = metrics
+          .iter()
+          .find(|metrics| metrics.metadata.name.as_ref() == Some(&pod))?;
+
+        let (cpu_millicores, memory_bytes) = pod_usage(pod_metrics);
+
+        Some(ReplicaUsage {
+          pod,
+          cpu_millicores,
+          memory_bytes,
+        })
+      })
+      .collect();
+
+    Ok(usage)
+  }
+}
+
+/// usage of all the replicas of a star with the network counters since the pods started
+#[derive(Debug, Default)]
+pub struct StarUsage {
+  pub replicas: i32,
+  pub cpu_millicores: f64,
+  pub memory_bytes: i64,
+  pub rx_bytes: u64,
+  pub tx_bytes: u64,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+  pods: Vec<PodStats>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodStats {
+  pod_ref: PodRef,
+  network: Option<NetworkStats>,
+}
+
+#[derive(Deserialize)]
+struct PodRef {
+  name: String,
+  namespace: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NetworkStats {
+  rx_bytes: Option<u64>,
+  tx_bytes: Option<u64>,
+}
+
+/// network counters of the pods of a node from the kubelet summary
+async fn node_network(
+  client: &Client,
+  node: &str,
+) -> Result<HashMap<(String, String), (u64, u64)>> {
+  let request = http::Request::get(format!("/api/v1/nodes/{}/proxy/stats/summary", node))
+    .body(vec![])
+    .map_err(kube::Error::HttpError)?;
+
+  let summary: Summary = client.request(request).await?;
+
+  let network = summary
+    .pods
+    .into_iter()
+    .filter_map(|pod| {
+      let network = pod.network?;
+
+      Some((
+        (pod.pod_ref.namespace, pod.pod_ref.name),
+        (
+          network.rx_bytes.unwrap_or_default(),
+          network.tx_bytes.unwrap_or_default(),
+        ),
+      ))
+    })
+    .collect();
+
+  Ok(network)
+}
+
+/// usage of every star in the cluster, stars without running pods are missing
+pub async fn sample_stars() -> Result<HashMap<Uuid, StarUsage>> {
+  let client = Client::try_default().await?;
+
+  let pods = Api::<Pod>::all(client.clone())
+    .list(&ListParams::default().labels("star_id"))
+    .await?
+    .items;
+
+  let metrics = Api::<DynamicObject>::all_with(client.clone(), &pod_metrics_resource())
+    .list(&ListParams::default())
+    .await?
+    .items;
+
+  let mut network = HashMap::new();
+  let mut nodes = pods
+    .iter()
+    .filter_map(|pod| pod.spec.as_ref()?.node_name.clone())
+    .collect::<Vec<_>>();
+  nodes.sort();
+  nodes.dedup();
+
+  for node in nodes {
+    // the usage is still recorded when the kubelet of a node does not answer
+    match node_network(&client, &node).await {
+      Ok(node_network) => network.extend(node_network),
+      Err(err) => warn!(
+        "Unable to read the kubelet summary of node {}: {}",
+        node, err
+      ),
+    }
+  }
+
+  let mut usage: HashMap<Uuid, StarUsage> = HashMap::new();
+
+  for pod in pods {
+    let star_id = pod
+      .metadata
+      .labels
+      .as_ref()
+      .and_then(|labels| labels.get("star_id"))
+      .and_then(|star_id| star_id.parse::<Uuid>().ok());
+
+    let (Some(star_id), Some(name), Some(namespace)) =
+      (star_id, pod.metadata.name, pod.metadata.namespace)
+    else {
+      continue;
+    };
+
+    let Some(pod_metrics) = metrics.iter().find(|metrics| {
+      metrics.metadata.name.as_ref() == Some(&name)
+        && metrics.metadata.namespace.as_ref() == Some(&namespace)
+    }) else {
+      continue;
+    };
+
+    let (cpu_millicores, memory_bytes) = pod_usage(pod_metrics);
+    let (rx_bytes, tx_bytes) = network.get(&(namespace, name)).copied().unwrap_or_default();
+
+    let star_usage = usage.entry(star_id).or_default();
+    star_usage.replicas += 1;
+    star_usage.cpu_millicores += cpu_millicores;
+    star_usage.memory_bytes += memory_bytes;
+    star_usage.rx_bytes += rx_bytes;
+    star_usage.tx_bytes += tx_bytes;
+  }
+
+  Ok(usage)
+}
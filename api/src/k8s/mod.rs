@@ -1,13 +1,44 @@
 use kube::Result;
 
+mod activator;
+mod build;
+mod cron;
+mod event;
+mod file;
 mod galaxy;
+mod group;
+mod metrics;
+mod placement;
 mod planet;
+mod rollout;
+mod run;
+mod security;
+mod snapshot;
 mod star;
 mod var;
 
+pub use activator::IDLE_ANNOTATION;
+pub use build::BuildRequestResolver;
+pub use cron::CronRequestResolver;
+pub use event::{EventFilter, EventRequestResolver};
+pub use file::ConfigFileRequestResolver;
+pub use group::VariableGroupRequestResolver;
+pub use metrics::{sample_stars, MetricsRequestResolver, StarUsage};
 pub use planet::PlanetRequestResolver;
+pub use run::RunRequestResolver;
+pub use snapshot::SnapshotRequestResolver;
 pub use star::StarRequestResolver;
-pub use var::VariableRequestResolver;
+pub use var::{pending_star_vars, sync_star_vars, VariableRequestResolver};
+
+/// host the cluster dns answers for the private domain of a star
+pub fn private_host(private_domain: &str) -> String {
+  format!("{}.gws.internal", private_domain)
+}
+
+/// host the ingress serves for the public domain of a star
+pub fn public_host(public_domain: &str) -> String {
+  format!("{}.localhost", public_domain)
+}
 
 pub trait ResourceBind: Sized {
   type RequestResolver;
@@ -0,0 +1,70 @@
+use log::warn;
+use serde_json::{json, Value};
+
+use crate::models::star::Star;
+use crate::placement::PlacementProfile;
+
+/// pods of the star, kept apart by the spread constraints and the anti-affinity
+fn star_selector(star: &Star) -> Value {
+  json!({
+    "matchLabels": {
+      "star_id": star.id,
+    }
+  })
+}
+
+fn affinity(star: &Star, profile: &PlacementProfile) -> Value {
+  let Some(anti_affinity) = &profile.anti_affinity else {
+    return Value::Null;
+  };
+
+  let term = json!({
+    "labelSelector": star_selector(star),
+    "topologyKey": anti_affinity.topology_key,
+  });
+
+  let pod_anti_affinity = match anti_affinity.required {
+    true => json!({
+      "requiredDuringSchedulingIgnoredDuringExecution": [term],
+    }),
+    false => json!({
+      "preferredDuringSchedulingIgnoredDuringExecution": [
+        {
+          "weight": 100,
+          "podAffinityTerm": term,
+        }
+      ],
+    }),
+  };
+
+  json!({ "podAntiAffinity": pod_anti_affinity })
+}
+
+/// render the placement profile of the star into a pod spec,
+/// a profile removed from the platform after being selected is ignored
+pub(super) fn apply_placement(spec: &mut Value, star: &Star) {
+  let Some(name) = &star.placement else {
+    return;
+  };
+
+  let Some(profile) = PlacementProfile::find(name) else {
+    warn!("Unknown placement profile {} of star {}", name, star.id);
+    return;
+  };
+
+  spec["nodeSelector"] = json!(profile.node_selector);
+  spec["tolerations"] = json!(profile.tolerations);
+  spec["topologySpreadConstraints"] = profile
+    .topology_spread
+    .iter()
+    .map(|spread| {
+      json!({
+        "topologyKey": spread.topology_key,
+        "maxSkew": spread.max_skew,
+        "whenUnsatisfiable": spread.when_unsatisfiable,
+        "labelSelector": star_selector(star),
+      })
+    })
+    .collect();
+  spec["affinity"] = affinity(star, profile);
+}
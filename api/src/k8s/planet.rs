@@ -1,18 +1,33 @@
-use k8s_openapi::api::{apps::v1::Deployment, core::v1::PersistentVolumeClaim};
+use k8s_openapi::api::{
+  apps::v1::Deployment,
+  batch::v1::CronJob,
+  core::v1::{PersistentVolumeClaim, TypedLocalObjectReference},
+};
 use kube::{
   api::{Patch, PatchParams},
   Api, Client, Result,
 };
-use serde_json::json;
+use serde_json::{json, Value};
 use uuid::Uuid;
 
-use crate::models::planet::Planet;
+use crate::{
+  models::{
+    planet::{Planet, PlanetResizeState},
+    snapshot::PlanetSnapshot,
+  },
+  storage::StorageTier,
+};
 
-use super::ResourceBind;
+use super::{
+  metrics::parse_memory,
+  snapshot::{snapshot_name, SNAPSHOT_API_GROUP},
+  ResourceBind,
+};
 
 pub struct PlanetRequestResolver {
   pvc: Api<PersistentVolumeClaim>,
   deploy: Api<Deployment>,
+  cronjob: Api<CronJob>,
 }
 
 impl PlanetRequestResolver {
@@ -22,16 +37,15 @@ impl PlanetRequestResolver {
 
     Ok(Self {
       pvc: Api::namespaced(client.clone(), &galaxy_ns),
-      deploy: Api::namespaced(client, &galaxy_ns),
+      deploy: Api::namespaced(client.clone(), &galaxy_ns),
+      cronjob: Api::namespaced(client, &galaxy_ns),
     })
   }
 }
 
 impl From<&Planet> for PersistentVolumeClaim {
   fn from(planet: &Planet) -> Self {
-    //! provisioner rancher.io/local-path not allow volume expansion
-    //! fix generate different pvc based on provisioner
-    let pvc = json!({
+    let mut pvc = json!({
       "apiVersion": "v1",
       "kind": "PersistentVolumeClaim",
       "metadata": {
@@ -46,15 +60,19 @@ impl From<&Planet> for PersistentVolumeClaim {
         "accessModes": [
           "ReadWriteOnce"
         ],
-        "storageClassName": "local-path",
         "resources": {
           "requests": {
-            "storage": "1G" // hardcoded storage for local-path
+            "storage": format!("{}M", planet.capacity)
           }
         }
       }
     });
 
+    // a tier removed from the platform leaves the class of the existing claims untouched
+    if let Some(tier) = StorageTier::find(&planet.storage_tier) {
+      pvc["spec"]["storageClassName"] = json!(tier.storage_class);
+    }
+
     serde_json::from_value(pvc).expect("Invalid Persistent Volume Claim format")
   }
 }
@@ -81,54 +99,217 @@ impl From<&Planet> for Patch<PersistentVolumeClaim> {
   }
 }
 
+/// pod template mounting the planet into the star container
+fn pod_template(planet: &Planet, star_id: &Uuid) -> Value {
+  json!({
+    "spec": {
+      "containers": [
+        {
+          "name": format!("star-container-{}", star_id),
+          "volumeMounts": [
+            {
+              "name": format!("planet-volume-{}", planet.id),
+              "mountPath": planet.path
+            }
+          ]
+        }
+      ],
+      "volumes": [
+        {
+          "name": format!("planet-volume-{}", planet.id),
+          "persistentVolumeClaim": {
+            "claimName": format!("planet-{}", planet.id)
+          }
+        }
+      ]
+    }
+  })
+}
+
+/// pod template without the planets of the star
+fn empty_pod_template(star_id: &Uuid) -> Value {
+  json!({
+    "spec": {
+      "containers": [
+        {
+          "name": format!("star-container-{}", star_id),
+          "volumeMounts": []
+        }
+      ],
+      "volumes": []
+    }
+  })
+}
+
+fn deployment_patch(planet: &Planet, star_id: &Uuid, template: Value) -> Patch<Deployment> {
+  let star_patch = json!({
+    "apiVersion": "apps/v1",
+    "kind": "Deployment",
+    "metadata": {
+      "name": format!("star-{}", star_id),
+      "namespace": format!("galaxy-{}", planet.galaxy_id),
+    },
+    "spec": {
+      "selector": {
+        "matchLabels": {
+          "star_id": star_id,
+        },
+      },
+      "template": template
+    }
+  });
+
+  let star_patch = serde_json::from_value(star_patch).expect("Invalid star patch");
+
+  Patch::Apply(star_patch)
+}
+
+/// the patch is not typed because a cronjob spec requires the schedule
+fn cronjob_patch(planet: &Planet, star_id: &Uuid, template: Value) -> Patch<Value> {
+  let star_patch = json!({
+    "apiVersion": "batch/v1",
+    "kind": "CronJob",
+    "metadata": {
+      "name": format!("star-{}", star_id),
+      "namespace": format!("galaxy-{}", planet.galaxy_id),
+    },
+    "spec": {
+      "jobTemplate": {
+        "spec": {
+          "template": template
+        }
+      }
+    }
+  });
+
+  Patch::Apply(star_patch)
+}
+
 impl From<&Planet> for Patch<Deployment> {
   fn from(planet: &Planet) -> Self {
     let star_id = planet
       .star_id
       .expect("Star id not found when patch star deployment");
 
-    let star_patch = json!({
-      "apiVersion": "apps/v1",
-      "kind": "Deployment",
-      "metadata": {
-        "name": format!("star-{}", star_id),
-        "namespace": format!("galaxy-{}", planet.galaxy_id),
-      },
-      "spec": {
-        "selector": {
-          "matchLabels": {
-            "star_id": star_id,
-          },
-        },
-        "template": {
-          "spec": {
-            "containers": [
-              {
-                "name": format!("star-container-{}", star_id),
-                "volumeMounts": [
-                  {
-                    "name": format!("planet-volume-{}", planet.id),
-                    "mountPath": planet.path
-                  }
-                ]
-              }
-            ],
-            "volumes": [
-              {
-                "name": format!("planet-volume-{}", planet.id),
-                "persistentVolumeClaim": {
-                  "claimName": format!("planet-{}", planet.id)
-                }
-              }
-            ]
-          }
-        }
-      }
-    });
+    deployment_patch(planet, &star_id, pod_template(planet, &star_id))
+  }
+}
+
+impl Planet {
+  /// storage class the claim of the planet was provisioned with, none before the claim exists
+  pub async fn storage_class(&self, api: &PlanetRequestResolver) -> Result<Option<String>> {
+    let pvc = api.pvc.get_opt(&format!("planet-{}", self.id)).await?;
+
+    Ok(pvc.and_then(|pvc| pvc.spec?.storage_class_name))
+  }
 
-    let star_patch = serde_json::from_value(star_patch).expect("Invalid star patch");
+  /// progress of the expansion of the claim towards the capacity of the planet
+  pub async fn resize_state(&self, api: &PlanetRequestResolver) -> Result<PlanetResizeState> {
+    let Some(pvc) = api.pvc.get_opt(&format!("planet-{}", self.id)).await? else {
+      return Ok(PlanetResizeState::Done);
+    };
+    let Some(status) = pvc.status else {
+      return Ok(PlanetResizeState::Resizing);
+    };
 
-    Patch::Apply(star_patch)
+    let condition = |kind: &str| {
+      status
+        .conditions
+        .iter()
+        .flatten()
+        .any(|condition| condition.type_ == kind && condition.status == "True")
+    };
+
+    if condition("FileSystemResizePending") {
+      return Ok(PlanetResizeState::FileSystemResizePending);
+    }
+
+    // the provisioner may round the capacity up
+    let requested = parse_memory(&format!("{}M", self.capacity));
+    let provisioned = status
+      .capacity
+      .as_ref()
+      .and_then(|capacity| capacity.get("storage"))
+      .map(|storage| parse_memory(&storage.0))
+      .unwrap_or_default();
+
+    if condition("Resizing") || provisioned < requested {
+      return Ok(PlanetResizeState::Resizing);
+    }
+
+    Ok(PlanetResizeState::Done)
+  }
+
+  /// new claim provisioned with the data of the snapshot, mounted like a created planet
+  pub async fn restore(&self, snapshot: &PlanetSnapshot, api: PlanetRequestResolver) -> Result<()> {
+    let mut pvc = PersistentVolumeClaim::from(self);
+
+    if let Some(spec) = pvc.spec.as_mut() {
+      spec.data_source = Some(TypedLocalObjectReference {
+        api_group: Some(SNAPSHOT_API_GROUP.to_string()),
+        kind: "VolumeSnapshot".to_string(),
+        name: snapshot_name(&snapshot.id),
+      });
+    }
+
+    api.pvc.create(&Default::default(), &pvc).await?;
+
+    if let Some(star_id) = self.star_id {
+      self.mount(&api, &star_id).await?;
+    }
+
+    Ok(())
+  }
+
+  /// mount the planet into the workload of the star, a deployment or a cronjob for cron stars
+  async fn mount(&self, api: &PlanetRequestResolver, star_id: &Uuid) -> Result<()> {
+    let name = format!("star-{}", star_id);
+    let pp = PatchParams::apply("gws-api");
+
+    if api.deploy.get_opt(&name).await?.is_some() {
+      api
+        .deploy
+        .patch(&name, &pp, &Patch::<Deployment>::from(self))
+        .await?;
+    } else {
+      api
+        .cronjob
+        .patch(
+          &name,
+          &pp,
+          &cronjob_patch(self, star_id, pod_template(self, star_id)),
+        )
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  async fn unmount(&self, api: &PlanetRequestResolver, star_id: &Uuid) -> Result<()> {
+    let name = format!("star-{}", star_id);
+    let pp = PatchParams::apply("gws-api");
+
+    if api.deploy.get_opt(&name).await?.is_some() {
+      api
+        .deploy
+        .patch(
+          &name,
+          &pp,
+          &deployment_patch(self, star_id, empty_pod_template(star_id)),
+        )
+        .await?;
+    } else {
+      api
+        .cronjob
+        .patch(
+          &name,
+          &pp,
+          &cronjob_patch(self, star_id, empty_pod_template(star_id)),
+        )
+        .await?;
+    }
+
+    Ok(())
   }
 }
 
@@ -142,20 +323,13 @@ impl ResourceBind for Planet {
       .await?;
 
     if let Some(star_id) = self.star_id {
-      let pp = PatchParams::apply("gws-api");
-      api
-        .deploy
-        .patch(
-          &format!("star-{}", star_id),
-          &pp,
-          &Patch::<Deployment>::from(self),
-        )
-        .await?;
+      self.mount(&api, &star_id).await?;
     }
 
     Ok(())
   }
 
+  /// a larger storage request expands the volume on the classes allowing it
   async fn update(&self, api: Self::RequestResolver) -> Result<()> {
     let pp = PatchParams::apply("gws-api");
 
@@ -169,14 +343,7 @@ impl ResourceBind for Planet {
       .await?;
 
     if let Some(star_id) = self.star_id {
-      api
-        .deploy
-        .patch(
-          &format!("star-{}", star_id),
-          &pp,
-          &Patch::<Deployment>::from(self),
-        )
-        .await?;
+      self.mount(&api, &star_id).await?;
     }
 
     Ok(())
@@ -189,41 +356,7 @@ impl ResourceBind for Planet {
       .await?;
 
     if let Some(star_id) = self.star_id {
-      let star_patch = json!({
-        "apiVersion": "apps/v1",
-        "kind": "Deployment",
-        "metadata": {
-          "name": format!("star-{}", star_id),
-          "namespace": format!("galaxy-{}", self.galaxy_id),
-        },
-        "spec": {
-          "selector": {
-            "matchLabels": {
-              "star_id": star_id,
-            },
-          },
-          "template": {
-            "spec": {
-              "containers": [
-                {
-                  "name": format!("star-container-{}", star_id),
-                  "volumeMounts": []
-                }
-              ],
-              "volumes": []
-            }
-          }
-        }
-      });
-
-      api
-        .deploy
-        .patch(
-          &format!("star-{}", star_id),
-          &PatchParams::apply("gws-api"),
-          &Patch::Apply(star_patch),
-        )
-        .await?;
+      self.unmount(&api, &star_id).await?;
     }
 
     Ok(())
@@ -0,0 +1,315 @@
+use k8s_openapi::api::{apps::v1::Deployment, core::v1::Service};
+use kube::{
+  api::{
+    ApiResource, DeleteParams, DynamicObject, GroupVersionKind, Patch, PatchParams, PostParams,
+  },
+  Result,
+};
+use serde_json::json;
+
+use crate::models::{
+  planet::Planet,
+  star::{Star, StrategyKind},
+};
+
+use super::{
+  public_host,
+  star::{deployment, mount_planets, pod_spec},
+  StarRequestResolver,
+};
+
+/// canary traffic is split by a traefik ingress route in front of the star ingress
+pub(super) fn ingress_route_resource() -> ApiResource {
+  ApiResource::from_gvk(&GroupVersionKind::gvk(
+    "traefik.containo.us",
+    "v1alpha1",
+    "IngressRoute",
+  ))
+}
+
+/// the preview of a star runs its new version next to the stable deployment
+struct Preview<'a> {
+  star: &'a Star,
+  planets: &'a [Planet],
+}
+
+impl Preview<'_> {
+  fn name(&self) -> String {
+    format!("star-{}-preview", self.star.id)
+  }
+}
+
+impl From<&Preview<'_>> for Deployment {
+  fn from(preview: &Preview) -> Self {
+    let star = preview.star;
+
+    let mut spec = pod_spec(star);
+    mount_planets(&mut spec, preview.planets);
+
+    // without the star_id label the pods stay out of the selector of the stable service
+    let labels = json!({
+      "preview_star_id": star.id,
+      "galaxy_id": star.galaxy_id,
+    });
+
+    let deployment = json!({
+      "apiVersion": "apps/v1",
+      "kind": "Deployment",
+      "metadata": {
+        "name": preview.name(),
+        "namespace": format!("galaxy-{}", star.galaxy_id),
+        "labels": labels,
+      },
+      "spec": {
+        "replicas": star.replicas(),
+        "selector": {
+          "matchLabels": {
+            "preview_star_id": star.id,
+          },
+        },
+        "template": {
+          "metadata": {
+            "labels": labels,
+          },
+          "spec": spec
+        },
+      },
+    });
+
+    serde_json::from_value(deployment).expect("Invalid preview deployment")
+  }
+}
+
+impl From<&Preview<'_>> for Service {
+  fn from(preview: &Preview) -> Self {
+    let star = preview.star;
+
+    let svc = json!({
+      "apiVersion": "v1",
+      "kind": "Service",
+      "metadata": {
+        "name": preview.name(),
+        "namespace": format!("galaxy-{}", star.galaxy_id),
+        "labels": {
+          "preview_star_id": star.id,
+          "galaxy_id": star.galaxy_id,
+        },
+      },
+      "spec": {
+        "selector": {
+          "preview_star_id": star.id,
+        },
+        "ports": [
+          {
+            "port": star.port,
+            "targetPort": star.port,
+          },
+        ],
+      },
+    });
+
+    serde_json::from_value(svc).expect("Invalid preview service")
+  }
+}
+
+impl From<&Preview<'_>> for DynamicObject {
+  fn from(preview: &Preview) -> Self {
+    let star = preview.star;
+
+    let public_domain = star
+      .public_domain
+      .as_ref()
+      .expect("Public domain not found when creating canary route");
+
+    // the priority puts the route before the router of the star ingress for the same host
+    let ingress_route = json!({
+      "apiVersion": "traefik.containo.us/v1alpha1",
+      "kind": "IngressRoute",
+      "metadata": {
+        "name": format!("star-{}-canary", star.id),
+        "namespace": format!("galaxy-{}", star.galaxy_id),
+        "labels": {
+          "preview_star_id": star.id,
+          "galaxy_id": star.galaxy_id,
+        },
+      },
+      "spec": {
+        "entryPoints": ["web", "websecure"],
+        "routes": [
+          {
+            "match": format!("Host(`{}`)", public_host(public_domain)),
+            "kind": "Rule",
+            "priority": 1000,
+            "services": [
+              {
+                "name": format!("star-{}", star.id),
+                "port": star.port,
+                "weight": 100 - star.canary_weight,
+              },
+              {
+                "name": preview.name(),
+                "port": star.port,
+                "weight": star.canary_weight,
+              }
+            ]
+          }
+        ],
+        "tls": {
+          "secretName": "stars-tls-secret-replica"
+        }
+      }
+    });
+
+    serde_json::from_value(ingress_route).expect("Invalid canary ingress route")
+  }
+}
+
+impl Star {
+  /// run the updated star as a preview, the stable deployment keeps serving the previous version
+  pub async fn start_rollout(&self, planets: &[Planet], api: StarRequestResolver) -> Result<()> {
+    let preview = Preview {
+      star: self,
+      planets,
+    };
+    let pp = PatchParams::apply("gws-api");
+
+    let _ = api
+      .deploy
+      .patch(
+        &preview.name(),
+        &pp,
+        &Patch::Apply(Deployment::from(&preview)),
+      )
+      .await?;
+
+    let _ = api
+      .svc
+      .patch(&preview.name(), &pp, &Patch::Apply(Service::from(&preview)))
+      .await?;
+
+    if self.strategy == StrategyKind::Canary {
+      let _ = api
+        .ingress_route
+        .patch(
+          &format!("star-{}-canary", self.id),
+          &pp,
+          &Patch::Apply(DynamicObject::from(&preview)),
+        )
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  /// remove the preview of the star and its canary route
+  pub async fn end_rollout(&self, api: &StarRequestResolver) -> Result<()> {
+    let preview_name = format!("star-{}-preview", self.id);
+    let canary_name = format!("star-{}-canary", self.id);
+    let dp = DeleteParams::default();
+
+    // the route goes first so no traffic reaches the preview while it is removed
+    if api.ingress_route.get_opt(&canary_name).await?.is_some() {
+      let _ = api.ingress_route.delete(&canary_name, &dp).await?;
+    }
+
+    if api.svc.get_opt(&preview_name).await?.is_some() {
+      let _ = api.svc.delete(&preview_name, &dp).await?;
+    }
+
+    if api.deploy.get_opt(&preview_name).await?.is_some() {
+      let _ = api.deploy.delete(&preview_name, &dp).await?;
+    }
+
+    Ok(())
+  }
+
+  /// send the traffic of a blue-green star to its preview,
+  /// the stable deployment keeps running the previous version until the promotion is confirmed
+  pub async fn switch_to_preview(&self, api: &StarRequestResolver) -> Result<()> {
+    let selector = json!({
+      "spec": {
+        "selector": {
+          "star_id": null,
+          "preview_star_id": self.id,
+        },
+      },
+    });
+
+    let _ = api
+      .svc
+      .patch(
+        &format!("star-{}", self.id),
+        &PatchParams::default(),
+        &Patch::Merge(selector),
+      )
+      .await?;
+
+    Ok(())
+  }
+
+  /// send the traffic of a blue-green star back to the stable deployment, which runs the star
+  /// as it is now even when an unconfirmed promotion already changed its template
+  pub async fn switch_to_stable(
+    &self,
+    planets: &[Planet],
+    api: &StarRequestResolver,
+  ) -> Result<()> {
+    let k8s_name = format!("star-{}", self.id);
+
+    let _ = api
+      .deploy
+      .replace(
+        &k8s_name,
+        &PostParams::default(),
+        &deployment(self, planets),
+      )
+      .await?;
+
+    let _ = api
+      .svc
+      .replace(&k8s_name, &PostParams::default(), &Service::from(self))
+      .await?;
+
+    Ok(())
+  }
+
+  /// whether every replica of the stable deployment runs its current template
+  async fn stable_available(&self, api: &StarRequestResolver) -> Result<bool> {
+    let deploy = api.deploy.get(&format!("star-{}", self.id)).await?;
+    let replicas = deploy
+      .spec
+      .and_then(|spec| spec.replicas)
+      .unwrap_or_default();
+
+    Ok(deploy.status.is_some_and(|status| {
+      status.observed_generation >= deploy.metadata.generation
+        && status.updated_replicas.unwrap_or_default() == replicas
+        && status.available_replicas.unwrap_or_default() == replicas
+    }))
+  }
+
+  /// move the previewed version to the stable deployment, mounting the planets the preview
+  /// ran with, false while the stable deployment of a switched blue-green star does not run
+  /// the new version yet and the preview keeps the traffic until a next promotion
+  pub async fn promote(&self, planets: &[Planet], api: StarRequestResolver) -> Result<bool> {
+    // replacing with the same template again starts no new rollout of the pods
+    let _ = api
+      .deploy
+      .replace(
+        &format!("star-{}", self.id),
+        &PostParams::default(),
+        &deployment(self, planets),
+      )
+      .await?;
+
+    if self.strategy == StrategyKind::BlueGreen && !self.stable_available(&api).await? {
+      return Ok(false);
+    }
+
+    // the traffic is back on the stable deployment before the preview goes away
+    self.update_network(&api).await?;
+    self.end_rollout(&api).await?;
+
+    Ok(true)
+  }
+}
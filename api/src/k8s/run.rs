@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use futures_util::AsyncBufRead;
+use k8s_openapi::api::{batch::v1::Job, core::v1::Pod};
+use kube::{
+  api::{ListParams, LogParams, PostParams},
+  Api, Client, Result,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::{
+  planet::Planet,
+  run::{Run, RunOutcome, RunStatus},
+  star::Star,
+};
+
+use super::star::{mount_planets, pod_spec};
+
+/// finished jobs are kept for a week, enough to read back their outcome and logs
+const JOB_TTL_SECONDS: i32 = 7 * 24 * 60 * 60;
+const POD_START_ATTEMPTS: u32 = 120;
+
+pub struct RunRequestResolver {
+  job: Api<Job>,
+  pod: Api<Pod>,
+}
+
+impl RunRequestResolver {
+  pub async fn try_default(galaxy_id: &Uuid) -> Result<Self> {
+    let client = Client::try_default().await?;
+    let galaxy_ns = format!("galaxy-{}", galaxy_id);
+
+    Ok(Self {
+      job: Api::namespaced(client.clone(), &galaxy_ns),
+      pod: Api::namespaced(client, &galaxy_ns),
+    })
+  }
+}
+
+/// job running the command with the pod spec of the star and its planets mounted
+fn job(run: &Run, star: &Star, planets: &[Planet]) -> Job {
+  let mut spec = pod_spec(star);
+  spec["restartPolicy"] = json!("Never");
+  spec["containers"][0]["command"] = json!(run.command);
+  mount_planets(&mut spec, planets);
+
+  // without the star_id label the pods stay out of the selector of the star service
+  let labels = json!({
+    "run_id": run.id,
+    "galaxy_id": star.galaxy_id,
+  });
+
+  let job = json!({
+    "apiVersion": "batch/v1",
+    "kind": "Job",
+    "metadata": {
+      "name": format!("run-{}", run.id),
+      "namespace": format!("galaxy-{}", star.galaxy_id),
+      "labels": labels,
+    },
+    "spec": {
+      "backoffLimit": 0,
+      "ttlSecondsAfterFinished": JOB_TTL_SECONDS,
+      "template": {
+        "metadata": {
+          "labels": labels,
+        },
+        "spec": spec
+      }
+    }
+  });
+
+  serde_json::from_value(job).expect("Invalid job")
+}
+
+impl Run {
+  fn job_selector(&self) -> ListParams {
+    ListParams::default().labels(&format!("job-name=run-{}", self.id))
+  }
+
+  pub async fn launch(
+    &self,
+    star: &Star,
+    planets: &[Planet],
+    api: RunRequestResolver,
+  ) -> Result<()> {
+    let _ = api
+      .job
+      .create(&PostParams::default(), &job(self, star, planets))
+      .await?;
+
+    Ok(())
+  }
+
+  /// outcome of the run if its job is over
+  pub async fn outcome(&self, api: &RunRequestResolver) -> Result<Option<RunOutcome>> {
+    let Some(job) = api.job.get_opt(&format!("run-{}", self.id)).await? else {
+      // the job was removed before its outcome was recorded
+      return Ok(Some(RunOutcome {
+        status: RunStatus::Failed,
+        exit_code: None,
+        error: Some("the job no longer exists".to_string()),
+        finished_at: Utc::now(),
+      }));
+    };
+
+    let pods = api.pod.list(&self.job_selector()).await?.items;
+
+    let terminated = pods
+      .into_iter()
+      .filter_map(|pod| pod.status)
+      .flat_map(|status| status.container_statuses.unwrap_or_default())
+      .find_map(|status| status.state.and_then(|state| state.terminated));
+
+    // a job past its deadline or its backoff limit fails without a container to report it
+    let failed = job
+      .status
+      .and_then(|status| status.conditions)
+      .unwrap_or_default()
+      .into_iter()
+      .find(|condition| condition.type_ == "Failed" && condition.status == "True");
+
+    if let Some(condition) = failed {
+      let error = match (condition.reason, condition.message) {
+        (Some(reason), Some(message)) => format!("{}: {}", reason, message),
+        (reason, message) => reason
+          .or(message)
+          .unwrap_or_else(|| "the job failed".to_string()),
+      };
+
+      return Ok(Some(RunOutcome {
+        status: RunStatus::Failed,
+        exit_code: terminated.map(|terminated| terminated.exit_code),
+        error: Some(error),
+        finished_at: condition
+          .last_transition_time
+          .map(|time| time.0)
+          .unwrap_or_else(Utc::now),
+      }));
+    }
+
+    Ok(terminated.map(|terminated| {
+      RunOutcome {
+        status: match terminated.exit_code {
+          0 => RunStatus::Succeeded,
+          _ => RunStatus::Failed,
+        },
+        exit_code: Some(terminated.exit_code),
+        error: None,
+        finished_at: terminated
+          .finished_at
+          .map(|time| time.0)
+          .unwrap_or_else(Utc::now),
+      }
+    }))
+  }
+
+  /// wait for the pod of the run to start and follow its output,
+  /// nothing is returned if the pod does not start in time
+  pub async fn logs(&self, api: RunRequestResolver) -> Result<Option<impl AsyncBufRead>> {
+    for _ in 0..POD_START_ATTEMPTS {
+      let pods = api.pod.list(&self.job_selector()).await?.items;
+
+      let started = pods.into_iter().find(|pod| {
+        pod
+          .status
+          .as_ref()
+          .and_then(|status| status.phase.as_deref())
+          .is_some_and(|phase| phase != "Pending")
+      });
+
+      if let Some(pod_name) = started.and_then(|pod| pod.metadata.name) {
+        let lp = LogParams {
+          follow: true,
+          ..Default::default()
+        };
+
+        return Ok(Some(api.pod.log_stream(&pod_name, &lp).await?));
+      }
+
+      actix_web::rt::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    Ok(None)
+  }
+}
@@ -0,0 +1,41 @@
+use serde_json::{json, Value};
+
+/// pod security standard enforced on the galaxy namespaces
+pub(super) const POD_SECURITY_LEVEL: &str = "restricted";
+
+/// labels enforcing the pod security standard on a namespace
+pub(super) fn pod_security_labels() -> Value {
+  json!({
+    "pod-security.kubernetes.io/enforce": POD_SECURITY_LEVEL,
+    "pod-security.kubernetes.io/enforce-version": "latest",
+    "pod-security.kubernetes.io/warn": POD_SECURITY_LEVEL,
+    "pod-security.kubernetes.io/warn-version": "latest",
+    "pod-security.kubernetes.io/audit": POD_SECURITY_LEVEL,
+    "pod-security.kubernetes.io/audit-version": "latest",
+  })
+}
+
+/// non root pod with the default seccomp profile of the runtime,
+/// the uid and gid of the image are used when none is given
+pub(super) fn pod_security_context(run_as_user: Option<i32>, run_as_group: Option<i32>) -> Value {
+  json!({
+    "runAsNonRoot": true,
+    "runAsUser": run_as_user,
+    "runAsGroup": run_as_group,
+    "fsGroup": run_as_group,
+    "seccompProfile": {
+      "type": "RuntimeDefault"
+    }
+  })
+}
+
+/// container without privilege escalation nor capabilities
+pub(super) fn container_security_context(read_only_root_filesystem: bool) -> Value {
+  json!({
+    "allowPrivilegeEscalation": false,
+    "readOnlyRootFilesystem": read_only_root_filesystem,
+    "capabilities": {
+      "drop": ["ALL"]
+    }
+  })
+}
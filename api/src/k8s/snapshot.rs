@@ -0,0 +1,118 @@
+use kube::{
+  api::{ApiResource, DeleteParams, DynamicObject, GroupVersionKind, PostParams},
+  Api, Client, Result,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::models::snapshot::{PlanetSnapshot, SnapshotOutcome, SnapshotStatus};
+
+pub(super) const SNAPSHOT_API_GROUP: &str = "snapshot.storage.k8s.io";
+
+fn volume_snapshot_resource() -> ApiResource {
+  ApiResource::from_gvk(&GroupVersionKind::gvk(
+    SNAPSHOT_API_GROUP,
+    "v1",
+    "VolumeSnapshot",
+  ))
+}
+
+pub(super) fn snapshot_name(snapshot_id: &Uuid) -> String {
+  format!("snapshot-{}", snapshot_id)
+}
+
+pub struct SnapshotRequestResolver {
+  snapshot: Api<DynamicObject>,
+}
+
+impl SnapshotRequestResolver {
+  pub async fn try_default(galaxy_id: &Uuid) -> Result<Self> {
+    let client = Client::try_default().await?;
+    let galaxy_ns = format!("galaxy-{}", galaxy_id);
+
+    Ok(Self {
+      snapshot: Api::namespaced_with(client, &galaxy_ns, &volume_snapshot_resource()),
+    })
+  }
+}
+
+impl From<&PlanetSnapshot> for DynamicObject {
+  fn from(snapshot: &PlanetSnapshot) -> Self {
+    let planet_id = snapshot
+      .planet_id
+      .expect("Planet not found when creating volume snapshot");
+
+    let volume_snapshot = json!({
+      "apiVersion": format!("{}/v1", SNAPSHOT_API_GROUP),
+      "kind": "VolumeSnapshot",
+      "metadata": {
+        "name": snapshot_name(&snapshot.id),
+        "namespace": format!("galaxy-{}", snapshot.galaxy_id),
+        "labels": {
+          "snapshot_id": snapshot.id,
+          "planet_id": planet_id,
+          "galaxy_id": snapshot.galaxy_id
+        }
+      },
+      "spec": {
+        "source": {
+          "persistentVolumeClaimName": format!("planet-{}", planet_id)
+        }
+      }
+    });
+
+    serde_json::from_value(volume_snapshot).expect("Invalid volume snapshot")
+  }
+}
+
+impl PlanetSnapshot {
+  /// copy the claim of the planet with the snapshot class of its tier
+  pub async fn take(&self, snapshot_class: &str, api: &SnapshotRequestResolver) -> Result<()> {
+    let mut volume_snapshot = DynamicObject::from(self);
+    volume_snapshot.data["spec"]["volumeSnapshotClassName"] = json!(snapshot_class);
+
+    let _ = api
+      .snapshot
+      .create(&PostParams::default(), &volume_snapshot)
+      .await?;
+
+    Ok(())
+  }
+
+  /// state of the snapshot if the snapshot controller is done with it
+  pub async fn outcome(&self, api: &SnapshotRequestResolver) -> Result<Option<SnapshotOutcome>> {
+    // the volume snapshot was removed before it was ready
+    let Some(volume_snapshot) = api.snapshot.get_opt(&snapshot_name(&self.id)).await? else {
+      return Ok(Some(SnapshotOutcome {
+        status: SnapshotStatus::Failed,
+        error: Some("the volume snapshot no longer exists".to_string()),
+      }));
+    };
+
+    let status = &volume_snapshot.data["status"];
+
+    if let Some(message) = status["error"]["message"].as_str() {
+      return Ok(Some(SnapshotOutcome {
+        status: SnapshotStatus::Failed,
+        error: Some(message.to_string()),
+      }));
+    }
+
+    let ready = status["readyToUse"].as_bool().unwrap_or_default();
+
+    Ok(ready.then_some(SnapshotOutcome {
+      status: SnapshotStatus::Ready,
+      error: None,
+    }))
+  }
+
+  pub async fn remove(&self, api: &SnapshotRequestResolver) -> Result<()> {
+    let name = snapshot_name(&self.id);
+
+    if api.snapshot.get_opt(&name).await?.is_some() {
+      let _ = api.snapshot.delete(&name, &DeleteParams::default()).await?;
+    }
+
+    Ok(())
+  }
+}
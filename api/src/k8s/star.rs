@@ -1,25 +1,45 @@
 use k8s_openapi::api::{
   apps::v1::Deployment,
+  batch::v1::CronJob,
   core::v1::{ConfigMap, Secret, Service},
   networking::v1::Ingress,
 };
 use kube::{
-  api::{DeleteParams, Patch, PatchParams, PostParams},
+  api::{DeleteParams, DynamicObject, Patch, PatchParams, PostParams},
   Api, Client, Result,
 };
-use serde_json::json;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
 use uuid::Uuid;
 
-use crate::models::star::Star;
-
-use super::ResourceBind;
+use crate::models::planet::Planet;
+use crate::models::star::{
+  ConcurrencyPolicy, ContainerKind, Star, StarContainer, StarKind, StarState, StrategyKind,
+};
+use crate::registry::NebulaReference;
+
+use super::{
+  activator::{
+    Activator, ActivatorRequestResolver, ACTIVATOR_NAME, ACTIVATOR_PORT, IDLE_ANNOTATION,
+  },
+  file::{file_volumes, remove_star_files},
+  placement::apply_placement,
+  private_host, public_host,
+  rollout::ingress_route_resource,
+  security::{container_security_context, pod_security_context},
+  ResourceBind,
+};
 
 pub struct StarRequestResolver {
   secret: Api<Secret>,
-  deploy: Api<Deployment>,
-  svc: Api<Service>,
+  config_map: Api<ConfigMap>,
+  pub(super) deploy: Api<Deployment>,
+  cronjob: Api<CronJob>,
+  pub(super) svc: Api<Service>,
   ingress: Api<Ingress>,
+  pub(super) ingress_route: Api<DynamicObject>,
   coredns_custom: Api<ConfigMap>,
+  activator: ActivatorRequestResolver,
 }
 
 impl StarRequestResolver {
@@ -29,12 +49,151 @@ impl StarRequestResolver {
 
     Ok(Self {
       secret: Api::namespaced(client.clone(), &galaxy_ns),
+      config_map: Api::namespaced(client.clone(), &galaxy_ns),
       deploy: Api::namespaced(client.clone(), &galaxy_ns),
+      cronjob: Api::namespaced(client.clone(), &galaxy_ns),
       svc: Api::namespaced(client.clone(), &galaxy_ns),
       ingress: Api::namespaced(client.clone(), &galaxy_ns),
-      coredns_custom: Api::namespaced(client, "kube-system"),
+      ingress_route: Api::namespaced_with(client.clone(), &galaxy_ns, &ingress_route_resource()),
+      coredns_custom: Api::namespaced(client.clone(), "kube-system"),
+      activator: ActivatorRequestResolver::new(client, &galaxy_ns),
     })
   }
+
+  /// native sidecars are served from kubernetes 1.29
+  pub async fn native_sidecars(&self) -> Result<bool> {
+    let version = self
+      .deploy
+      .clone()
+      .into_client()
+      .apiserver_version()
+      .await?;
+    // managed clusters report minors like "29+"
+    let major = version.major.parse::<u32>().unwrap_or_default();
+    let minor = version
+      .minor
+      .trim_end_matches('+')
+      .parse::<u32>()
+      .unwrap_or_default();
+
+    Ok((major, minor) >= (1, 29))
+  }
+}
+
+impl Star {
+  pub(super) fn replicas(&self) -> i32 {
+    match self.state {
+      StarState::Running => 1,
+      StarState::Stopped => 0,
+    }
+  }
+
+  /// scale the star deployment to match its desired state, cron stars are suspended instead
+  pub async fn scale(&self, api: StarRequestResolver) -> Result<()> {
+    let k8s_name = format!("star-{}", self.id);
+
+    if self.kind == StarKind::Cron {
+      let patch = json!({
+        "spec": {
+          "suspend": self.state == StarState::Stopped
+        }
+      });
+
+      let _ = api
+        .cronjob
+        .patch(&k8s_name, &PatchParams::default(), &Patch::Merge(patch))
+        .await?;
+
+      return Ok(());
+    }
+
+    // the idle annotation is dropped so the activator does not wake a stopped star
+    let patch = json!({
+      "metadata": {
+        "annotations": {
+          IDLE_ANNOTATION: null
+        }
+      },
+      "spec": {
+        "replicas": self.replicas()
+      }
+    });
+
+    let _ = api
+      .deploy
+      .patch(&k8s_name, &PatchParams::default(), &Patch::Merge(patch))
+      .await?;
+
+    Ok(())
+  }
+
+  pub async fn restart(&self, api: StarRequestResolver) -> Result<()> {
+    // every run of a cron star already starts from scratch
+    if self.kind == StarKind::Cron {
+      return Ok(());
+    }
+
+    let _ = api.deploy.restart(&format!("star-{}", self.id)).await?;
+
+    Ok(())
+  }
+
+  /// service, activator, ingress and private domain of the star, everything but its pods
+  pub(super) async fn update_network(&self, api: &StarRequestResolver) -> Result<()> {
+    let k8s_name = format!("star-{}", self.id);
+    let pp = PostParams::default();
+
+    let _ = api
+      .svc
+      .replace(&k8s_name, &pp, &Service::from(self))
+      .await?;
+
+    let activator = Activator::new(self.galaxy_id);
+
+    if self.idle_timeout.is_some() {
+      activator.bind(self, &api.activator).await?;
+    } else {
+      activator.unbind(self, &api.activator).await?;
+    }
+
+    if api.ingress.get_opt(&k8s_name).await?.is_some() {
+      if self.public_domain.is_some() {
+        let _ = api
+          .ingress
+          .replace(&k8s_name, &pp, &Ingress::from(self))
+          .await?;
+      } else {
+        let _ = api.ingress.delete(&k8s_name, &Default::default()).await?;
+      }
+    } else {
+      if self.public_domain.is_some() {
+        let _ = api
+          .ingress
+          .create(&Default::default(), &Ingress::from(self))
+          .await?;
+      }
+    }
+
+    let pp = PatchParams::apply("gws-api");
+    let patch = Patch::Apply(if self.private_domain.is_some() {
+      ConfigMap::from(self)
+    } else {
+      let mut coredns_custom = api.coredns_custom.get("coredns-custom").await?;
+
+      if let Some(data) = coredns_custom.data.as_mut() {
+        let _ = data.remove(&format!("star-{}.override", self.id));
+      }
+
+      coredns_custom
+    });
+
+    let _ = api
+      .coredns_custom
+      .patch("coredns-custom", &pp, &patch)
+      .await?;
+
+    Ok(())
+  }
 }
 
 impl From<&Star> for Secret {
@@ -53,99 +212,312 @@ impl From<&Star> for Secret {
   }
 }
 
+/// labels shared by all the resources of a star
+fn labels(star: &Star) -> Value {
+  json!({
+    "star_name": star.name,
+    "star_id": star.id,
+    "galaxy_id": star.galaxy_id,
+  })
+}
+
+/// init container or native sidecar, sidecars restart and outlive the init phase,
+/// the restart policy of init containers needs kubernetes 1.29
+fn container(container: &StarContainer) -> Value {
+  json!({
+    "name": container.name,
+    "image": container.image,
+    "command": (!container.command.is_empty()).then_some(&container.command),
+    "restartPolicy": match container.kind {
+      ContainerKind::Init => None,
+      ContainerKind::Sidecar => Some("Always"),
+    },
+    "securityContext": container_security_context(false),
+    "env": container
+      .variables
+      .iter()
+      .map(|(name, value)| json!({ "name": name, "value": value }))
+      .collect::<Vec<_>>(),
+    "volumeMounts": container
+      .planets
+      .iter()
+      .map(|mount| json!({
+        "name": format!("planet-volume-{}", mount.planet_id),
+        "mountPath": mount.path
+      }))
+      .collect::<Vec<_>>()
+  })
+}
+
+/// volumes of the planets mounted by the containers of the star
+fn container_volumes(star: &Star) -> Vec<Value> {
+  let planet_ids = star
+    .containers
+    .iter()
+    .flat_map(|container| container.planets.iter().map(|mount| mount.planet_id))
+    .collect::<BTreeSet<_>>();
+
+  planet_ids
+    .into_iter()
+    .map(|planet_id| {
+      json!({
+        "name": format!("planet-volume-{}", planet_id),
+        "persistentVolumeClaim": {
+          "claimName": format!("planet-{}", planet_id)
+        }
+      })
+    })
+    .collect()
+}
+
+/// image of the star container, pinned to the resolved digest of the nebula if any
+fn nebula_image(star: &Star) -> String {
+  match (&star.nebula_digest, NebulaReference::parse(&star.nebula)) {
+    (Some(digest), Ok(reference)) => reference.pinned(digest),
+    _ => star.nebula.clone(),
+  }
+}
+
+/// variable groups first, when a variable is defined twice the last source wins
+fn env_from(star: &Star) -> Vec<Value> {
+  let mut sources = star
+    .variable_groups
+    .iter()
+    .map(|group_id| {
+      json!({
+        // the group may be deleted while the star is still rolling out
+        "secretRef": {
+          "name": format!("group-{}-vars", group_id),
+          "optional": true
+        }
+      })
+    })
+    .collect::<Vec<_>>();
+
+  sources.push(json!({
+    "secretRef": {
+      "name": format!("star-{}-vars", star.id)
+    }
+  }));
+  sources.push(json!({
+    // created with the first plain variable of the star
+    "configMapRef": {
+      "name": format!("star-{}-vars", star.id),
+      "optional": true
+    }
+  }));
+
+  sources
+}
+
+/// pod spec running the star nebula, shared by services and cron stars
+pub(super) fn pod_spec(star: &Star) -> Value {
+  let (file_volumes, file_mounts) = file_volumes(star);
+  let mut volumes = container_volumes(star);
+  volumes.extend(file_volumes);
+
+  let mut spec = json!({
+    "enableServiceLinks": false,
+    "securityContext": pod_security_context(star.run_as_user, star.run_as_group),
+    "initContainers": star.containers.iter().map(container).collect::<Vec<_>>(),
+    "volumes": volumes,
+    "containers": [
+      {
+        "name": format!("star-container-{}", star.id),
+        "image": nebula_image(star),
+        "env": [
+          {
+            "name": "ADDRESS",
+            "value": "0.0.0.0"
+          },
+          {
+            "name": "PORT",
+            "value": star.port.to_string()
+          }
+        ],
+        "envFrom": env_from(star),
+        "volumeMounts": file_mounts,
+        "ports": [
+          {
+            "containerPort": star.port
+          }
+        ],
+        "securityContext": container_security_context(star.read_only_root_filesystem)
+      }
+    ],
+  });
+
+  apply_placement(&mut spec, star);
+
+  spec
+}
+
+/// mount the planets of the star into the star container of a pod spec
+pub(super) fn mount_planets(spec: &mut Value, planets: &[Planet]) {
+  let mounts = spec["containers"][0]["volumeMounts"]
+    .as_array_mut()
+    .expect("missing container volume mounts");
+  mounts.extend(planets.iter().map(|planet| {
+    json!({
+      "name": format!("planet-volume-{}", planet.id),
+      "mountPath": planet.path
+    })
+  }));
+
+  // the containers of the star may already mount some of the planets
+  let volumes = spec["volumes"].as_array_mut().expect("missing pod volumes");
+  for planet in planets {
+    let name = format!("planet-volume-{}", planet.id);
+
+    if volumes.iter().all(|volume| volume["name"] != name.as_str()) {
+      volumes.push(json!({
+        "name": name,
+        "persistentVolumeClaim": {
+          "claimName": format!("planet-{}", planet.id)
+        }
+      }));
+    }
+  }
+}
+
+/// maxSurge and maxUnavailable are either a number of pods or a percentage
+fn int_or_string(value: &Option<String>) -> Value {
+  match value.as_deref().map(|value| (value, value.parse::<i32>())) {
+    Some((_, Ok(pods))) => json!(pods),
+    Some((percent, Err(_))) => json!(percent),
+    None => Value::Null,
+  }
+}
+
+/// blue-green and canary stars roll out through a preview deployment,
+/// their stable deployment is replaced only on promotion
+fn strategy(star: &Star) -> Value {
+  match star.strategy {
+    StrategyKind::Recreate => json!({ "type": "Recreate" }),
+    StrategyKind::RollingUpdate => json!({
+      "type": "RollingUpdate",
+      "rollingUpdate": {
+        "maxSurge": int_or_string(&star.max_surge),
+        "maxUnavailable": int_or_string(&star.max_unavailable),
+      }
+    }),
+    StrategyKind::BlueGreen | StrategyKind::Canary => json!({ "type": "RollingUpdate" }),
+  }
+}
+
+/// deployment of the star with the planets mounted into the star container
+pub(super) fn deployment(star: &Star, planets: &[Planet]) -> Deployment {
+  let mut spec = pod_spec(star);
+  mount_planets(&mut spec, planets);
+
+  let deployment = json!({
+    "apiVersion": "apps/v1",
+    "kind": "Deployment",
+    "metadata": {
+      "name": format!("star-{}", star.id),
+      "namespace": format!("galaxy-{}", star.galaxy_id),
+      "labels": labels(star),
+      "annotasions": {
+        "kubernetes.io/change-cause": "gws api"
+      }
+    },
+    "spec": {
+      "replicas": star.replicas(),
+      "strategy": strategy(star),
+      "selector": {
+        "matchLabels": {
+          "star_id": star.id,
+        },
+      },
+      "template": {
+        "metadata": {
+          "labels": labels(star),
+        },
+        "spec": spec
+      },
+    },
+  });
+
+  serde_json::from_value(deployment).expect("Invalid deployment")
+}
+
 impl From<&Star> for Deployment {
   fn from(star: &Star) -> Self {
-    let deployment = json!({
-      "apiVersion": "apps/v1",
-      "kind": "Deployment",
+    deployment(star, &[])
+  }
+}
+
+impl From<&Star> for CronJob {
+  fn from(star: &Star) -> Self {
+    let schedule = star
+      .schedule
+      .as_ref()
+      .expect("Schedule not found when creating cronjob");
+
+    let mut template_spec = pod_spec(star);
+    template_spec["restartPolicy"] = json!("OnFailure");
+
+    let cronjob = json!({
+      "apiVersion": "batch/v1",
+      "kind": "CronJob",
       "metadata": {
         "name": format!("star-{}", star.id),
         "namespace": format!("galaxy-{}", star.galaxy_id),
-        "labels": {
-          "star_name": star.name,
-          "star_id": star.id,
-          "galaxy_id": star.galaxy_id,
-        },
-        "annotasions": {
-          "kubernetes.io/change-cause": "gws api"
-        }
+        "labels": labels(star),
       },
       "spec": {
-        "replicas": 1,
-        "selector": {
-          "matchLabels": {
-            "star_id": star.id,
-          },
+        "schedule": schedule,
+        "timeZone": star.timezone,
+        "concurrencyPolicy": match star.concurrency_policy {
+          ConcurrencyPolicy::Allow => "Allow",
+          ConcurrencyPolicy::Forbid => "Forbid",
+          ConcurrencyPolicy::Replace => "Replace",
         },
-        "template": {
+        "suspend": star.state == StarState::Stopped,
+        "successfulJobsHistoryLimit": star.successful_jobs_history_limit,
+        "failedJobsHistoryLimit": star.failed_jobs_history_limit,
+        "jobTemplate": {
           "metadata": {
-            "labels": {
-              "star_name": star.name,
-              "star_id": star.id,
-              "galaxy_id": star.galaxy_id
-            },
+            "labels": labels(star),
           },
           "spec": {
-            "enableServiceLinks": false,
-            "containers": [
-              {
-                "name": format!("star-container-{}", star.id),
-                "image": star.nebula.to_lowercase(),
-                "env": [
-                  {
-                    "name": "ADDRESS",
-                    "value": "0.0.0.0"
-                  },
-                  {
-                    "name": "PORT",
-                    "value": star.port.to_string()
-                  }
-                ],
-                "envFrom": [
-                  {
-                    "secretRef": {
-                      "name": format!("star-{}-vars", star.id)
-                    }
-                  }
-                ],
-                "ports": [
-                  {
-                    "containerPort": star.port
-                  }
-                ]
-              }
-            ],
+            "template": {
+              "metadata": {
+                "labels": labels(star),
+              },
+              "spec": template_spec
+            }
           }
-        },
-      },
+        }
+      }
     });
 
-    serde_json::from_value(deployment).expect("Invalid deployment")
+    serde_json::from_value(cronjob).expect("Invalid cronjob")
   }
 }
 
 impl From<&Star> for Service {
   fn from(star: &Star) -> Self {
+    // idle stars are reached through the activator, which only speaks http
+    let (selector, target_port) = match star.idle_timeout {
+      Some(_) => (json!({ "app": ACTIVATOR_NAME }), ACTIVATOR_PORT),
+      None => (json!({ "star_id": star.id }), star.port),
+    };
+
     let svc = json!({
       "apiVersion": "v1",
       "kind": "Service",
       "metadata": {
         "name": format!("star-{}", star.id),
         "namespace": format!("galaxy-{}", star.galaxy_id),
-        "labels": {
-          "star_name": star.name,
-          "star_id": star.id,
-          "galaxy_id": star.galaxy_id,
-        },
+        "labels": labels(star),
       },
       "spec": {
-        "selector": {
-          "star_id": star.id,
-        },
+        "selector": selector,
         "ports": [
           {
             "port": star.port,
-            "targetPort": star.port,
+            "targetPort": target_port,
           },
         ],
       },
@@ -171,12 +543,12 @@ impl From<&Star> for ConfigMap {
       },
       "data": {
         format!("star-{}.override", star.id): format!(
-          r#"template IN ANY {}.gws.internal {{
-              match "^{}\.gws\.internal\.$"
+          r#"template IN ANY {} {{
+              match "^{}\.$"
               answer "{{{{ .Name }}}} 60 IN CNAME star-{}.{{{{ .Meta \"kubernetes/client-namespace\" }}}}.svc.cluster.local"
             }}"#,
-          private_domain,
-          private_domain,
+          private_host(private_domain),
+          private_host(private_domain).replace('.', r"\."),
           star.id,
         )
       }
@@ -199,11 +571,7 @@ impl From<&Star> for Ingress {
       "metadata": {
         "name": format!("star-{}", star.id),
         "namespace": format!("galaxy-{}", star.galaxy_id),
-        "labels": {
-          "star_name": star.name,
-          "star_id": star.id,
-          "galaxy_id": star.galaxy_id,
-        },
+        "labels": labels(star),
         "annotations": {
           "traefik.ingress.kubernetes.io/router.middlewares": "default-redirect@kubernetescrd",
           "traefik.ingress.kubernetes.io/router.entrypoints": "web, websecure"
@@ -213,14 +581,14 @@ impl From<&Star> for Ingress {
         "tls": [
           {
             "hosts": [
-              format!("{}.localhost", public_domain),
+              public_host(public_domain),
             ],
             "secretName": "stars-tls-secret-replica"
           }
         ],
         "rules": [
           {
-            "host": format!("{}.localhost", public_domain),
+            "host": public_host(public_domain),
             "http": {
               "paths": [
                 {
@@ -256,6 +624,15 @@ impl ResourceBind for Star {
       .create(&Default::default(), &Secret::from(self))
       .await?;
 
+    if self.kind == StarKind::Cron {
+      let _ = api
+        .cronjob
+        .create(&Default::default(), &CronJob::from(self))
+        .await?;
+
+      return Ok(());
+    }
+
     let _ = api
       .deploy
       .create(&Default::default(), &Deployment::from(self))
@@ -266,6 +643,12 @@ impl ResourceBind for Star {
       .create(&Default::default(), &Service::from(self))
       .await?;
 
+    if self.idle_timeout.is_some() {
+      Activator::new(self.galaxy_id)
+        .bind(self, &api.activator)
+        .await?;
+    }
+
     if self.public_domain.is_some() {
       let _ = api
         .ingress
@@ -288,71 +671,56 @@ impl ResourceBind for Star {
   }
 
   async fn update(&self, api: Self::RequestResolver) -> Result<()> {
-    let k8s_name = format!("star-{}", self.id.to_string());
+    let k8s_name = format!("star-{}", self.id);
     let pp = PostParams::default();
 
+    if self.kind == StarKind::Cron {
+      let _ = api
+        .cronjob
+        .replace(&k8s_name, &pp, &CronJob::from(self))
+        .await?;
+
+      return Ok(());
+    }
+
     let _ = api
       .deploy
       .replace(&k8s_name, &pp, &Deployment::from(self))
       .await?;
 
-    let _ = api
-      .svc
-      .replace(&k8s_name, &pp, &Service::from(self))
-      .await?;
+    self.update_network(&api).await
+  }
 
-    if api.ingress.get_opt(&k8s_name).await?.is_some() {
-      if self.public_domain.is_some() {
-        let _ = api
-          .ingress
-          .replace(&k8s_name, &pp, &Ingress::from(self))
-          .await?;
-      } else {
-        let _ = api.ingress.delete(&k8s_name, &Default::default()).await?;
-      }
-    } else {
-      if self.public_domain.is_some() {
-        let _ = api
-          .ingress
-          .create(&Default::default(), &Ingress::from(self))
-          .await?;
-      }
-    }
+  async fn delete(&self, api: Self::RequestResolver) -> Result<()> {
+    let k8s_name = format!("star-{}", self.id);
+    let dp = DeleteParams::default();
 
-    let pp = PatchParams::apply("gws-api");
-    let patch = Patch::Apply(if self.private_domain.is_some() {
-      ConfigMap::from(self)
-    } else {
-      let mut coredns_custom = api.coredns_custom.get("coredns-custom").await?;
+    let vars_name = format!("star-{}-vars", self.id);
 
-      if let Some(data) = coredns_custom.data.as_mut() {
-        let _ = data.remove(&format!("star-{}.override", self.id));
-      }
+    let _ = api.secret.delete(&vars_name, &dp).await?;
 
-      coredns_custom
-    });
+    if api.config_map.get_opt(&vars_name).await?.is_some() {
+      let _ = api.config_map.delete(&vars_name, &dp).await?;
+    }
 
-    let _ = api
-      .coredns_custom
-      .patch("coredns-custom", &pp, &patch)
-      .await?;
+    remove_star_files(&self.id, &api.secret, &api.config_map).await?;
 
-    Ok(())
-  }
+    if self.kind == StarKind::Cron {
+      let _ = api.cronjob.delete(&k8s_name, &dp).await?;
 
-  async fn delete(&self, api: Self::RequestResolver) -> Result<()> {
-    let k8s_name = format!("star-{}", self.id.to_string());
-    let dp = DeleteParams::default();
+      return Ok(());
+    }
 
-    let _ = api
-      .secret
-      .delete(&format!("star-{}-vars", self.id), &dp)
-      .await?;
+    self.end_rollout(&api).await?;
 
     let _ = api.deploy.delete(&k8s_name, &dp).await?;
 
     let _ = api.svc.delete(&k8s_name, &dp).await?;
 
+    Activator::new(self.galaxy_id)
+      .unbind(self, &api.activator)
+      .await?;
+
     if api.ingress.get_opt(&k8s_name).await?.is_some() {
       let _ = api.ingress.delete(&k8s_name, &dp).await?;
     }
@@ -1,17 +1,24 @@
-use k8s_openapi::api::{apps::v1::Deployment, core::v1::Secret};
-use kube::{
-  api::{Patch, PatchParams},
-  Api, Client, Result,
+use std::collections::BTreeMap;
+
+use k8s_openapi::{
+  api::{
+    apps::v1::Deployment,
+    core::v1::{ConfigMap, Secret},
+  },
+  ByteString,
 };
+use kube::{api::PostParams, Api, Client, Result};
 use serde_json::json;
 use uuid::Uuid;
 
-use crate::models::var::Variable;
-
-use super::ResourceBind;
+use crate::{
+  interpolation::RenderedVariable,
+  models::var::{PendingVariableChange, VariableChange, VariableKind},
+};
 
 pub struct VariableRequestResolver {
   secret: Api<Secret>,
+  config_map: Api<ConfigMap>,
   deploy: Api<Deployment>,
   galaxy_id: Uuid,
 }
@@ -23,73 +30,186 @@ impl VariableRequestResolver {
 
     Ok(Self {
       secret: Api::namespaced(client.clone(), &galaxy_ns),
+      config_map: Api::namespaced(client.clone(), &galaxy_ns),
       deploy: Api::namespaced(client, &galaxy_ns),
       galaxy_id,
     })
   }
 }
 
-impl Variable {
-  async fn patch(&self, api: VariableRequestResolver) -> Result<()> {
-    let secret_name = format!("star-{}-vars", self.star_id);
-    let deploy_name = format!("star-{}", self.star_id);
-    let pp = PatchParams::apply("gws-api");
-
-    let secret = json!({
-      "apiVersion": "v1",
-      "kind": "Secret",
-      "metadata": {
-        "name": &secret_name,
-        "namespace": format!("galaxy-{}", api.galaxy_id),
-      },
-      "stringData": {
-        &self.name: self.value
-      }
-    });
+/// the secret and the config map of a star share the same name
+fn vars_name(star_id: &Uuid) -> String {
+  format!("star-{}-vars", star_id)
+}
 
-    let secret: Secret = serde_json::from_value(secret).expect("Invalid secret");
-
-    let _ = api
-      .secret
-      .patch(&secret_name, &pp, &Patch::Apply(secret))
-      .await?;
+async fn restart_star(star_id: &Uuid, api: &VariableRequestResolver) -> Result<()> {
+  let deploy_name = format!("star-{}", star_id);
 
+  // cron stars read the variables on the next run
+  if api.deploy.get_opt(&deploy_name).await?.is_some() {
     let _ = api.deploy.restart(&deploy_name).await?;
+  }
+
+  Ok(())
+}
+
+fn empty_config_map(star_id: &Uuid, api: &VariableRequestResolver) -> ConfigMap {
+  let config_map = json!({
+    "apiVersion": "v1",
+    "kind": "ConfigMap",
+    "metadata": {
+      "name": vars_name(star_id),
+      "namespace": format!("galaxy-{}", api.galaxy_id),
+    },
+  });
+
+  serde_json::from_value(config_map).expect("Invalid config map")
+}
+
+/// secret data and config map data holding the rendered variables
+fn vars_data(
+  vars: &[RenderedVariable],
+) -> (BTreeMap<String, ByteString>, BTreeMap<String, String>) {
+  let mut secret_data = BTreeMap::new();
+  let mut config_data = BTreeMap::new();
+
+  for var in vars {
+    match var.kind {
+      VariableKind::Secret => {
+        secret_data.insert(var.name.clone(), ByteString(var.value.clone().into_bytes()));
+      }
+      VariableKind::Plain => {
+        config_data.insert(var.name.clone(), var.value.clone());
+      }
+    }
+  }
+
+  (secret_data, config_data)
+}
 
-    Ok(())
+/// kind and value of every variable in the secret and the config map of the star
+fn deployed_values(
+  secret: Option<&Secret>,
+  config_map: Option<&ConfigMap>,
+) -> BTreeMap<String, (VariableKind, Vec<u8>)> {
+  let mut values = BTreeMap::new();
+
+  for (name, value) in secret
+    .and_then(|secret| secret.data.clone())
+    .unwrap_or_default()
+  {
+    values.insert(name, (VariableKind::Secret, value.0));
   }
+
+  for (name, value) in config_map
+    .and_then(|config_map| config_map.data.clone())
+    .unwrap_or_default()
+  {
+    values.insert(name, (VariableKind::Plain, value.into_bytes()));
+  }
+
+  values
 }
 
-impl ResourceBind for Variable {
-  type RequestResolver = VariableRequestResolver;
+/// variables of the star that differ from what its pods read
+pub async fn pending_star_vars(
+  star_id: &Uuid,
+  vars: &[RenderedVariable],
+  api: &VariableRequestResolver,
+) -> Result<Vec<PendingVariableChange>> {
+  let vars_name = vars_name(star_id);
+
+  let secret = api.secret.get_opt(&vars_name).await?;
+  let config_map = api.config_map.get_opt(&vars_name).await?;
+  let mut deployed = deployed_values(secret.as_ref(), config_map.as_ref());
+
+  let mut changes = Vec::new();
+
+  for var in vars {
+    let change = match deployed.remove(&var.name) {
+      None => VariableChange::Added,
+      Some((kind, value)) if kind != var.kind || value != var.value.as_bytes() => {
+        VariableChange::Changed
+      }
+      Some(_) => continue,
+    };
 
-  async fn create(&self, api: Self::RequestResolver) -> Result<()> {
-    self.patch(api).await
+    changes.push(PendingVariableChange {
+      name: var.name.clone(),
+      kind: var.kind,
+      change,
+    });
   }
 
-  async fn update(&self, api: Self::RequestResolver) -> Result<()> {
-    self.patch(api).await
+  changes.extend(
+    deployed
+      .into_iter()
+      .map(|(name, (kind, _))| PendingVariableChange {
+        name,
+        kind,
+        change: VariableChange::Removed,
+      }),
+  );
+  changes.sort_by(|a, b| a.name.cmp(&b.name));
+
+  Ok(changes)
+}
+
+/// write the rendered variables of a star in its secret and its config map,
+/// the star restarts once and only when a value changed
+pub async fn sync_star_vars(
+  star_id: &Uuid,
+  vars: &[RenderedVariable],
+  api: &VariableRequestResolver,
+) -> Result<()> {
+  let vars_name = vars_name(star_id);
+  let (secret_data, config_data) = vars_data(vars);
+
+  let mut changed = false;
+
+  let mut secret = api.secret.get(&vars_name).await?;
+
+  if secret.data.clone().unwrap_or_default() != secret_data {
+    secret.data = Some(secret_data);
+    secret.string_data = None;
+
+    let _ = api
+      .secret
+      .replace(&vars_name, &PostParams::default(), &secret)
+      .await?;
+
+    changed = true;
   }
 
-  async fn delete(&self, api: Self::RequestResolver) -> Result<()> {
-    let secret_name = format!("star-{}-vars", self.star_id);
-    let deploy_name = format!("star-{}", self.star_id);
+  match api.config_map.get_opt(&vars_name).await? {
+    Some(mut config_map) if config_map.data.clone().unwrap_or_default() != config_data => {
+      config_map.data = Some(config_data);
 
-    let mut secret = api.secret.get(&secret_name).await?;
+      let _ = api
+        .config_map
+        .replace(&vars_name, &PostParams::default(), &config_map)
+        .await?;
 
-    if let Some(ref mut vars) = secret.data {
-      let var = vars.remove(&self.name);
+      changed = true;
+    }
+    // stars created before the plain variables have no config map
+    None if !config_data.is_empty() => {
+      let mut config_map = empty_config_map(star_id, api);
+      config_map.data = Some(config_data);
 
       let _ = api
-        .secret
-        .replace(&secret_name, &Default::default(), &secret)
+        .config_map
+        .create(&PostParams::default(), &config_map)
         .await?;
 
-      if var.is_some() {
-        let _ = api.deploy.restart(&deploy_name).await?;
-      }
+      changed = true;
     }
+    _ => {}
+  }
 
-    Ok(())
+  if changed {
+    restart_star(star_id, api).await?;
   }
+
+  Ok(())
 }
@@ -1,8 +1,17 @@
 pub(crate) mod k8s;
 
 pub mod auth;
+pub mod config;
 pub mod database;
+pub mod encryption;
+pub mod env_file;
 pub mod error;
+pub mod interpolation;
 pub mod models;
 pub mod openapi;
+pub mod placement;
+pub mod registry;
 pub mod routes;
+pub mod secrets;
+pub mod storage;
+pub mod workers;
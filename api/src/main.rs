@@ -24,6 +24,22 @@ async fn main() -> std::io::Result<()> {
     .await
     .expect("Unable connect to database");
 
+  // invalid placement profiles, storage tiers and master keys are reported before serving any request
+  api::placement::profiles();
+  api::storage::tiers();
+  api::encryption::keyring();
+
+  // the variables written in clear before the encryption are sealed before serving any request
+  let mut tx = pool.begin().await.expect("Unable to start the transaction");
+  api::models::var::Variable::seal_all_in_clear(&mut tx)
+    .await
+    .expect("Unable to seal the variables written in clear");
+  tx.commit()
+    .await
+    .expect("Unable to seal the variables written in clear");
+
+  api::workers::spawn(Arc::clone(&pool));
+
   HttpServer::new(move || {
     let cors = Cors::default()
       .allowed_origin("http://localhost:3000")
@@ -40,8 +56,20 @@ async fn main() -> std::io::Result<()> {
           .configure(api::routes::user::config)
           .configure(api::routes::galaxy::config)
           .configure(api::routes::star::config)
+          .configure(api::routes::cron::config)
+          .configure(api::routes::run::config)
+          .configure(api::routes::build::config)
+          .configure(api::routes::image::config)
+          .configure(api::routes::placement::config)
+          .configure(api::routes::storage::config)
+          .configure(api::routes::rollout::config)
+          .configure(api::routes::event::config)
+          .configure(api::routes::metrics::config)
           .configure(api::routes::var::config)
-          .configure(api::routes::planet::config),
+          .configure(api::routes::group::config)
+          .configure(api::routes::file::config)
+          .configure(api::routes::planet::config)
+          .configure(api::routes::snapshot::config),
       )
       .wrap(Logger::default())
   })
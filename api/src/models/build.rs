@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_more::From;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::database::{Connection, DbResult};
+
+use super::{
+  star::{Star, StarPath},
+  CrudOperations,
+};
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Build {
+  pub id: Uuid,
+  pub status: BuildStatus,
+  /// tagged reference of the image in the platform registry
+  #[schema(example = "k3d-gws-registry:5000/galaxy-0c6b6f4e/star-4f4f1d2a:9b1e7d3c")]
+  pub image: String,
+  #[schema(example = "sha256:4b5a1e2f")]
+  pub digest: Option<String>,
+  pub started_at: DateTime<Utc>,
+  pub finished_at: Option<DateTime<Utc>>,
+  /// duration of the finished build in seconds
+  #[schema(minimum = 0)]
+  pub duration: Option<i32>,
+  pub star_id: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildStatus {
+  Running,
+  Succeeded,
+  Failed,
+}
+
+impl BuildStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      BuildStatus::Running => "running",
+      BuildStatus::Succeeded => "succeeded",
+      BuildStatus::Failed => "failed",
+    }
+  }
+}
+
+impl From<String> for BuildStatus {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known statuses
+    match value.as_str() {
+      "succeeded" => BuildStatus::Succeeded,
+      "failed" => BuildStatus::Failed,
+      _ => BuildStatus::Running,
+    }
+  }
+}
+
+/// final state of a build read back from its job
+#[derive(Debug)]
+pub struct BuildOutcome {
+  pub status: BuildStatus,
+  pub digest: Option<String>,
+  pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug, From, Deserialize, IntoParams)]
+#[into_params(names("galaxy_id", "star_id", "build_id"), parameter_in = Path)]
+pub struct BuildPath(pub Uuid, pub Uuid, pub Uuid);
+
+impl Build {
+  pub fn is_running(&self) -> bool {
+    self.status == BuildStatus::Running
+  }
+
+  /// image pinned to the pushed digest, used as nebula of the star
+  pub fn nebula(&self) -> Option<String> {
+    let digest = self.digest.as_ref()?;
+    let repository = self
+      .image
+      .rsplit_once(':')
+      .map_or(self.image.as_str(), |(repository, _)| repository);
+
+    Some(format!("{}@{}", repository, digest))
+  }
+
+  /// new build pushing its image to the repository of the star, tagged with the build id
+  pub async fn create(conn: &mut Connection, ident: &StarPath, repository: &str) -> DbResult<Self> {
+    let StarPath(_, star_id) = ident;
+
+    let build = sqlx::query_as!(
+      Build,
+      r#"
+      INSERT INTO builds(id, image, star_id)
+      SELECT new_build.id, $1 || ':' || new_build.id, $2
+      FROM (SELECT gen_random_uuid() AS id) AS new_build
+      RETURNING *
+    "#,
+      repository,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(build)
+  }
+
+  /// builds of every star still waiting for their outcome
+  pub async fn all_running(conn: &mut Connection) -> DbResult<Vec<Self>> {
+    let builds = sqlx::query_as!(
+      Build,
+      "SELECT * FROM builds WHERE status = 'running' ORDER BY started_at"
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(builds)
+  }
+
+  pub async fn star(&self, conn: &mut Connection) -> DbResult<Star> {
+    let star = sqlx::query_as!(Star, "SELECT * FROM stars WHERE id = $1", self.star_id)
+      .fetch_one(conn)
+      .await?;
+
+    Ok(star)
+  }
+
+  pub async fn finish(
+    conn: &mut Connection,
+    build_id: &Uuid,
+    outcome: &BuildOutcome,
+  ) -> DbResult<Self> {
+    let build = sqlx::query_as!(
+      Build,
+      r#"UPDATE builds
+      SET status = $1, digest = $2, finished_at = $3
+      WHERE id = $4
+      RETURNING *"#,
+      outcome.status.as_str(),
+      outcome.digest,
+      outcome.finished_at,
+      build_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(build)
+  }
+}
+
+#[async_trait]
+impl CrudOperations for Build {
+  type OwnerIdent = StarPath;
+  type ResourceIdent = BuildPath;
+  type CreateData = ();
+  type UpdateData = ();
+
+  async fn all(conn: &mut Connection, ident: &Self::OwnerIdent) -> DbResult<Vec<Self>> {
+    let StarPath(_, star_id) = ident;
+
+    let builds = sqlx::query_as!(
+      Build,
+      "SELECT * FROM builds WHERE star_id = $1 ORDER BY started_at DESC",
+      star_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(builds)
+  }
+
+  async fn get(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
+    let BuildPath(_, star_id, build_id) = ident;
+
+    let build = sqlx::query_as!(
+      Build,
+      "SELECT * FROM builds WHERE star_id = $1 AND id = $2",
+      star_id,
+      build_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(build)
+  }
+}
@@ -0,0 +1,365 @@
+use async_trait::async_trait;
+use derive_more::From;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use std::{borrow::Cow, path::Path};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::{
+  database::{Connection, DbResult},
+  encryption::SealedValue,
+  gen_update_data,
+};
+
+use super::{star::StarPath, var::VariableKind, CrudOperations};
+
+#[derive(Debug, ToSchema)]
+pub struct ConfigFile {
+  pub id: Uuid,
+  #[schema(example = "/etc/nginx/nginx.conf")]
+  pub mount_path: String,
+  #[schema(minimum = 0, maximum = 511, example = 420)]
+  pub mode: i32,
+  pub kind: VariableKind,
+  pub mount_directory: bool,
+  /// missing for secret files, they are write-only
+  #[schema(value_type = Option<String>)]
+  pub contents: SealedValue,
+  pub star_id: Uuid,
+}
+
+/// row of the config files table, the contents are kept sealed
+struct ConfigFileRow {
+  id: Uuid,
+  mount_path: String,
+  mode: i32,
+  kind: VariableKind,
+  mount_directory: bool,
+  contents: Vec<u8>,
+  data_key: Option<Vec<u8>>,
+  key_id: Option<String>,
+  star_id: Uuid,
+}
+
+impl From<ConfigFileRow> for ConfigFile {
+  fn from(row: ConfigFileRow) -> Self {
+    Self {
+      id: row.id,
+      mount_path: row.mount_path,
+      mode: row.mode,
+      kind: row.kind,
+      mount_directory: row.mount_directory,
+      contents: SealedValue {
+        ciphertext: row.contents,
+        data_key: row.data_key,
+        key_id: row.key_id,
+      },
+      star_id: row.star_id,
+    }
+  }
+}
+
+/// the contents of secret files never leave the api
+impl Serialize for ConfigFile {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let contents = match self.kind {
+      VariableKind::Secret => None,
+      VariableKind::Plain => Some(self.contents.open().map_err(serde::ser::Error::custom)?),
+    };
+
+    let mut file = serializer.serialize_struct("ConfigFile", 7)?;
+    file.serialize_field("id", &self.id)?;
+    file.serialize_field("mount_path", &self.mount_path)?;
+    file.serialize_field("mode", &self.mode)?;
+    file.serialize_field("kind", &self.kind)?;
+    file.serialize_field("mount_directory", &self.mount_directory)?;
+    file.serialize_field("contents", &contents)?;
+    file.serialize_field("star_id", &self.star_id)?;
+    file.end()
+  }
+}
+
+impl ConfigFile {
+  /// the mount changes the pod spec of the star, the contents only the mounted volume
+  pub fn mount(&self) -> ConfigFileMount {
+    ConfigFileMount {
+      id: self.id,
+      mount_path: self.mount_path.clone(),
+      mode: self.mode,
+      kind: self.kind,
+      mount_directory: self.mount_directory,
+    }
+  }
+
+  pub fn open_contents(&self) -> DbResult<String> {
+    Ok(self.contents.open()?)
+  }
+
+  /// every config file of the platform, to seal them again with another master key
+  pub async fn all_sealed(conn: &mut Connection) -> DbResult<Vec<Self>> {
+    let files = sqlx::query_as!(ConfigFileRow, "SELECT * FROM config_files")
+      .fetch_all(conn)
+      .await?;
+
+    Ok(files.into_iter().map(ConfigFile::from).collect())
+  }
+
+  pub async fn set_sealed_contents(
+    conn: &mut Connection,
+    file_id: &Uuid,
+    contents: &SealedValue,
+  ) -> DbResult<()> {
+    sqlx::query!(
+      "UPDATE config_files SET contents = $1, data_key = $2, key_id = $3 WHERE id = $4",
+      contents.ciphertext,
+      contents.data_key,
+      contents.key_id,
+      file_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+  }
+}
+
+/// config file as mounted in the star pods
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ConfigFileMount {
+  pub id: Uuid,
+  pub mount_path: String,
+  pub mode: i32,
+  pub kind: VariableKind,
+  pub mount_directory: bool,
+}
+
+impl ConfigFileMount {
+  /// directory holding the file and the name of the file inside it
+  pub fn split_path(&self) -> (&str, &str) {
+    match self.mount_path.rsplit_once('/') {
+      Some(("", name)) => ("/", name),
+      Some((directory, name)) => (directory, name),
+      None => ("/", &self.mount_path),
+    }
+  }
+}
+
+/// config files of the star in mount path order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(transparent)]
+pub struct StarConfigFiles(Vec<ConfigFileMount>);
+
+impl StarConfigFiles {
+  pub fn iter(&self) -> std::slice::Iter<'_, ConfigFileMount> {
+    self.0.iter()
+  }
+}
+
+impl From<serde_json::Value> for StarConfigFiles {
+  fn from(value: serde_json::Value) -> Self {
+    // the column is only written from the config files table
+    serde_json::from_value(value).unwrap_or_default()
+  }
+}
+
+fn validate_mount_path(path: &str) -> Result<(), ValidationError> {
+  let valid = Path::new(path).is_absolute()
+    && !path.ends_with('/')
+    && path
+      .split('/')
+      .skip(1)
+      .all(|component| !component.is_empty() && component != "." && component != "..");
+
+  if valid {
+    return Ok(());
+  }
+
+  Err(
+    ValidationError::new("mount_path")
+      .with_message(Cow::from("must be an absolute path to a file")),
+  )
+}
+
+/// permissions of a mounted file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(transparent)]
+#[schema(value_type = i32, example = 420)]
+pub struct FileMode(pub i32);
+
+impl Default for FileMode {
+  fn default() -> Self {
+    FileMode(0o644)
+  }
+}
+
+fn validate_file_mode(mode: &FileMode) -> Result<(), ValidationError> {
+  if (0..=0o777).contains(&mode.0) {
+    return Ok(());
+  }
+
+  Err(ValidationError::new("mode").with_message(Cow::from("must be between 0 and 0777")))
+}
+
+gen_update_data! {
+  UpdateConfigFileData,
+  #[derive(Debug, Deserialize, Validate, ToSchema)]
+  pub struct CreateConfigFileData {
+    #[schema(example = "/etc/nginx/nginx.conf")]
+    #[validate(custom(function = "validate_mount_path"))]
+    mount_path: String,
+    #[validate(custom(function = "validate_file_mode"))]
+    #[serde(default)]
+    mode: FileMode,
+    #[serde(default)]
+    kind: VariableKind,
+    /// mount the whole directory of the file so new contents reach the running pods
+    /// without a restart, the files of the image in that directory are hidden
+    #[serde(default)]
+    mount_directory: bool,
+    #[validate(length(max = 1048576, message = "contents cannot exceed 1MiB"))]
+    pub contents: String,
+  }
+}
+
+#[derive(Debug, From, Deserialize, IntoParams)]
+#[into_params(names("galaxy_id", "star_id", "file_id"), parameter_in = Path)]
+pub struct ConfigFilePath(pub Uuid, pub Uuid, pub Uuid);
+
+#[async_trait]
+impl CrudOperations for ConfigFile {
+  type OwnerIdent = StarPath;
+  type ResourceIdent = ConfigFilePath;
+  type CreateData = CreateConfigFileData;
+  type UpdateData = UpdateConfigFileData;
+
+  async fn all(conn: &mut Connection, ident: &Self::OwnerIdent) -> DbResult<Vec<Self>> {
+    let StarPath(_, star_id) = ident;
+
+    let files = sqlx::query_as!(
+      ConfigFileRow,
+      "SELECT * FROM config_files WHERE star_id = $1 ORDER BY mount_path",
+      star_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(files.into_iter().map(ConfigFile::from).collect())
+  }
+
+  async fn get(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
+    let ConfigFilePath(_, star_id, file_id) = ident;
+
+    let file = sqlx::query_as!(
+      ConfigFileRow,
+      "SELECT * FROM config_files WHERE star_id = $1 AND id = $2",
+      star_id,
+      file_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(file.into())
+  }
+
+  async fn create(
+    conn: &mut Connection,
+    ident: &Self::OwnerIdent,
+    data: &Self::CreateData,
+  ) -> DbResult<Self> {
+    let StarPath(_, star_id) = ident;
+    let CreateConfigFileData {
+      mount_path,
+      mode,
+      kind,
+      mount_directory,
+      contents,
+    } = data;
+    let contents = SealedValue::seal(contents)?;
+
+    let file = sqlx::query_as!(
+      ConfigFileRow,
+      r#"INSERT INTO config_files(mount_path, mode, kind, mount_directory, contents, data_key, key_id, star_id)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+      RETURNING *"#,
+      mount_path,
+      mode.0,
+      kind.as_str(),
+      mount_directory,
+      contents.ciphertext,
+      contents.data_key,
+      contents.key_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(file.into())
+  }
+
+  async fn update(
+    conn: &mut Connection,
+    ident: &Self::ResourceIdent,
+    data: &Self::UpdateData,
+  ) -> DbResult<Self> {
+    let ConfigFilePath(_, star_id, file_id) = ident;
+    let UpdateConfigFileData {
+      mount_path,
+      mode,
+      kind,
+      mount_directory,
+      contents,
+    } = data;
+    let contents = contents.as_deref().map(SealedValue::seal).transpose()?;
+
+    let file = sqlx::query_as!(
+      ConfigFileRow,
+      r#"
+      UPDATE config_files
+      SET mount_path = COALESCE($1, mount_path),
+        mode = COALESCE($2, mode),
+        kind = COALESCE($3, kind),
+        mount_directory = COALESCE($4, mount_directory),
+        contents = COALESCE($5, contents),
+        data_key = (CASE WHEN $5::BYTEA IS NULL THEN data_key ELSE $6 END),
+        key_id = (CASE WHEN $5::BYTEA IS NULL THEN key_id ELSE $7 END)
+      WHERE star_id = $8 AND id = $9
+      RETURNING *
+    "#,
+      mount_path.as_deref(),
+      mode.map(|mode| mode.0),
+      kind.map(|kind| kind.as_str()),
+      mount_directory.as_ref(),
+      contents
+        .as_ref()
+        .map(|contents| contents.ciphertext.as_slice()),
+      contents
+        .as_ref()
+        .and_then(|contents| contents.data_key.as_deref()),
+      contents
+        .as_ref()
+        .and_then(|contents| contents.key_id.as_deref()),
+      star_id,
+      file_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(file.into())
+  }
+
+  async fn delete(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
+    let ConfigFilePath(_, star_id, file_id) = ident;
+
+    let file = sqlx::query_as!(
+      ConfigFileRow,
+      "DELETE FROM config_files WHERE star_id = $1 AND id = $2 RETURNING *",
+      star_id,
+      file_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(file.into())
+  }
+}
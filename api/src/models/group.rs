@@ -0,0 +1,437 @@
+use async_trait::async_trait;
+use derive_more::From;
+use serde::{Deserialize, Serialize};
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, HashMap},
+};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::{
+  database::{Connection, DbResult},
+  encryption::SealedValue,
+  env_file, gen_update_data,
+};
+
+use super::{galaxy::GalaxyPath, var::Variable, CrudOperations};
+
+/// variables shared by the stars of a galaxy, their values are write-only
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VariableGroup {
+  pub id: Uuid,
+  #[schema(min_length = 1)]
+  pub name: String,
+  pub galaxy_id: Uuid,
+  /// names of the variables of the group
+  pub variables: Vec<String>,
+}
+
+gen_update_data! {
+  UpdateVariableGroupData,
+  #[derive(Debug, Deserialize, Validate, ToSchema)]
+  pub struct CreateVariableGroupData {
+    #[schema(min_length = 1)]
+    #[validate(length(min = 1, message = "cannot be empty"))]
+    name: String,
+    /// every variable of the group, an update replaces all of them,
+    /// a single variable is set or deleted through its own route
+    #[validate(custom(function = "validate_group_variables"))]
+    variables: BTreeMap<String, String>,
+  }
+}
+
+fn validate_group_variable(name: &str, value: &str) -> Result<(), ValidationError> {
+  if !env_file::valid_name(name) {
+    return Err(
+      ValidationError::new("variables")
+        .with_message(Cow::from(format!("{}: invalid variable name", name))),
+    );
+  }
+
+  if value.is_empty() {
+    return Err(
+      ValidationError::new("variables")
+        .with_message(Cow::from(format!("{}: cannot be empty", name))),
+    );
+  }
+
+  Ok(())
+}
+
+fn validate_group_variables(variables: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+  for (name, value) in variables {
+    validate_group_variable(name, value)?;
+  }
+
+  Ok(())
+}
+
+/// value of a single variable of the group, the other variables are left as they are
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetGroupVariableData {
+  #[schema(min_length = 1)]
+  value: String,
+}
+
+impl SetGroupVariableData {
+  /// the name comes from the path, it is checked with the value
+  pub fn validate_for(&self, name: &str) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+
+    if let Err(error) = validate_group_variable(name, &self.value) {
+      errors.add("value", error);
+    }
+
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(errors)
+    }
+  }
+}
+
+#[derive(Debug, From, Deserialize, IntoParams)]
+#[into_params(names("galaxy_id", "group_id"), parameter_in = Path)]
+pub struct VariableGroupPath(pub Uuid, pub Uuid);
+
+#[derive(Debug, From, Deserialize, IntoParams)]
+#[into_params(names("galaxy_id", "group_id", "name"), parameter_in = Path)]
+pub struct GroupVariablePath(pub Uuid, pub Uuid, pub String);
+
+#[derive(Debug, From, Deserialize, IntoParams)]
+#[into_params(names("galaxy_id", "star_id", "group_id"), parameter_in = Path)]
+pub struct StarVariableGroupPath(pub Uuid, pub Uuid, pub Uuid);
+
+/// variable group attached to a star with the variables it does not provide
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttachedVariableGroup {
+  pub id: Uuid,
+  pub name: String,
+  pub variables: Vec<String>,
+  /// variables of the group replaced by a later group or by the star
+  pub overridden: Vec<VariableOverride>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VariableOverride {
+  pub name: String,
+  /// group defining the value the star receives, the star itself when missing
+  pub group_id: Option<Uuid>,
+}
+
+/// sealed value of a group variable
+pub struct SealedGroupVariable {
+  pub id: Uuid,
+  pub value: SealedValue,
+}
+
+impl VariableGroup {
+  /// opened values of the group, to render its secret
+  pub async fn values(
+    conn: &mut Connection,
+    group_id: &Uuid,
+  ) -> DbResult<BTreeMap<String, String>> {
+    let rows = sqlx::query!(
+      "SELECT name, value, data_key, key_id FROM group_variables WHERE group_id = $1",
+      group_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    let mut values = BTreeMap::new();
+
+    for row in rows {
+      let value = SealedValue {
+        ciphertext: row.value,
+        data_key: row.data_key,
+        key_id: row.key_id,
+      };
+
+      values.insert(row.name, value.open()?);
+    }
+
+    Ok(values)
+  }
+
+  async fn set_values(
+    conn: &mut Connection,
+    group_id: &Uuid,
+    variables: &BTreeMap<String, String>,
+  ) -> DbResult<()> {
+    sqlx::query!("DELETE FROM group_variables WHERE group_id = $1", group_id)
+      .execute(&mut *conn)
+      .await?;
+
+    for (name, value) in variables {
+      let value = SealedValue::seal(value)?;
+
+      sqlx::query!(
+        r#"INSERT INTO group_variables(name, value, data_key, key_id, group_id)
+        VALUES ($1, $2, $3, $4, $5)"#,
+        name,
+        value.ciphertext,
+        value.data_key,
+        value.key_id,
+        group_id
+      )
+      .execute(&mut *conn)
+      .await?;
+    }
+
+    Ok(())
+  }
+
+  /// create or replace one variable of the group
+  pub async fn set_variable(
+    conn: &mut Connection,
+    ident: &GroupVariablePath,
+    data: &SetGroupVariableData,
+  ) -> DbResult<Self> {
+    let GroupVariablePath(galaxy_id, group_id, name) = ident;
+    let group_path = VariableGroupPath(*galaxy_id, *group_id);
+
+    // the group is looked up in its galaxy before its variables change
+    let _ = Self::get(conn, &group_path).await?;
+
+    let value = SealedValue::seal(&data.value)?;
+
+    sqlx::query!(
+      r#"INSERT INTO group_variables(name, value, data_key, key_id, group_id)
+      VALUES ($1, $2, $3, $4, $5)
+      ON CONFLICT (name, group_id) DO UPDATE
+      SET value = excluded.value, data_key = excluded.data_key, key_id = excluded.key_id"#,
+      name,
+      value.ciphertext,
+      value.data_key,
+      value.key_id,
+      group_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Self::get(conn, &group_path).await
+  }
+
+  pub async fn delete_variable(conn: &mut Connection, ident: &GroupVariablePath) -> DbResult<Self> {
+    let GroupVariablePath(galaxy_id, group_id, name) = ident;
+    let group_path = VariableGroupPath(*galaxy_id, *group_id);
+
+    let _ = Self::get(conn, &group_path).await?;
+
+    sqlx::query_scalar!(
+      "DELETE FROM group_variables WHERE group_id = $1 AND name = $2 RETURNING id",
+      group_id,
+      name
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Self::get(conn, &group_path).await
+  }
+
+  /// every group variable of the platform, to seal them again with another master key
+  pub async fn all_sealed(conn: &mut Connection) -> DbResult<Vec<SealedGroupVariable>> {
+    let rows = sqlx::query!("SELECT id, value, data_key, key_id FROM group_variables")
+      .fetch_all(conn)
+      .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| SealedGroupVariable {
+          id: row.id,
+          value: SealedValue {
+            ciphertext: row.value,
+            data_key: row.data_key,
+            key_id: row.key_id,
+          },
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn set_sealed_value(
+    conn: &mut Connection,
+    var_id: &Uuid,
+    value: &SealedValue,
+  ) -> DbResult<()> {
+    sqlx::query!(
+      "UPDATE group_variables SET value = $1, data_key = $2, key_id = $3 WHERE id = $4",
+      value.ciphertext,
+      value.data_key,
+      value.key_id,
+      var_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+  }
+
+  /// groups of a star in attach order with the variables each one loses,
+  /// a later group overrides an earlier one and the star overrides them all
+  pub fn attached(
+    groups: Vec<VariableGroup>,
+    order: &[Uuid],
+    star_vars: &[Variable],
+  ) -> Vec<AttachedVariableGroup> {
+    let mut groups = groups
+      .into_iter()
+      .map(|group| (group.id, group))
+      .collect::<HashMap<_, _>>();
+    let groups = order
+      .iter()
+      .filter_map(|id| groups.remove(id))
+      .collect::<Vec<_>>();
+
+    // source of the value the star receives for each name
+    let mut winners: HashMap<&str, Option<Uuid>> = HashMap::new();
+    for group in &groups {
+      for name in &group.variables {
+        winners.insert(name, Some(group.id));
+      }
+    }
+    for var in star_vars {
+      winners.insert(&var.name, None);
+    }
+
+    groups
+      .iter()
+      .map(|group| AttachedVariableGroup {
+        id: group.id,
+        name: group.name.clone(),
+        variables: group.variables.clone(),
+        overridden: group
+          .variables
+          .iter()
+          .filter_map(|name| match winners.get(name.as_str()) {
+            Some(&winner) if winner != Some(group.id) => Some(VariableOverride {
+              name: name.clone(),
+              group_id: winner,
+            }),
+            _ => None,
+          })
+          .collect(),
+      })
+      .collect()
+  }
+}
+
+#[async_trait]
+impl CrudOperations for VariableGroup {
+  type OwnerIdent = GalaxyPath;
+  type ResourceIdent = VariableGroupPath;
+  type CreateData = CreateVariableGroupData;
+  type UpdateData = UpdateVariableGroupData;
+
+  async fn all(conn: &mut Connection, ident: &Self::OwnerIdent) -> DbResult<Vec<Self>> {
+    let GalaxyPath(galaxy_id) = ident;
+
+    let groups = sqlx::query_as!(
+      VariableGroup,
+      r#"
+      SELECT variable_groups.id, variable_groups.name, variable_groups.galaxy_id,
+        array_remove(array_agg(group_variables.name ORDER BY group_variables.name), NULL) AS "variables!"
+      FROM variable_groups
+      LEFT JOIN group_variables ON group_variables.group_id = variable_groups.id
+      WHERE variable_groups.galaxy_id = $1
+      GROUP BY variable_groups.id
+    "#,
+      galaxy_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(groups)
+  }
+
+  async fn get(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
+    let VariableGroupPath(galaxy_id, group_id) = ident;
+
+    let group = sqlx::query_as!(
+      VariableGroup,
+      r#"
+      SELECT variable_groups.id, variable_groups.name, variable_groups.galaxy_id,
+        array_remove(array_agg(group_variables.name ORDER BY group_variables.name), NULL) AS "variables!"
+      FROM variable_groups
+      LEFT JOIN group_variables ON group_variables.group_id = variable_groups.id
+      WHERE variable_groups.galaxy_id = $1 AND variable_groups.id = $2
+      GROUP BY variable_groups.id
+    "#,
+      galaxy_id,
+      group_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(group)
+  }
+
+  async fn create(
+    conn: &mut Connection,
+    ident: &Self::OwnerIdent,
+    data: &Self::CreateData,
+  ) -> DbResult<Self> {
+    let GalaxyPath(galaxy_id) = ident;
+    let CreateVariableGroupData { name, variables } = data;
+
+    let group_id = sqlx::query_scalar!(
+      "INSERT INTO variable_groups(name, galaxy_id) VALUES ($1, $2) RETURNING id",
+      name,
+      galaxy_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Self::set_values(conn, &group_id, variables).await?;
+
+    Self::get(conn, &VariableGroupPath(*galaxy_id, group_id)).await
+  }
+
+  async fn update(
+    conn: &mut Connection,
+    ident: &Self::ResourceIdent,
+    data: &Self::UpdateData,
+  ) -> DbResult<Self> {
+    let VariableGroupPath(galaxy_id, group_id) = ident;
+    let UpdateVariableGroupData { name, variables } = data;
+
+    let group_id = sqlx::query_scalar!(
+      r#"
+      UPDATE variable_groups
+      SET name = COALESCE($1, name)
+      WHERE galaxy_id = $2 AND id = $3
+      RETURNING id
+    "#,
+      name.as_deref(),
+      galaxy_id,
+      group_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if let Some(variables) = variables {
+      Self::set_values(conn, &group_id, variables).await?;
+    }
+
+    Self::get(conn, ident).await
+  }
+
+  async fn delete(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
+    let VariableGroupPath(galaxy_id, group_id) = ident;
+
+    let deleted_group = Self::get(conn, ident).await?;
+
+    sqlx::query!(
+      "DELETE FROM variable_groups WHERE galaxy_id = $1 AND id = $2",
+      galaxy_id,
+      group_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(deleted_group)
+  }
+}
@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::database::{Connection, DbResult};
+
+use super::{galaxy::GalaxyPath, star::StarPath};
+
+/// samples older than this are removed by the sampler
+pub const METRICS_RETENTION_DAYS: i64 = 7;
+
+/// current usage of a replica of a star
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplicaUsage {
+  pub pod: String,
+  #[schema(minimum = 0)]
+  pub cpu_millicores: f64,
+  #[schema(minimum = 0)]
+  pub memory_bytes: i64,
+}
+
+/// usage of all the replicas of a star at a point in time
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MetricsSample {
+  pub sampled_at: DateTime<Utc>,
+  #[schema(minimum = 0)]
+  pub replicas: i32,
+  #[schema(minimum = 0)]
+  pub cpu_millicores: f64,
+  #[schema(minimum = 0)]
+  pub memory_bytes: i64,
+  /// received bytes per second
+  #[schema(minimum = 0)]
+  pub network_rx_rate: Option<f64>,
+  /// transmitted bytes per second
+  #[schema(minimum = 0)]
+  pub network_tx_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+pub enum MetricsRange {
+  #[default]
+  #[serde(rename = "24h")]
+  Day,
+  #[serde(rename = "7d")]
+  Week,
+}
+
+impl MetricsRange {
+  /// length of the range in seconds
+  fn window(&self) -> f64 {
+    match self {
+      MetricsRange::Day => 24.0 * 60.0 * 60.0,
+      MetricsRange::Week => 7.0 * 24.0 * 60.0 * 60.0,
+    }
+  }
+
+  /// samples are averaged in buckets of this many seconds
+  fn bucket(&self) -> f64 {
+    match self {
+      MetricsRange::Day => 5.0 * 60.0,
+      MetricsRange::Week => 60.0 * 60.0,
+    }
+  }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MetricsQuery {
+  /// time span of the series, the last 24 hours by default
+  #[serde(default)]
+  pub range: MetricsRange,
+}
+
+impl MetricsSample {
+  pub async fn create(conn: &mut Connection, star_id: &Uuid, sample: &Self) -> DbResult<()> {
+    sqlx::query!(
+      r#"INSERT INTO star_metrics(
+        sampled_at,
+        replicas,
+        cpu_millicores,
+        memory_bytes,
+        network_rx_rate,
+        network_tx_rate,
+        star_id
+      ) VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+      sample.sampled_at,
+      sample.replicas,
+      sample.cpu_millicores,
+      sample.memory_bytes,
+      sample.network_rx_rate,
+      sample.network_tx_rate,
+      star_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+  }
+
+  /// remove the samples older than the retention
+  pub async fn prune(conn: &mut Connection) -> DbResult<()> {
+    sqlx::query!(
+      "DELETE FROM star_metrics WHERE sampled_at < now() - make_interval(days => $1)",
+      METRICS_RETENTION_DAYS as i32
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn star_series(
+    conn: &mut Connection,
+    ident: &StarPath,
+    range: MetricsRange,
+  ) -> DbResult<Vec<Self>> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let series = sqlx::query_as!(
+      MetricsSample,
+      r#"
+      SELECT date_bin(make_interval(secs => $1), sampled_at, TIMESTAMPTZ 'epoch') AS "sampled_at!",
+        MAX(replicas) AS "replicas!",
+        AVG(cpu_millicores) AS "cpu_millicores!",
+        AVG(memory_bytes)::BIGINT AS "memory_bytes!",
+        AVG(network_rx_rate) AS network_rx_rate,
+        AVG(network_tx_rate) AS network_tx_rate
+      FROM star_metrics
+      JOIN stars ON stars.id = star_metrics.star_id
+      WHERE stars.galaxy_id = $2
+        AND star_metrics.star_id = $3
+        AND sampled_at > now() - make_interval(secs => $4)
+      GROUP BY 1
+      ORDER BY 1
+    "#,
+      range.bucket(),
+      galaxy_id,
+      star_id,
+      range.window()
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(series)
+  }
+
+  /// usage of all the stars of the galaxy summed in each bucket
+  pub async fn galaxy_series(
+    conn: &mut Connection,
+    ident: &GalaxyPath,
+    range: MetricsRange,
+  ) -> DbResult<Vec<Self>> {
+    let GalaxyPath(galaxy_id) = ident;
+
+    let series = sqlx::query_as!(
+      MetricsSample,
+      r#"
+      SELECT bucket AS "sampled_at!",
+        SUM(replicas)::INT AS "replicas!",
+        SUM(cpu_millicores) AS "cpu_millicores!",
+        SUM(memory_bytes)::BIGINT AS "memory_bytes!",
+        SUM(network_rx_rate) AS network_rx_rate,
+        SUM(network_tx_rate) AS network_tx_rate
+      FROM (
+        SELECT date_bin(make_interval(secs => $1), sampled_at, TIMESTAMPTZ 'epoch') AS bucket,
+          MAX(replicas) AS replicas,
+          AVG(cpu_millicores) AS cpu_millicores,
+          AVG(memory_bytes) AS memory_bytes,
+          AVG(network_rx_rate) AS network_rx_rate,
+          AVG(network_tx_rate) AS network_tx_rate
+        FROM star_metrics
+        JOIN stars ON stars.id = star_metrics.star_id
+        WHERE stars.galaxy_id = $2 AND sampled_at > now() - make_interval(secs => $3)
+        GROUP BY star_metrics.star_id, 1
+      ) AS star_buckets
+      GROUP BY bucket
+      ORDER BY bucket
+    "#,
+      range.bucket(),
+      galaxy_id,
+      range.window()
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(series)
+  }
+}
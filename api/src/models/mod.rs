@@ -2,9 +2,16 @@ use async_trait::async_trait;
 
 use crate::database::{Connection, DbError, DbResult, Operation};
 
+pub mod build;
+pub mod file;
 pub mod galaxy;
+pub mod group;
+pub mod metrics;
 pub mod planet;
+pub mod rollout;
+pub mod run;
 pub mod session;
+pub mod snapshot;
 pub mod star;
 pub mod user;
 pub mod var;
@@ -1,31 +1,95 @@
+use std::borrow::Cow;
+
 use async_trait::async_trait;
 use derive_more::From;
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use crate::database::{Connection, DbResult};
 use crate::gen_update_data;
+use crate::storage::StorageTier;
 
-pub use super::{galaxy::GalaxyPath, CrudOperations};
+pub use super::{galaxy::GalaxyPath, star::StarPath, CrudOperations};
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Planet {
   pub id: Uuid,
   #[schema(min_length = 1)]
   pub name: String,
-  #[schema(minimum = 0, maximum = 2147483647)]
+  /// size of the volume in megabytes
+  #[schema(minimum = 1, maximum = 2147483647)]
   pub capacity: i32,
   #[schema(min_length = 1)]
   pub path: String,
   pub star_id: Option<Uuid>,
   pub galaxy_id: Uuid,
+  /// storage tier provisioning the volume
+  #[schema(example = "fast-ssd")]
+  pub storage_tier: String,
+  /// progress of the expansion of the volume after the capacity grew
+  pub resize_state: PlanetResizeState,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanetResizeState {
+  /// the volume has the capacity of the planet
+  #[default]
+  Done,
+  /// the storage provider is expanding the volume
+  Resizing,
+  /// the volume is expanded, its file system grows once a pod mounts it
+  FileSystemResizePending,
+}
+
+impl PlanetResizeState {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PlanetResizeState::Done => "done",
+      PlanetResizeState::Resizing => "resizing",
+      PlanetResizeState::FileSystemResizePending => "file_system_resize_pending",
+    }
+  }
+}
+
+impl From<String> for PlanetResizeState {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known states
+    match value.as_str() {
+      "resizing" => PlanetResizeState::Resizing,
+      "file_system_resize_pending" => PlanetResizeState::FileSystemResizePending,
+      _ => PlanetResizeState::Done,
+    }
+  }
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct ConnectPlanetToStar {
-  id: Option<Uuid>, // id of the star to connect
+  pub id: Option<Uuid>, // id of the star to connect
+}
+
+/// name of a storage tier of the platform
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(transparent)]
+#[schema(value_type = String, example = "fast-ssd")]
+pub struct StorageTierName(pub String);
+
+impl Default for StorageTierName {
+  fn default() -> Self {
+    StorageTierName(StorageTier::default_tier().name.clone())
+  }
+}
+
+fn validate_storage_tier(tier: &StorageTierName) -> Result<(), ValidationError> {
+  if StorageTier::find(&tier.0).is_none() {
+    return Err(
+      ValidationError::new("storage_tier").with_message(Cow::from("unknown storage tier")),
+    );
+  }
+
+  Ok(())
 }
 
 gen_update_data! {
@@ -34,20 +98,99 @@ gen_update_data! {
   pub struct CreatePlanetData {
     #[schema(min_length = 1)]
     #[validate(length(min = 1, message = "cannot be empty"))]
-    name: String,
-    #[schema(minimum = 0, maximum = 2147483647)]
-    #[validate(range(min = 0, max = 2147483647, message = "capacity must be between 0 and 2147483647"))]
-    capacity: i32,
+    pub name: String,
+    /// size of the volume in megabytes
+    #[schema(minimum = 1, maximum = 2147483647)]
+    #[validate(range(min = 1, max = 2147483647, message = "capacity must be between 1 and 2147483647"))]
+    pub capacity: i32,
     #[schema(min_length = 1)]
     #[validate(length(min = 1, message = "cannot be empty"))]
-    path: String,
-    star: ConnectPlanetToStar,
+    pub path: String,
+    pub star: ConnectPlanetToStar,
+    /// storage tier provisioning the volume, the first tier of the platform when missing
+    #[validate(custom(function = "validate_storage_tier"))]
+    #[serde(default)]
+    pub storage_tier: StorageTierName,
   }
 }
 
 #[derive(Debug, From, Deserialize, IntoParams)]
 #[into_params(names("galaxy_id", "planet_id"), parameter_in = Path)]
-pub struct PlanetPath(Uuid, Uuid);
+pub struct PlanetPath(pub Uuid, pub Uuid);
+
+impl Planet {
+  /// planets connected to the star
+  pub async fn all_in_star(conn: &mut Connection, ident: &StarPath) -> DbResult<Vec<Self>> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let planets = sqlx::query_as!(
+      Planet,
+      "SELECT * FROM planets WHERE galaxy_id = $1 AND star_id = $2",
+      galaxy_id,
+      star_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(planets)
+  }
+
+  /// planets whose volume has not reached their capacity yet
+  pub async fn all_resizing(conn: &mut Connection) -> DbResult<Vec<Self>> {
+    let planets = sqlx::query_as!(Planet, "SELECT * FROM planets WHERE resize_state <> 'done'")
+      .fetch_all(conn)
+      .await?;
+
+    Ok(planets)
+  }
+
+  /// planets of a tier the platform does not define
+  pub async fn all_outside_tiers(conn: &mut Connection, tiers: &[String]) -> DbResult<Vec<Self>> {
+    let planets = sqlx::query_as!(
+      Planet,
+      "SELECT * FROM planets WHERE NOT (storage_tier = ANY($1))",
+      tiers
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(planets)
+  }
+
+  pub async fn set_storage_tier(
+    conn: &mut Connection,
+    planet_id: &Uuid,
+    storage_tier: &str,
+  ) -> DbResult<Self> {
+    let planet = sqlx::query_as!(
+      Planet,
+      "UPDATE planets SET storage_tier = $1 WHERE id = $2 RETURNING *",
+      storage_tier,
+      planet_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(planet)
+  }
+
+  pub async fn set_resize_state(
+    conn: &mut Connection,
+    planet_id: &Uuid,
+    state: PlanetResizeState,
+  ) -> DbResult<Self> {
+    let planet = sqlx::query_as!(
+      Planet,
+      "UPDATE planets SET resize_state = $1 WHERE id = $2 RETURNING *",
+      state.as_str(),
+      planet_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(planet)
+  }
+}
 
 #[async_trait]
 impl CrudOperations for Planet {
@@ -96,16 +239,18 @@ impl CrudOperations for Planet {
       capacity,
       path,
       star,
+      storage_tier,
     } = data;
 
     let new_galaxy = sqlx::query_as!(
       Planet,
-      "INSERT INTO planets(name, capacity, path, star_id, galaxy_id) VALUES ($1, $2, $3, $4, $5) RETURNING *",
+      "INSERT INTO planets(name, capacity, path, star_id, galaxy_id, storage_tier) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *",
       name,
       capacity,
       path,
       star.id,
-      galaxy_id
+      galaxy_id,
+      storage_tier.0
     )
     .fetch_one(conn)
     .await?;
@@ -123,6 +268,7 @@ impl CrudOperations for Planet {
       capacity,
       path,
       star,
+      ..
     } = data;
 
     let update_star = star.is_some();
@@ -134,6 +280,7 @@ impl CrudOperations for Planet {
       UPDATE planets
       SET name = COALESCE($1, name),
         capacity = COALESCE($2, capacity),
+        resize_state = (CASE WHEN $2 > capacity THEN 'resizing' ELSE resize_state END),
         path = COALESCE($3, path),
         star_id = (CASE WHEN $4 = true THEN $5 ELSE star_id END)
       WHERE galaxy_id = $6 AND id = $7
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::borrow::Cow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::{ValidationError, ValidationErrors};
+
+use crate::database::{Connection, DbResult};
+
+use super::star::{GalaxyPath, Star, StarPath, StrategyKind};
+
+/// update of a blue-green or canary star waiting to be promoted or aborted
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Rollout {
+  pub id: Uuid,
+  pub strategy: StrategyKind,
+  /// star before the rollout, restored on abort
+  #[serde(skip)]
+  pub previous: serde_json::Value,
+  pub started_at: DateTime<Utc>,
+  /// the traffic of a blue-green rollout reaches the preview,
+  /// the stable deployment keeps the previous version until the promotion is confirmed
+  pub switched_at: Option<DateTime<Utc>>,
+  pub star_id: Uuid,
+}
+
+impl Rollout {
+  /// an update during the rollout replaces the preview, it cannot switch strategy
+  /// nor change the routing and the scaling of the star which the preview does not run
+  pub fn validate_update(&self, star: &Star, updated_star: &Star) -> Result<(), ValidationErrors> {
+    let changed = [
+      ("strategy", updated_star.strategy != self.strategy),
+      (
+        "public_domain",
+        updated_star.public_domain != star.public_domain,
+      ),
+      (
+        "private_domain",
+        updated_star.private_domain != star.private_domain,
+      ),
+      ("port", updated_star.port != star.port),
+      (
+        "idle_timeout",
+        updated_star.idle_timeout != star.idle_timeout,
+      ),
+      ("max_surge", updated_star.max_surge != star.max_surge),
+      (
+        "max_unavailable",
+        updated_star.max_unavailable != star.max_unavailable,
+      ),
+    ];
+
+    let mut errors = ValidationErrors::new();
+
+    for (field, _) in changed.into_iter().filter(|(_, changed)| *changed) {
+      errors.add(
+        field,
+        ValidationError::new("rollout")
+          .with_message(Cow::from("promote or abort the active rollout first")),
+      );
+    }
+
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(errors)
+    }
+  }
+
+  pub fn is_switched(&self) -> bool {
+    self.switched_at.is_some()
+  }
+
+  pub async fn get(conn: &mut Connection, ident: &StarPath) -> DbResult<Option<Self>> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let rollout = sqlx::query_as!(
+      Rollout,
+      r#"
+      SELECT rollouts.* FROM rollouts
+      JOIN stars ON stars.id = rollouts.star_id
+      WHERE stars.galaxy_id = $1 AND rollouts.star_id = $2
+    "#,
+      galaxy_id,
+      star_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(rollout)
+  }
+
+  pub async fn all_in_galaxy(conn: &mut Connection, ident: &GalaxyPath) -> DbResult<Vec<Self>> {
+    let GalaxyPath(galaxy_id) = ident;
+
+    let rollouts = sqlx::query_as!(
+      Rollout,
+      r#"
+      SELECT rollouts.* FROM rollouts
+      JOIN stars ON stars.id = rollouts.star_id
+      WHERE stars.galaxy_id = $1
+    "#,
+      galaxy_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rollouts)
+  }
+
+  /// start a rollout from the star as it is before the update
+  pub async fn create(
+    conn: &mut Connection,
+    previous: &Star,
+    strategy: StrategyKind,
+  ) -> DbResult<Self> {
+    let rollout = sqlx::query_as!(
+      Rollout,
+      "INSERT INTO rollouts(strategy, previous, star_id) VALUES ($1, $2, $3) RETURNING *",
+      strategy.as_str(),
+      previous.to_json(),
+      previous.id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(rollout)
+  }
+
+  /// the traffic of the blue-green rollout moved to the preview
+  pub async fn switch(conn: &mut Connection, ident: &StarPath) -> DbResult<Self> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let rollout = sqlx::query_as!(
+      Rollout,
+      r#"
+      UPDATE rollouts SET switched_at = now()
+      FROM stars
+      WHERE stars.id = rollouts.star_id AND stars.galaxy_id = $1 AND rollouts.star_id = $2
+      RETURNING rollouts.*
+    "#,
+      galaxy_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(rollout)
+  }
+
+  pub async fn delete(conn: &mut Connection, ident: &StarPath) -> DbResult<Self> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let rollout = sqlx::query_as!(
+      Rollout,
+      r#"
+      DELETE FROM rollouts
+      USING stars
+      WHERE stars.id = rollouts.star_id AND stars.galaxy_id = $1 AND rollouts.star_id = $2
+      RETURNING rollouts.*
+    "#,
+      galaxy_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(rollout)
+  }
+}
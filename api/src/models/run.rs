@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_more::From;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::database::{Connection, DbResult};
+
+use super::{star::StarPath, CrudOperations};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Run {
+  pub id: Uuid,
+  #[schema(min_items = 1, example = json!(["./manage.py", "migrate"]))]
+  pub command: Vec<String>,
+  pub status: RunStatus,
+  pub exit_code: Option<i32>,
+  /// reason given by kubernetes for a failed job
+  pub error: Option<String>,
+  pub started_at: DateTime<Utc>,
+  pub finished_at: Option<DateTime<Utc>>,
+  /// duration of the finished run in seconds
+  #[schema(minimum = 0)]
+  pub duration: Option<i32>,
+  pub star_id: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+  Running,
+  Succeeded,
+  Failed,
+}
+
+impl RunStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      RunStatus::Running => "running",
+      RunStatus::Succeeded => "succeeded",
+      RunStatus::Failed => "failed",
+    }
+  }
+}
+
+impl From<String> for RunStatus {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known statuses
+    match value.as_str() {
+      "succeeded" => RunStatus::Succeeded,
+      "failed" => RunStatus::Failed,
+      _ => RunStatus::Running,
+    }
+  }
+}
+
+/// final state of a run read back from its job
+#[derive(Debug)]
+pub struct RunOutcome {
+  pub status: RunStatus,
+  pub exit_code: Option<i32>,
+  pub error: Option<String>,
+  pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateRunData {
+  /// command executed in place of the entrypoint of the nebula
+  #[schema(min_items = 1, example = json!(["./manage.py", "migrate"]))]
+  #[validate(length(min = 1, message = "cannot be empty"))]
+  command: Vec<String>,
+}
+
+#[derive(Debug, From, Deserialize, IntoParams)]
+#[into_params(names("galaxy_id", "star_id", "run_id"), parameter_in = Path)]
+pub struct RunPath(pub Uuid, pub Uuid, pub Uuid);
+
+impl Run {
+  pub fn is_running(&self) -> bool {
+    self.status == RunStatus::Running
+  }
+
+  pub async fn finish(
+    conn: &mut Connection,
+    run_id: &Uuid,
+    outcome: &RunOutcome,
+  ) -> DbResult<Self> {
+    let run = sqlx::query_as!(
+      Run,
+      r#"UPDATE runs
+      SET status = $1, exit_code = $2, error = $3, finished_at = $4
+      WHERE id = $5
+      RETURNING *"#,
+      outcome.status.as_str(),
+      outcome.exit_code,
+      outcome.error,
+      outcome.finished_at,
+      run_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(run)
+  }
+}
+
+#[async_trait]
+impl CrudOperations for Run {
+  type OwnerIdent = StarPath;
+  type ResourceIdent = RunPath;
+  type CreateData = CreateRunData;
+  type UpdateData = ();
+
+  async fn all(conn: &mut Connection, ident: &Self::OwnerIdent) -> DbResult<Vec<Self>> {
+    let StarPath(_, star_id) = ident;
+
+    let runs = sqlx::query_as!(
+      Run,
+      "SELECT * FROM runs WHERE star_id = $1 ORDER BY started_at DESC",
+      star_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(runs)
+  }
+
+  async fn get(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
+    let RunPath(_, star_id, run_id) = ident;
+
+    let run = sqlx::query_as!(
+      Run,
+      "SELECT * FROM runs WHERE star_id = $1 AND id = $2",
+      star_id,
+      run_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(run)
+  }
+
+  async fn create(
+    conn: &mut Connection,
+    ident: &Self::OwnerIdent,
+    data: &Self::CreateData,
+  ) -> DbResult<Self> {
+    let StarPath(_, star_id) = ident;
+    let CreateRunData { command } = data;
+
+    let run = sqlx::query_as!(
+      Run,
+      "INSERT INTO runs(command, star_id) VALUES ($1, $2) RETURNING *",
+      command,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(run)
+  }
+}
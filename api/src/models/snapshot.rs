@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_more::From;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::database::{Connection, DbResult};
+
+use super::{
+  planet::{ConnectPlanetToStar, CreatePlanetData, GalaxyPath, PlanetPath, StorageTierName},
+  CrudOperations,
+};
+
+/// point in time copy of the volume of a planet
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PlanetSnapshot {
+  pub id: Uuid,
+  #[schema(example = "before-migration")]
+  pub name: String,
+  pub status: SnapshotStatus,
+  /// reason given by the snapshot controller for a failed snapshot
+  pub error: Option<String>,
+  /// capacity of the planet when the snapshot was taken, in megabytes
+  #[schema(minimum = 1)]
+  pub capacity: i32,
+  /// storage tier of the planet, the restored planets use the same tier
+  #[schema(example = "fast-ssd")]
+  pub storage_tier: String,
+  pub created_at: DateTime<Utc>,
+  pub ready_at: Option<DateTime<Utc>>,
+  /// planet the snapshot was taken from, none once the planet is deleted
+  pub planet_id: Option<Uuid>,
+  pub galaxy_id: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotStatus {
+  Pending,
+  Ready,
+  Failed,
+}
+
+impl SnapshotStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      SnapshotStatus::Pending => "pending",
+      SnapshotStatus::Ready => "ready",
+      SnapshotStatus::Failed => "failed",
+    }
+  }
+}
+
+impl From<String> for SnapshotStatus {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known statuses
+    match value.as_str() {
+      "ready" => SnapshotStatus::Ready,
+      "failed" => SnapshotStatus::Failed,
+      _ => SnapshotStatus::Pending,
+    }
+  }
+}
+
+/// state of a snapshot read back from its volume snapshot
+#[derive(Debug)]
+pub struct SnapshotOutcome {
+  pub status: SnapshotStatus,
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateSnapshotData {
+  #[schema(min_length = 1, example = "before-migration")]
+  #[validate(length(min = 1, message = "cannot be empty"))]
+  name: String,
+}
+
+/// new planet provisioned with the data of the snapshot
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RestoreSnapshotData {
+  #[schema(min_length = 1)]
+  #[validate(length(min = 1, message = "cannot be empty"))]
+  name: String,
+  /// size of the volume in megabytes, the capacity of the snapshot when missing
+  #[schema(minimum = 1, maximum = 2147483647)]
+  #[validate(range(
+    min = 1,
+    max = 2147483647,
+    message = "capacity must be between 1 and 2147483647"
+  ))]
+  capacity: Option<i32>,
+  #[schema(min_length = 1)]
+  #[validate(length(min = 1, message = "cannot be empty"))]
+  path: String,
+  star: ConnectPlanetToStar,
+}
+
+impl RestoreSnapshotData {
+  pub fn planet_data(self, snapshot: &PlanetSnapshot) -> CreatePlanetData {
+    CreatePlanetData {
+      name: self.name,
+      capacity: self.capacity.unwrap_or(snapshot.capacity),
+      path: self.path,
+      star: self.star,
+      storage_tier: StorageTierName(snapshot.storage_tier.clone()),
+    }
+  }
+}
+
+#[derive(Debug, From, Deserialize, IntoParams)]
+#[into_params(names("galaxy_id", "snapshot_id"), parameter_in = Path)]
+pub struct SnapshotPath(pub Uuid, pub Uuid);
+
+impl PlanetSnapshot {
+  pub fn is_ready(&self) -> bool {
+    self.status == SnapshotStatus::Ready
+  }
+
+  /// snapshots of the galaxy, with the ones of its deleted planets
+  pub async fn all_in_galaxy(conn: &mut Connection, ident: &GalaxyPath) -> DbResult<Vec<Self>> {
+    let GalaxyPath(galaxy_id) = ident;
+
+    let snapshots = sqlx::query_as!(
+      PlanetSnapshot,
+      "SELECT * FROM planet_snapshots WHERE galaxy_id = $1 ORDER BY created_at DESC",
+      galaxy_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(snapshots)
+  }
+
+  /// snapshots of every planet still waiting for their volume snapshot
+  pub async fn all_pending(conn: &mut Connection) -> DbResult<Vec<Self>> {
+    let snapshots = sqlx::query_as!(
+      PlanetSnapshot,
+      "SELECT * FROM planet_snapshots WHERE status = 'pending' ORDER BY created_at"
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(snapshots)
+  }
+
+  pub async fn finish(
+    conn: &mut Connection,
+    snapshot_id: &Uuid,
+    outcome: &SnapshotOutcome,
+  ) -> DbResult<Self> {
+    let snapshot = sqlx::query_as!(
+      PlanetSnapshot,
+      r#"UPDATE planet_snapshots
+      SET status = $1, error = $2, ready_at = (CASE WHEN $1 = 'ready' THEN now() END)
+      WHERE id = $3
+      RETURNING *"#,
+      outcome.status.as_str(),
+      outcome.error,
+      snapshot_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(snapshot)
+  }
+}
+
+#[async_trait]
+impl CrudOperations for PlanetSnapshot {
+  type OwnerIdent = PlanetPath;
+  type ResourceIdent = SnapshotPath;
+  type CreateData = CreateSnapshotData;
+  type UpdateData = ();
+
+  async fn all(conn: &mut Connection, ident: &Self::OwnerIdent) -> DbResult<Vec<Self>> {
+    let PlanetPath(galaxy_id, planet_id) = ident;
+
+    let snapshots = sqlx::query_as!(
+      PlanetSnapshot,
+      "SELECT * FROM planet_snapshots WHERE galaxy_id = $1 AND planet_id = $2 ORDER BY created_at DESC",
+      galaxy_id,
+      planet_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(snapshots)
+  }
+
+  async fn get(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
+    let SnapshotPath(galaxy_id, snapshot_id) = ident;
+
+    let snapshot = sqlx::query_as!(
+      PlanetSnapshot,
+      "SELECT * FROM planet_snapshots WHERE galaxy_id = $1 AND id = $2",
+      galaxy_id,
+      snapshot_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(snapshot)
+  }
+
+  /// the capacity and the tier are copied from the planet as it is now
+  async fn create(
+    conn: &mut Connection,
+    ident: &Self::OwnerIdent,
+    data: &Self::CreateData,
+  ) -> DbResult<Self> {
+    let PlanetPath(galaxy_id, planet_id) = ident;
+
+    let snapshot = sqlx::query_as!(
+      PlanetSnapshot,
+      r#"
+      INSERT INTO planet_snapshots(name, capacity, storage_tier, planet_id, galaxy_id)
+      SELECT $1, capacity, storage_tier, id, galaxy_id
+      FROM planets WHERE galaxy_id = $2 AND id = $3
+      RETURNING *
+    "#,
+      data.name,
+      galaxy_id,
+      planet_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(snapshot)
+  }
+
+  async fn delete(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
+    let SnapshotPath(galaxy_id, snapshot_id) = ident;
+
+    let snapshot = sqlx::query_as!(
+      PlanetSnapshot,
+      "DELETE FROM planet_snapshots WHERE galaxy_id = $1 AND id = $2 RETURNING *",
+      galaxy_id,
+      snapshot_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(snapshot)
+  }
+}
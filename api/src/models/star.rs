@@ -1,12 +1,20 @@
 use async_trait::async_trait;
 use derive_more::From;
 use serde::{Deserialize, Serialize};
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, HashSet},
+};
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::database::{Connection, DbResult};
 use crate::gen_update_data;
+use crate::placement::PlacementProfile;
+use crate::registry::NebulaReference;
+
+use super::{file::StarConfigFiles, planet::Planet};
 
 pub use super::{galaxy::GalaxyPath, CrudOperations};
 
@@ -15,7 +23,7 @@ pub struct Star {
   pub id: Uuid,
   #[schema(min_length = 1)]
   pub name: String,
-  #[schema(format = Uri)]
+  #[schema(example = "ghcr.io/acme/app:1.2.0")]
   pub nebula: String,
   #[schema(min_length = 1)]
   pub public_domain: Option<String>,
@@ -24,6 +32,126 @@ pub struct Star {
   #[schema(minimum = 0, maximum = 65535)]
   pub port: i32,
   pub galaxy_id: Uuid,
+  pub state: StarState,
+  #[schema(minimum = 1)]
+  pub idle_timeout: Option<i32>,
+  pub kind: StarKind,
+  pub schedule: Option<String>,
+  pub timezone: Option<String>,
+  pub concurrency_policy: ConcurrencyPolicy,
+  #[schema(minimum = 0)]
+  pub successful_jobs_history_limit: i32,
+  #[schema(minimum = 0)]
+  pub failed_jobs_history_limit: i32,
+  pub containers: StarContainers,
+  pub strategy: StrategyKind,
+  #[schema(example = "25%")]
+  pub max_surge: Option<String>,
+  #[schema(example = "25%")]
+  pub max_unavailable: Option<String>,
+  #[schema(minimum = 0, maximum = 100)]
+  pub canary_weight: i32,
+  #[schema(minimum = 1)]
+  pub run_as_user: Option<i32>,
+  #[schema(minimum = 0)]
+  pub run_as_group: Option<i32>,
+  pub read_only_root_filesystem: bool,
+  /// the star is deployed with the digest its nebula resolved to
+  pub pin_digest: bool,
+  #[schema(example = "sha256:4b5a1e2f")]
+  pub nebula_digest: Option<String>,
+  /// placement profile deciding the nodes of the star
+  #[schema(example = "high-memory")]
+  pub placement: Option<String>,
+  /// variable groups of the galaxy attached to the star, the later ones override the earlier
+  pub variable_groups: Vec<Uuid>,
+  /// config files mounted in the star pods, their contents are served by the files routes
+  pub config_files: StarConfigFiles,
+  /// variable changes were staged without a restart, the star reads them once they are applied
+  pub vars_pending: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StarState {
+  Running,
+  Stopped,
+}
+
+impl StarState {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      StarState::Running => "running",
+      StarState::Stopped => "stopped",
+    }
+  }
+}
+
+impl From<String> for StarState {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known states
+    match value.as_str() {
+      "stopped" => StarState::Stopped,
+      _ => StarState::Running,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StarKind {
+  #[default]
+  Service,
+  Cron,
+}
+
+impl StarKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      StarKind::Service => "service",
+      StarKind::Cron => "cron",
+    }
+  }
+}
+
+impl From<String> for StarKind {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known kinds
+    match value.as_str() {
+      "cron" => StarKind::Cron,
+      _ => StarKind::Service,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConcurrencyPolicy {
+  #[default]
+  Allow,
+  Forbid,
+  Replace,
+}
+
+impl ConcurrencyPolicy {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ConcurrencyPolicy::Allow => "allow",
+      ConcurrencyPolicy::Forbid => "forbid",
+      ConcurrencyPolicy::Replace => "replace",
+    }
+  }
+}
+
+impl From<String> for ConcurrencyPolicy {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known policies
+    match value.as_str() {
+      "forbid" => ConcurrencyPolicy::Forbid,
+      "replace" => ConcurrencyPolicy::Replace,
+      _ => ConcurrencyPolicy::Allow,
+    }
+  }
 }
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -33,6 +161,328 @@ pub struct DomainName {
   subdomain: Option<String>,
 }
 
+#[derive(Debug, Default, Deserialize, Validate, ToSchema)]
+pub struct Placement {
+  /// name of a placement profile of the platform, anywhere in the cluster when missing
+  #[schema(example = "high-memory")]
+  #[validate(custom(function = "validate_placement_profile"))]
+  profile: Option<String>,
+}
+
+fn validate_placement_profile(profile: &str) -> Result<(), ValidationError> {
+  if PlacementProfile::find(profile).is_none() {
+    return Err(
+      ValidationError::new("placement").with_message(Cow::from("unknown placement profile")),
+    );
+  }
+
+  Ok(())
+}
+
+/// idle stars are reached through the activator, which only proxies http,
+/// so they require a public domain and their private domain must serve http too
+#[derive(Debug, Default, Deserialize, Validate, ToSchema)]
+pub struct IdleTimeout {
+  /// minutes without requests before scaling the star to zero
+  #[schema(minimum = 1)]
+  #[validate(range(min = 1, message = "must be at least one minute"))]
+  minutes: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CronSchedule {
+  /// cron expression with five fields or a macro like `@daily`
+  #[schema(example = "0 3 * * *")]
+  #[validate(custom(function = "validate_cron_expression"))]
+  schedule: Option<String>,
+  /// IANA time zone of the schedule, the time zone of the cluster when missing
+  #[schema(example = "Europe/Rome")]
+  #[validate(custom(function = "validate_timezone"))]
+  timezone: Option<String>,
+  #[serde(default)]
+  concurrency_policy: ConcurrencyPolicy,
+  #[serde(default = "CronSchedule::default_successful_jobs_history_limit")]
+  #[schema(minimum = 0, maximum = 100)]
+  #[validate(range(min = 0, max = 100))]
+  successful_jobs_history_limit: i32,
+  #[serde(default = "CronSchedule::default_failed_jobs_history_limit")]
+  #[schema(minimum = 0, maximum = 100)]
+  #[validate(range(min = 0, max = 100))]
+  failed_jobs_history_limit: i32,
+}
+
+impl CronSchedule {
+  fn default_successful_jobs_history_limit() -> i32 {
+    3
+  }
+
+  fn default_failed_jobs_history_limit() -> i32 {
+    1
+  }
+}
+
+impl Default for CronSchedule {
+  fn default() -> Self {
+    Self {
+      schedule: None,
+      timezone: None,
+      concurrency_policy: ConcurrencyPolicy::default(),
+      successful_jobs_history_limit: Self::default_successful_jobs_history_limit(),
+      failed_jobs_history_limit: Self::default_failed_jobs_history_limit(),
+    }
+  }
+}
+
+const CRON_MACROS: [&str; 7] = [
+  "@yearly",
+  "@annually",
+  "@monthly",
+  "@weekly",
+  "@daily",
+  "@midnight",
+  "@hourly",
+];
+
+fn validate_cron_expression(schedule: &str) -> Result<(), ValidationError> {
+  if CRON_MACROS.contains(&schedule) {
+    return Ok(());
+  }
+
+  let fields = schedule.split_whitespace().collect::<Vec<_>>();
+
+  let valid_field = |field: &&str| {
+    field
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '*' | ',' | '-' | '/' | '?'))
+  };
+
+  if fields.len() != 5 || !fields.iter().all(valid_field) {
+    return Err(ValidationError::new("cron").with_message(Cow::from("invalid cron expression")));
+  }
+
+  Ok(())
+}
+
+fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+  // the cronjob controller only knows the zones of the tz database
+  if timezone.parse::<chrono_tz::Tz>().is_err() {
+    return Err(ValidationError::new("timezone").with_message(Cow::from("unknown time zone")));
+  }
+
+  Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+  /// replace the pods a few at a time
+  #[default]
+  RollingUpdate,
+  /// stop all the pods before starting the new ones, required by planets that cannot be shared
+  Recreate,
+  /// run the new version next to the current one until it is promoted
+  BlueGreen,
+  /// send a share of the traffic to the new version until it is promoted
+  Canary,
+}
+
+impl StrategyKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      StrategyKind::RollingUpdate => "rolling_update",
+      StrategyKind::Recreate => "recreate",
+      StrategyKind::BlueGreen => "blue_green",
+      StrategyKind::Canary => "canary",
+    }
+  }
+
+  /// updates with this strategy wait for a promotion
+  pub fn has_rollout(&self) -> bool {
+    matches!(self, StrategyKind::BlueGreen | StrategyKind::Canary)
+  }
+}
+
+impl From<String> for StrategyKind {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known strategies
+    match value.as_str() {
+      "recreate" => StrategyKind::Recreate,
+      "blue_green" => StrategyKind::BlueGreen,
+      "canary" => StrategyKind::Canary,
+      _ => StrategyKind::RollingUpdate,
+    }
+  }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct DeploymentStrategy {
+  #[serde(default)]
+  kind: StrategyKind,
+  /// pods created above the desired amount during a rolling update
+  #[schema(example = "25%")]
+  #[validate(custom(function = "validate_int_or_percent"))]
+  max_surge: Option<String>,
+  /// pods that can be unavailable during a rolling update
+  #[schema(example = "25%")]
+  #[validate(custom(function = "validate_int_or_percent"))]
+  max_unavailable: Option<String>,
+  /// share of the traffic sent to the new version of a canary star
+  #[serde(default = "DeploymentStrategy::default_canary_weight")]
+  #[schema(minimum = 0, maximum = 100)]
+  #[validate(range(min = 0, max = 100))]
+  canary_weight: i32,
+}
+
+impl DeploymentStrategy {
+  fn default_canary_weight() -> i32 {
+    10
+  }
+}
+
+impl Default for DeploymentStrategy {
+  fn default() -> Self {
+    Self {
+      kind: StrategyKind::default(),
+      max_surge: None,
+      max_unavailable: None,
+      canary_weight: Self::default_canary_weight(),
+    }
+  }
+}
+
+/// exceptions to the security defaults of the star pods,
+/// which always run as non root without privilege escalation nor capabilities
+#[derive(Debug, Default, Deserialize, Validate, ToSchema)]
+pub struct SecurityOptions {
+  /// fixed uid of the containers instead of the user of the image
+  #[schema(minimum = 1)]
+  #[validate(range(min = 1, message = "containers cannot run as root"))]
+  run_as_user: Option<i32>,
+  /// fixed gid of the containers, also owning the mounted planets
+  #[schema(minimum = 0)]
+  #[validate(range(min = 0))]
+  run_as_group: Option<i32>,
+  #[serde(default)]
+  read_only_root_filesystem: bool,
+}
+
+fn validate_int_or_percent(value: &str) -> Result<(), ValidationError> {
+  let digits = value.strip_suffix('%').unwrap_or(value);
+
+  if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+    return Err(
+      ValidationError::new("int_or_percent")
+        .with_message(Cow::from("must be a number of pods or a percentage")),
+    );
+  }
+
+  Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerKind {
+  /// runs to completion before the next containers start
+  Init,
+  /// keeps running next to the star container, a native sidecar
+  /// needing kubernetes 1.29 or later
+  Sidecar,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ContainerPlanet {
+  pub planet_id: Uuid,
+  #[schema(min_length = 1)]
+  #[validate(length(min = 1, message = "cannot be empty"))]
+  pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct StarContainer {
+  #[schema(min_length = 1, max_length = 63, example = "wait-for-db")]
+  #[validate(custom(function = "validate_container_name"))]
+  pub name: String,
+  pub kind: ContainerKind,
+  #[schema(min_length = 1)]
+  #[validate(custom(function = "validate_image_reference"))]
+  pub image: String,
+  /// command executed in place of the entrypoint of the image
+  #[serde(default)]
+  pub command: Vec<String>,
+  #[serde(default)]
+  pub variables: BTreeMap<String, String>,
+  #[serde(default)]
+  #[validate(nested)]
+  pub planets: Vec<ContainerPlanet>,
+}
+
+/// containers of the star pod, init containers and sidecars start in list order
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(transparent)]
+pub struct StarContainers(Vec<StarContainer>);
+
+impl StarContainers {
+  pub fn iter(&self) -> std::slice::Iter<'_, StarContainer> {
+    self.0.iter()
+  }
+
+  pub fn to_json(&self) -> serde_json::Value {
+    serde_json::to_value(self).expect("Error serializing star containers")
+  }
+}
+
+impl From<serde_json::Value> for StarContainers {
+  fn from(value: serde_json::Value) -> Self {
+    serde_json::from_value(value).expect("Invalid star containers")
+  }
+}
+
+impl Validate for StarContainers {
+  fn validate(&self) -> Result<(), ValidationErrors> {
+    self.0.validate()?;
+
+    let mut names = HashSet::new();
+
+    if self.iter().all(|container| names.insert(&container.name)) {
+      return Ok(());
+    }
+
+    let mut errors = ValidationErrors::new();
+    errors.add(
+      "containers",
+      ValidationError::new("unique")
+        .with_message(Cow::from("container names must be unique in the star")),
+    );
+
+    Err(errors)
+  }
+}
+
+fn validate_image_reference(reference: &str) -> Result<(), ValidationError> {
+  NebulaReference::parse(reference)
+    .map(|_| ())
+    .map_err(|err| ValidationError::new("image_reference").with_message(Cow::from(err.to_string())))
+}
+
+fn validate_container_name(name: &str) -> Result<(), ValidationError> {
+  // names must be valid dns labels and cannot take the name of the star container
+  let valid = (1..=63).contains(&name.len())
+    && name
+      .chars()
+      .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    && !name.starts_with('-')
+    && !name.ends_with('-')
+    && !name.starts_with("star-container");
+
+  if !valid {
+    return Err(
+      ValidationError::new("container_name").with_message(Cow::from("invalid container name")),
+    );
+  }
+
+  Ok(())
+}
+
 gen_update_data! {
   UpdateStarData,
   #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -40,8 +490,8 @@ gen_update_data! {
     #[schema(min_length = 1)]
     #[validate(length(min = 1, message = "cannot be empty"))]
     name: String,
-    #[schema(format = Uri)]
-    #[validate(length(min = 1, message = "cannot be empty"))]
+    #[schema(example = "ghcr.io/acme/app:1.2.0")]
+    #[validate(custom(function = "validate_image_reference"))]
     nebula: String,
     #[validate(nested)]
     public_domain: DomainName,
@@ -50,6 +500,49 @@ gen_update_data! {
     #[schema(minimum = 1, maximum = 65535)]
     #[validate(range(min = 1, max = 65535))]
     port: i32,
+    #[serde(default)]
+    #[validate(nested)]
+    idle_timeout: IdleTimeout,
+    #[serde(default)]
+    kind: StarKind,
+    #[serde(default)]
+    #[validate(nested)]
+    cron: CronSchedule,
+    #[serde(default)]
+    #[validate(nested)]
+    containers: StarContainers,
+    #[serde(default)]
+    #[validate(nested)]
+    strategy: DeploymentStrategy,
+    #[serde(default)]
+    #[validate(nested)]
+    security: SecurityOptions,
+    /// resolve the tag of the nebula once so the star keeps running the same image
+    #[serde(default)]
+    pin_digest: bool,
+    #[serde(default)]
+    #[validate(nested)]
+    placement: Placement,
+  }
+}
+
+impl UpdateStarData {
+  /// check the update against the current star
+  pub fn validate_for(&self, star: &Star) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+
+    if self.kind.is_some_and(|kind| kind != star.kind) {
+      errors.add(
+        "kind",
+        ValidationError::new("immutable")
+          .with_message(Cow::from("the kind of a star cannot be changed")),
+      );
+    }
+
+    match errors.is_empty() {
+      true => Ok(()),
+      false => Err(errors),
+    }
   }
 }
 
@@ -57,6 +550,325 @@ gen_update_data! {
 #[into_params(names("galaxy_id", "star_id"), parameter_in = Path)]
 pub struct StarPath(pub Uuid, pub Uuid);
 
+impl Star {
+  /// check the fields that depend on the kind of the star
+  pub fn validate_kind(&self) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+
+    let invalid =
+      |message: &'static str| ValidationError::new("kind").with_message(Cow::from(message));
+
+    if self.kind == StarKind::Cron {
+      if self.schedule.is_none() {
+        errors.add("cron", invalid("cron stars require a schedule"));
+      }
+
+      if self.public_domain.is_some() {
+        errors.add("public_domain", invalid("cron stars cannot have a domain"));
+      }
+
+      if self.private_domain.is_some() {
+        errors.add("private_domain", invalid("cron stars cannot have a domain"));
+      }
+
+      if self.idle_timeout.is_some() {
+        errors.add("idle_timeout", invalid("cron stars cannot be idle"));
+      }
+
+      if self.strategy != StrategyKind::RollingUpdate {
+        errors.add(
+          "strategy",
+          invalid("cron stars have no deployment strategy"),
+        );
+      }
+    }
+
+    if self.strategy == StrategyKind::Canary && self.public_domain.is_none() {
+      errors.add(
+        "strategy",
+        ValidationError::new("strategy")
+          .with_message(Cow::from("canary stars require a public domain")),
+      );
+    }
+
+    // a database or any other tcp service would break silently behind the http activator
+    if self.kind != StarKind::Cron && self.idle_timeout.is_some() && self.public_domain.is_none() {
+      errors.add(
+        "idle_timeout",
+        ValidationError::new("idle_timeout").with_message(Cow::from(
+          "idle stars require a public domain served over http",
+        )),
+      );
+    }
+
+    // the switch moves the selector of the service to the preview, bypassing the activator
+    if self.strategy == StrategyKind::BlueGreen && self.idle_timeout.is_some() {
+      errors.add(
+        "strategy",
+        ValidationError::new("strategy").with_message(Cow::from("blue-green stars cannot be idle")),
+      );
+    }
+
+    match errors.is_empty() {
+      true => Ok(()),
+      false => Err(errors),
+    }
+  }
+
+  /// check that the containers mount only planets of the galaxy
+  pub fn validate_containers(&self, galaxy_planets: &[Planet]) -> Result<(), ValidationErrors> {
+    let known = galaxy_planets
+      .iter()
+      .map(|planet| planet.id)
+      .collect::<HashSet<_>>();
+
+    let unknown = self
+      .containers
+      .iter()
+      .flat_map(|container| container.planets.iter())
+      .any(|mount| !known.contains(&mount.planet_id));
+
+    if !unknown {
+      return Ok(());
+    }
+
+    let mut errors = ValidationErrors::new();
+    errors.add(
+      "containers",
+      ValidationError::new("planet")
+        .with_message(Cow::from("containers can mount only planets of the galaxy")),
+    );
+
+    Err(errors)
+  }
+
+  /// sidecars are init containers restarting always, older clusters
+  /// would run them as init containers blocking the star forever
+  pub fn validate_sidecars(&self, native_sidecars: bool) -> Result<(), ValidationErrors> {
+    let sidecars = self
+      .containers
+      .iter()
+      .any(|container| container.kind == ContainerKind::Sidecar);
+
+    if !sidecars || native_sidecars {
+      return Ok(());
+    }
+
+    let mut errors = ValidationErrors::new();
+    errors.add(
+      "containers",
+      ValidationError::new("sidecar")
+        .with_message(Cow::from("sidecars need kubernetes 1.29 or later")),
+    );
+
+    Err(errors)
+  }
+
+  pub fn to_json(&self) -> serde_json::Value {
+    serde_json::to_value(self).expect("Error serializing star")
+  }
+
+  /// bring back the settings of a star serialized with `to_json`,
+  /// the kind never changes and the state is kept as it is now
+  pub async fn restore(
+    conn: &mut Connection,
+    ident: &StarPath,
+    previous: &serde_json::Value,
+  ) -> DbResult<Self> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let restored_star = sqlx::query_as!(
+      Star,
+      r#"
+      UPDATE stars
+      SET name = previous.name,
+        nebula = previous.nebula,
+        public_domain = previous.public_domain,
+        private_domain = previous.private_domain,
+        port = previous.port,
+        idle_timeout = previous.idle_timeout,
+        schedule = previous.schedule,
+        timezone = previous.timezone,
+        concurrency_policy = previous.concurrency_policy,
+        successful_jobs_history_limit = previous.successful_jobs_history_limit,
+        failed_jobs_history_limit = previous.failed_jobs_history_limit,
+        containers = previous.containers,
+        strategy = previous.strategy,
+        max_surge = previous.max_surge,
+        max_unavailable = previous.max_unavailable,
+        canary_weight = previous.canary_weight,
+        run_as_user = previous.run_as_user,
+        run_as_group = previous.run_as_group,
+        read_only_root_filesystem = previous.read_only_root_filesystem,
+        pin_digest = previous.pin_digest,
+        nebula_digest = previous.nebula_digest,
+        placement = previous.placement,
+        variable_groups = previous.variable_groups
+      FROM jsonb_populate_record(NULL::stars, $1) AS previous
+      WHERE stars.galaxy_id = $2 AND stars.id = $3
+      RETURNING stars.*
+    "#,
+      previous,
+      galaxy_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(restored_star)
+  }
+
+  pub async fn set_state(
+    conn: &mut Connection,
+    ident: &StarPath,
+    state: StarState,
+  ) -> DbResult<Self> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let updated_star = sqlx::query_as!(
+      Star,
+      "UPDATE stars SET state = $1 WHERE galaxy_id = $2 AND id = $3 RETURNING *",
+      state.as_str(),
+      galaxy_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(updated_star)
+  }
+
+  pub async fn set_nebula(conn: &mut Connection, ident: &StarPath, nebula: &str) -> DbResult<Self> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let updated_star = sqlx::query_as!(
+      Star,
+      "UPDATE stars SET nebula = $1 WHERE galaxy_id = $2 AND id = $3 RETURNING *",
+      nebula,
+      galaxy_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(updated_star)
+  }
+
+  pub async fn set_variable_groups(
+    conn: &mut Connection,
+    ident: &StarPath,
+    groups: &[Uuid],
+  ) -> DbResult<Self> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let updated_star = sqlx::query_as!(
+      Star,
+      "UPDATE stars SET variable_groups = $1 WHERE galaxy_id = $2 AND id = $3 RETURNING *",
+      groups,
+      galaxy_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(updated_star)
+  }
+
+  /// copy the mounts of the config files of the star on it, they are part of its pod spec
+  pub async fn refresh_config_files(conn: &mut Connection, ident: &StarPath) -> DbResult<Self> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let updated_star = sqlx::query_as!(
+      Star,
+      r#"
+      UPDATE stars
+      SET config_files = COALESCE(
+        (
+          SELECT jsonb_agg(
+            jsonb_build_object(
+              'id', id,
+              'mount_path', mount_path,
+              'mode', mode,
+              'kind', kind,
+              'mount_directory', mount_directory
+            )
+            ORDER BY mount_path
+          )
+          FROM config_files
+          WHERE star_id = stars.id
+        ),
+        '[]'
+      )
+      WHERE galaxy_id = $1 AND id = $2
+      RETURNING *
+    "#,
+      galaxy_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(updated_star)
+  }
+
+  pub async fn set_vars_pending(
+    conn: &mut Connection,
+    ident: &StarPath,
+    pending: bool,
+  ) -> DbResult<()> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    sqlx::query!(
+      "UPDATE stars SET vars_pending = $1 WHERE galaxy_id = $2 AND id = $3",
+      pending,
+      galaxy_id,
+      star_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+  }
+
+  /// stars the variable group is attached to
+  pub async fn all_with_variable_group(
+    conn: &mut Connection,
+    galaxy_id: &Uuid,
+    group_id: &Uuid,
+  ) -> DbResult<Vec<Self>> {
+    let stars = sqlx::query_as!(
+      Star,
+      "SELECT * FROM stars WHERE galaxy_id = $1 AND $2 = ANY(variable_groups)",
+      galaxy_id,
+      group_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(stars)
+  }
+
+  pub async fn set_nebula_digest(
+    conn: &mut Connection,
+    ident: &StarPath,
+    digest: Option<&str>,
+  ) -> DbResult<Self> {
+    let StarPath(galaxy_id, star_id) = ident;
+
+    let updated_star = sqlx::query_as!(
+      Star,
+      "UPDATE stars SET nebula_digest = $1 WHERE galaxy_id = $2 AND id = $3 RETURNING *",
+      digest,
+      galaxy_id,
+      star_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(updated_star)
+  }
+}
+
 #[async_trait]
 impl CrudOperations for Star {
   type OwnerIdent = GalaxyPath;
@@ -101,6 +913,14 @@ impl CrudOperations for Star {
       public_domain,
       private_domain,
       port,
+      idle_timeout,
+      kind,
+      cron,
+      containers,
+      strategy,
+      security,
+      pin_digest,
+      placement,
     } = data;
 
     let new_star = sqlx::query_as!(
@@ -111,13 +931,51 @@ impl CrudOperations for Star {
         public_domain,
         private_domain,
         port,
+        idle_timeout,
+        kind,
+        schedule,
+        timezone,
+        concurrency_policy,
+        successful_jobs_history_limit,
+        failed_jobs_history_limit,
+        containers,
+        strategy,
+        max_surge,
+        max_unavailable,
+        canary_weight,
+        run_as_user,
+        run_as_group,
+        read_only_root_filesystem,
+        pin_digest,
+        placement,
         galaxy_id
-      ) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *"#,
+      ) VALUES (
+        $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20,
+        $21, $22, $23
+      )
+      RETURNING *"#,
       name,
       nebula,
       public_domain.subdomain,
       private_domain.subdomain,
       port,
+      idle_timeout.minutes,
+      kind.as_str(),
+      cron.schedule,
+      cron.timezone,
+      cron.concurrency_policy.as_str(),
+      cron.successful_jobs_history_limit,
+      cron.failed_jobs_history_limit,
+      containers.to_json(),
+      strategy.kind.as_str(),
+      strategy.max_surge,
+      strategy.max_unavailable,
+      strategy.canary_weight,
+      security.run_as_user,
+      security.run_as_group,
+      security.read_only_root_filesystem,
+      pin_digest,
+      placement.profile,
       galaxy_id
     )
     .fetch_one(conn)
@@ -137,6 +995,14 @@ impl CrudOperations for Star {
       public_domain,
       private_domain,
       port,
+      idle_timeout,
+      cron,
+      containers,
+      strategy,
+      security,
+      pin_digest,
+      placement,
+      ..
     } = data;
 
     let update_public_domain = public_domain.is_some();
@@ -151,6 +1017,28 @@ impl CrudOperations for Star {
       .map(|dom| dom.subdomain.as_ref())
       .unwrap_or(None);
 
+    let update_idle_timeout = idle_timeout.is_some();
+    let idle_timeout = idle_timeout
+      .as_ref()
+      .map(|idle| idle.minutes)
+      .unwrap_or(None);
+
+    // the cron schedule, the strategy and the security options are always replaced as a whole
+    let update_cron = cron.is_some();
+    let cron = cron.as_ref();
+
+    let update_strategy = strategy.is_some();
+    let strategy = strategy.as_ref();
+
+    let update_security = security.is_some();
+    let security = security.as_ref();
+
+    let update_placement = placement.is_some();
+    let placement = placement
+      .as_ref()
+      .map(|placement| placement.profile.as_ref())
+      .unwrap_or(None);
+
     let updated_star = sqlx::query_as!(
       Star,
       r#"
@@ -159,8 +1047,24 @@ impl CrudOperations for Star {
         nebula = COALESCE($2, nebula),
         public_domain = (CASE WHEN $3 = true THEN $4 ELSE public_domain END),
         private_domain = (CASE WHEN $5 = true THEN $6 ELSE private_domain END),
-        port = COALESCE($7, port)
-      WHERE galaxy_id = $8 AND id = $9
+        port = COALESCE($7, port),
+        idle_timeout = (CASE WHEN $8 = true THEN $9 ELSE idle_timeout END),
+        schedule = (CASE WHEN $10 = true THEN $11 ELSE schedule END),
+        timezone = (CASE WHEN $10 = true THEN $12 ELSE timezone END),
+        concurrency_policy = COALESCE($13, concurrency_policy),
+        successful_jobs_history_limit = COALESCE($14, successful_jobs_history_limit),
+        failed_jobs_history_limit = COALESCE($15, failed_jobs_history_limit),
+        containers = COALESCE($16, containers),
+        strategy = COALESCE($17, strategy),
+        max_surge = (CASE WHEN $18 = true THEN $19 ELSE max_surge END),
+        max_unavailable = (CASE WHEN $18 = true THEN $20 ELSE max_unavailable END),
+        canary_weight = COALESCE($21, canary_weight),
+        run_as_user = (CASE WHEN $22 = true THEN $23 ELSE run_as_user END),
+        run_as_group = (CASE WHEN $22 = true THEN $24 ELSE run_as_group END),
+        read_only_root_filesystem = COALESCE($25, read_only_root_filesystem),
+        pin_digest = COALESCE($26, pin_digest),
+        placement = (CASE WHEN $27 = true THEN $28 ELSE placement END)
+      WHERE galaxy_id = $29 AND id = $30
       RETURNING *
     "#,
       name.as_deref(),
@@ -170,6 +1074,27 @@ impl CrudOperations for Star {
       update_private_domain,
       private_domain,
       port.as_ref(),
+      update_idle_timeout,
+      idle_timeout,
+      update_cron,
+      cron.and_then(|cron| cron.schedule.as_deref()),
+      cron.and_then(|cron| cron.timezone.as_deref()),
+      cron.map(|cron| cron.concurrency_policy.as_str()),
+      cron.map(|cron| cron.successful_jobs_history_limit),
+      cron.map(|cron| cron.failed_jobs_history_limit),
+      containers.as_ref().map(StarContainers::to_json),
+      strategy.map(|strategy| strategy.kind.as_str()),
+      update_strategy,
+      strategy.and_then(|strategy| strategy.max_surge.as_deref()),
+      strategy.and_then(|strategy| strategy.max_unavailable.as_deref()),
+      strategy.map(|strategy| strategy.canary_weight),
+      update_security,
+      security.and_then(|security| security.run_as_user),
+      security.and_then(|security| security.run_as_group),
+      security.map(|security| security.read_only_root_filesystem),
+      pin_digest.as_ref(),
+      update_placement,
+      placement,
       galaxy_id,
       star_id
     )
@@ -1,25 +1,613 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use derive_more::From;
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::{
   database::{Connection, DbResult},
+  encryption::{EncryptionResult, SealedValue},
   gen_update_data,
 };
 
-use super::{star::StarPath, CrudOperations};
+use super::{galaxy::UserId, star::StarPath, CrudOperations};
 
-#[derive(Debug, Serialize, ToSchema)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableKind {
+  /// stored in a secret, the value is write-only
+  #[default]
+  Secret,
+  /// stored in a config map, the value is always readable
+  Plain,
+}
+
+impl VariableKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      VariableKind::Secret => "secret",
+      VariableKind::Plain => "plain",
+    }
+  }
+}
+
+impl From<String> for VariableKind {
+  fn from(value: String) -> Self {
+    // the database constraint allows only the known kinds
+    match value.as_str() {
+      "plain" => VariableKind::Plain,
+      _ => VariableKind::Secret,
+    }
+  }
+}
+
+/// external secret store the value of a variable is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExternalProvider {
+  /// kv v2 secrets engine of a vault compatible store
+  Vault,
+}
+
+impl ExternalProvider {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ExternalProvider::Vault => "vault",
+    }
+  }
+}
+
+impl From<String> for ExternalProvider {
+  fn from(_value: String) -> Self {
+    // the database constraint allows only the known providers
+    ExternalProvider::Vault
+  }
+}
+
+/// key of a secret in an external store, the variable follows its latest version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ExternalReference {
+  pub provider: ExternalProvider,
+  #[schema(min_length = 1, example = "apps/billing/db")]
+  pub path: String,
+  #[schema(min_length = 1, example = "password")]
+  pub key: String,
+}
+
+#[derive(Debug, ToSchema)]
 pub struct Variable {
   pub id: Uuid,
   #[schema(min_length = 1)]
   pub name: String,
-  #[schema(min_length = 1)]
+  /// missing for secret variables, their value is read only through a reveal
+  #[schema(value_type = Option<String>, min_length = 1)]
+  pub value: SealedValue,
+  pub star_id: Uuid,
+  pub kind: VariableKind,
+  /// the value is read from an external store and refreshed by the platform
+  pub external: Option<ExternalReference>,
+}
+
+/// row of the variables table, the value is kept sealed
+struct VariableRow {
+  id: Uuid,
+  name: String,
+  value: Vec<u8>,
+  star_id: Uuid,
+  kind: VariableKind,
+  data_key: Option<Vec<u8>>,
+  key_id: Option<String>,
+  external_provider: Option<String>,
+  external_path: Option<String>,
+  external_key: Option<String>,
+}
+
+impl From<VariableRow> for Variable {
+  fn from(row: VariableRow) -> Self {
+    Self {
+      id: row.id,
+      name: row.name,
+      value: SealedValue {
+        ciphertext: row.value,
+        data_key: row.data_key,
+        key_id: row.key_id,
+      },
+      star_id: row.star_id,
+      kind: row.kind,
+      external: match (row.external_provider, row.external_path, row.external_key) {
+        (Some(provider), Some(path), Some(key)) => Some(ExternalReference {
+          provider: provider.into(),
+          path,
+          key,
+        }),
+        _ => None,
+      },
+    }
+  }
+}
+
+/// variable read from an external store with the galaxy of its star
+pub struct ExternalVariable {
+  pub galaxy_id: Uuid,
+  pub variable: Variable,
+}
+
+impl Variable {
+  pub fn is_secret(&self) -> bool {
+    self.kind == VariableKind::Secret
+  }
+
+  pub fn reveal(self) -> EncryptionResult<RevealedVariable> {
+    Ok(RevealedVariable {
+      value: self.value.open()?,
+      id: self.id,
+      name: self.name,
+    })
+  }
+
+  /// every variable of the platform, to seal them again with another master key
+  pub async fn all_sealed(conn: &mut Connection) -> DbResult<Vec<Self>> {
+    let vars = sqlx::query_as!(VariableRow, "SELECT * FROM variables")
+      .fetch_all(conn)
+      .await?;
+
+    Ok(vars.into_iter().map(Variable::from).collect())
+  }
+
+  /// variables of every star of a galaxy, to resolve the references between them
+  pub async fn all_in_galaxy(conn: &mut Connection, galaxy_id: &Uuid) -> DbResult<Vec<Self>> {
+    let vars = sqlx::query_as!(
+      VariableRow,
+      r#"SELECT variables.* FROM variables
+      JOIN stars ON stars.id = variables.star_id
+      WHERE stars.galaxy_id = $1"#,
+      galaxy_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(vars.into_iter().map(Variable::from).collect())
+  }
+
+  /// every variable read from an external store, to refresh their values
+  pub async fn all_external(conn: &mut Connection) -> DbResult<Vec<ExternalVariable>> {
+    let rows = sqlx::query!(
+      r#"SELECT stars.galaxy_id, variables.*
+      FROM variables JOIN stars ON stars.id = variables.star_id
+      WHERE variables.external_provider IS NOT NULL"#
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| ExternalVariable {
+          galaxy_id: row.galaxy_id,
+          variable: Variable::from(VariableRow {
+            id: row.id,
+            name: row.name,
+            value: row.value,
+            star_id: row.star_id,
+            kind: row.kind.into(),
+            data_key: row.data_key,
+            key_id: row.key_id,
+            external_provider: row.external_provider,
+            external_path: row.external_path,
+            external_key: row.external_key,
+          }),
+        })
+        .collect(),
+    )
+  }
+
+  /// write all the variables of an import, the others are deleted when replacing
+  pub async fn import(
+    conn: &mut Connection,
+    ident: &StarPath,
+    vars: &[(String, String)],
+    query: &BulkImportQuery,
+  ) -> DbResult<Vec<Self>> {
+    let StarPath(_, star_id) = ident;
+
+    for (name, value) in vars {
+      let value = SealedValue::seal(value)?;
+
+      sqlx::query!(
+        r#"INSERT INTO variables(name, value, data_key, key_id, kind, star_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (name, star_id) DO UPDATE
+        SET value = excluded.value, data_key = excluded.data_key, key_id = excluded.key_id,
+          external_provider = NULL, external_path = NULL, external_key = NULL"#,
+        name,
+        value.ciphertext,
+        value.data_key,
+        value.key_id,
+        query.kind.as_str(),
+        star_id
+      )
+      .execute(&mut *conn)
+      .await?;
+    }
+
+    if query.mode == BulkMode::Replace {
+      let names = vars
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+
+      sqlx::query!(
+        "DELETE FROM variables WHERE star_id = $1 AND NOT (name = ANY($2))",
+        star_id,
+        &names
+      )
+      .execute(&mut *conn)
+      .await?;
+    }
+
+    Self::all(conn, ident).await
+  }
+
+  /// put the variable back in the state of one of its versions,
+  /// an external variable keeps the restored value and stops following its store
+  pub async fn restore(
+    conn: &mut Connection,
+    ident: &VariablePath,
+    version: &VariableVersion,
+  ) -> DbResult<Self> {
+    let VariablePath(_, star_id, var_id) = ident;
+
+    let var = sqlx::query_as!(
+      VariableRow,
+      r#"UPDATE variables
+      SET name = $1, value = $2, data_key = $3, key_id = $4, kind = $5,
+        external_provider = NULL, external_path = NULL, external_key = NULL
+      WHERE star_id = $6 AND id = $7
+      RETURNING *"#,
+      version.name,
+      version.value.ciphertext,
+      version.value.data_key,
+      version.value.key_id,
+      version.kind.as_str(),
+      star_id,
+      var_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(var.into())
+  }
+
+  pub async fn set_sealed_value(
+    conn: &mut Connection,
+    var_id: &Uuid,
+    value: &SealedValue,
+  ) -> DbResult<()> {
+    sqlx::query!(
+      "UPDATE variables SET value = $1, data_key = $2, key_id = $3 WHERE id = $4",
+      value.ciphertext,
+      value.data_key,
+      value.key_id,
+      var_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+  }
+
+  /// seal the values written in clear before the encryption, the number of values sealed
+  pub async fn seal_all_in_clear(conn: &mut Connection) -> DbResult<usize> {
+    let vars = sqlx::query_as!(
+      VariableRow,
+      "SELECT * FROM variables WHERE data_key IS NULL"
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let sealed = vars.len();
+
+    for var in vars.into_iter().map(Variable::from) {
+      let value = SealedValue::seal(&var.value.open()?)?;
+      Self::set_sealed_value(conn, &var.id, &value).await?;
+    }
+
+    Ok(sealed)
+  }
+}
+
+/// the value of secret variables never leaves the api unless revealed
+impl Serialize for Variable {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let value = match self.is_secret() {
+      true => None,
+      false => Some(self.value.open().map_err(serde::ser::Error::custom)?),
+    };
+
+    let mut var = serializer.serialize_struct("Variable", 6)?;
+    var.serialize_field("id", &self.id)?;
+    var.serialize_field("name", &self.name)?;
+    var.serialize_field("value", &value)?;
+    var.serialize_field("star_id", &self.star_id)?;
+    var.serialize_field("kind", &self.kind)?;
+    var.serialize_field("external", &self.external)?;
+    var.end()
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkMode {
+  /// create the missing variables and update the others, the existing kind is kept
+  #[default]
+  Upsert,
+  /// same as upsert and delete the variables missing from the import
+  Replace,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct BulkImportQuery {
+  #[serde(default)]
+  pub mode: BulkMode,
+  /// kind of the variables created by the import
+  #[serde(default)]
+  pub kind: VariableKind,
+}
+
+fn default_restart() -> bool {
+  true
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ApplyQuery {
+  /// false stages the change, the star keeps its current variables until the next apply
+  #[serde(default = "default_restart")]
+  pub restart: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableChange {
+  Added,
+  Changed,
+  Removed,
+}
+
+/// staged variable the star does not read yet, the value is never shown
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PendingVariableChange {
+  pub name: String,
+  pub kind: VariableKind,
+  pub change: VariableChange,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkFormat {
+  #[default]
+  Dotenv,
+  Json,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct BulkExportQuery {
+  #[serde(default)]
+  pub format: BulkFormat,
+}
+
+/// value of a secret variable returned by a reveal
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevealedVariable {
+  pub id: Uuid,
+  pub name: String,
   pub value: String,
+}
+
+/// audit record of a secret value read through the api
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VariableReveal {
+  pub id: Uuid,
+  pub revealed_at: DateTime<Utc>,
+  pub variable_id: Uuid,
+  pub user_id: Uuid,
+}
+
+impl VariableReveal {
+  pub async fn create(conn: &mut Connection, var: &Variable, user_id: &UserId) -> DbResult<Self> {
+    let reveal = sqlx::query_as!(
+      VariableReveal,
+      "INSERT INTO variable_reveals(variable_id, user_id) VALUES ($1, $2) RETURNING *",
+      var.id,
+      **user_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(reveal)
+  }
+
+  /// reveals of the variable, newest first
+  pub async fn all(conn: &mut Connection, ident: &VariablePath) -> DbResult<Vec<Self>> {
+    let VariablePath(_, star_id, var_id) = ident;
+
+    let reveals = sqlx::query_as!(
+      VariableReveal,
+      r#"SELECT variable_reveals.*
+      FROM variable_reveals JOIN variables ON variables.id = variable_reveals.variable_id
+      WHERE variables.star_id = $1 AND variables.id = $2
+      ORDER BY revealed_at DESC"#,
+      star_id,
+      var_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(reveals)
+  }
+}
+
+/// state of a variable before a change, the value stays sealed
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VariableVersion {
+  pub id: Uuid,
+  pub created_at: DateTime<Utc>,
+  pub name: String,
+  pub kind: VariableKind,
+  /// missing once the variable is deleted
+  pub variable_id: Option<Uuid>,
   pub star_id: Uuid,
+  /// user who replaced this version, missing once the user is deleted
+  pub user_id: Option<Uuid>,
+  #[serde(skip)]
+  pub value: SealedValue,
+}
+
+/// row of the variable versions table
+struct VariableVersionRow {
+  id: Uuid,
+  created_at: DateTime<Utc>,
+  name: String,
+  value: Vec<u8>,
+  data_key: Option<Vec<u8>>,
+  key_id: Option<String>,
+  kind: VariableKind,
+  variable_id: Option<Uuid>,
+  star_id: Uuid,
+  user_id: Option<Uuid>,
+}
+
+impl From<VariableVersionRow> for VariableVersion {
+  fn from(row: VariableVersionRow) -> Self {
+    Self {
+      id: row.id,
+      created_at: row.created_at,
+      name: row.name,
+      kind: row.kind,
+      variable_id: row.variable_id,
+      star_id: row.star_id,
+      user_id: row.user_id,
+      value: SealedValue {
+        ciphertext: row.value,
+        data_key: row.data_key,
+        key_id: row.key_id,
+      },
+    }
+  }
+}
+
+#[derive(Debug, From, Deserialize, IntoParams)]
+#[into_params(
+  names("galaxy_id", "star_id", "variable_id", "version_id"),
+  parameter_in = Path
+)]
+pub struct VariableVersionPath(pub Uuid, pub Uuid, pub Uuid, pub Uuid);
+
+impl VariableVersion {
+  /// keep the current state of the variable before it changes or is deleted,
+  /// without a user when the platform refreshed an external value
+  pub async fn create(
+    conn: &mut Connection,
+    var: &Variable,
+    user_id: Option<&UserId>,
+  ) -> DbResult<Self> {
+    let version = sqlx::query_as!(
+      VariableVersionRow,
+      r#"INSERT INTO variable_versions(name, value, data_key, key_id, kind, variable_id, star_id, user_id)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+      RETURNING *"#,
+      var.name,
+      var.value.ciphertext,
+      var.value.data_key,
+      var.value.key_id,
+      var.kind.as_str(),
+      var.id,
+      var.star_id,
+      user_id.map(|user_id| **user_id)
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(version.into())
+  }
+
+  /// versions of the variable, newest first
+  pub async fn all(conn: &mut Connection, ident: &VariablePath) -> DbResult<Vec<Self>> {
+    let VariablePath(_, star_id, var_id) = ident;
+
+    let versions = sqlx::query_as!(
+      VariableVersionRow,
+      r#"SELECT * FROM variable_versions
+      WHERE star_id = $1 AND variable_id = $2
+      ORDER BY created_at DESC"#,
+      star_id,
+      var_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(versions.into_iter().map(VariableVersion::from).collect())
+  }
+
+  /// versions of every variable of the star with the deleted ones, newest first
+  pub async fn all_in_star(conn: &mut Connection, ident: &StarPath) -> DbResult<Vec<Self>> {
+    let StarPath(_, star_id) = ident;
+
+    let versions = sqlx::query_as!(
+      VariableVersionRow,
+      "SELECT * FROM variable_versions WHERE star_id = $1 ORDER BY created_at DESC",
+      star_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(versions.into_iter().map(VariableVersion::from).collect())
+  }
+
+  pub async fn get(conn: &mut Connection, ident: &VariableVersionPath) -> DbResult<Self> {
+    let VariableVersionPath(_, star_id, var_id, version_id) = ident;
+
+    let version = sqlx::query_as!(
+      VariableVersionRow,
+      "SELECT * FROM variable_versions WHERE star_id = $1 AND variable_id = $2 AND id = $3",
+      star_id,
+      var_id,
+      version_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(version.into())
+  }
+
+  /// every version of the platform, to seal them again with another master key
+  pub async fn all_sealed(conn: &mut Connection) -> DbResult<Vec<Self>> {
+    let versions = sqlx::query_as!(VariableVersionRow, "SELECT * FROM variable_versions")
+      .fetch_all(conn)
+      .await?;
+
+    Ok(versions.into_iter().map(VariableVersion::from).collect())
+  }
+
+  pub async fn set_sealed_value(
+    conn: &mut Connection,
+    version_id: &Uuid,
+    value: &SealedValue,
+  ) -> DbResult<()> {
+    sqlx::query!(
+      "UPDATE variable_versions SET value = $1, data_key = $2, key_id = $3 WHERE id = $4",
+      value.ciphertext,
+      value.data_key,
+      value.key_id,
+      version_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+  }
 }
 
 gen_update_data! {
@@ -29,9 +617,54 @@ gen_update_data! {
     #[schema(min_length = 1)]
     #[validate(length(min = 1, message = "cannot be empty"))]
     name: String,
+    /// read from the store instead for an external variable
     #[schema(min_length = 1)]
     #[validate(length(min = 1, message = "cannot be empty"))]
-    value: String,
+    #[serde(default)]
+    pub value: String,
+    #[serde(default)]
+    pub kind: VariableKind,
+    /// a new value replaces the reference of an external variable
+    #[serde(default)]
+    pub external: Option<ExternalReference>,
+  }
+}
+
+/// a variable has a value or a reference to one, never both
+fn check_external(
+  external: Option<&ExternalReference>,
+  has_value: bool,
+) -> Result<Option<&ExternalReference>, ValidationErrors> {
+  let message = match external {
+    None => return Ok(None),
+    Some(_) if has_value => "cannot be given with a value, it is read from the store",
+    Some(external) if external.path.trim_matches('/').is_empty() || external.key.is_empty() => {
+      "path and key cannot be empty"
+    }
+    Some(external) => return Ok(Some(external)),
+  };
+
+  let mut errors = ValidationErrors::new();
+  errors.add(
+    "external",
+    ValidationError::new("external").with_message(Cow::from(message)),
+  );
+
+  Err(errors)
+}
+
+impl CreateVariableData {
+  pub fn external_reference(&self) -> Result<Option<&ExternalReference>, ValidationErrors> {
+    check_external(self.external.as_ref(), !self.value.is_empty())
+  }
+}
+
+impl UpdateVariableData {
+  pub fn external_reference(&self) -> Result<Option<&ExternalReference>, ValidationErrors> {
+    check_external(
+      self.external.as_ref().and_then(Option::as_ref),
+      self.value.is_some(),
+    )
   }
 }
 
@@ -50,21 +683,21 @@ impl CrudOperations for Variable {
     let StarPath(_, star_id) = ident;
 
     let vars = sqlx::query_as!(
-      Variable,
+      VariableRow,
       "SELECT * FROM variables WHERE star_id = $1",
       star_id
     )
     .fetch_all(conn)
     .await?;
 
-    Ok(vars)
+    Ok(vars.into_iter().map(Variable::from).collect())
   }
 
   async fn get(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
     let VariablePath(_, star_id, var_id) = ident;
 
     let var = sqlx::query_as!(
-      Variable,
+      VariableRow,
       "SELECT * FROM variables WHERE star_id = $1 AND id = $2",
       star_id,
       var_id
@@ -72,7 +705,7 @@ impl CrudOperations for Variable {
     .fetch_one(conn)
     .await?;
 
-    Ok(var)
+    Ok(Variable::from(var))
   }
 
   async fn create(
@@ -81,19 +714,34 @@ impl CrudOperations for Variable {
     data: &Self::CreateData,
   ) -> DbResult<Self> {
     let StarPath(_, star_id) = ident;
-    let CreateVariableData { name, value } = data;
+    let CreateVariableData {
+      name,
+      value,
+      kind,
+      external,
+    } = data;
+    let value = SealedValue::seal(value)?;
 
     let var = sqlx::query_as!(
-      Variable,
-      "INSERT INTO variables(name, value, star_id) VALUES ($1, $2, $3) RETURNING *",
+      VariableRow,
+      r#"INSERT INTO variables(name, value, data_key, key_id, kind, star_id,
+        external_provider, external_path, external_key)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+      RETURNING *"#,
       name,
-      value,
-      star_id
+      value.ciphertext,
+      value.data_key,
+      value.key_id,
+      kind.as_str(),
+      star_id,
+      external.as_ref().map(|external| external.provider.as_str()),
+      external.as_ref().map(|external| external.path.as_str()),
+      external.as_ref().map(|external| external.key.as_str())
     )
     .fetch_one(conn)
     .await?;
 
-    Ok(var)
+    Ok(Variable::from(var))
   }
 
   async fn update(
@@ -102,30 +750,51 @@ impl CrudOperations for Variable {
     data: &Self::UpdateData,
   ) -> DbResult<Self> {
     let VariablePath(_, star_id, var_id) = ident;
-    let UpdateVariableData { name, value } = data;
+    let UpdateVariableData {
+      name,
+      value,
+      kind,
+      external,
+    } = data;
+    let value = value.as_deref().map(SealedValue::seal).transpose()?;
+    let external = external.as_ref().and_then(Option::as_ref);
 
+    // the data key and the reference are replaced with the value
     let new_var = sqlx::query_as!(
-      Variable,
+      VariableRow,
       r#"UPDATE variables
-      SET name = COALESCE($1, name), value = COALESCE($2, value)
-      WHERE star_id = $3 AND id = $4
+      SET name = COALESCE($1, name),
+        value = COALESCE($2, value),
+        data_key = (CASE WHEN $2::BYTEA IS NULL THEN data_key ELSE $3 END),
+        key_id = (CASE WHEN $2::BYTEA IS NULL THEN key_id ELSE $4 END),
+        kind = COALESCE($5, kind),
+        external_provider = (CASE WHEN $2::BYTEA IS NULL THEN external_provider ELSE $8 END),
+        external_path = (CASE WHEN $2::BYTEA IS NULL THEN external_path ELSE $9 END),
+        external_key = (CASE WHEN $2::BYTEA IS NULL THEN external_key ELSE $10 END)
+      WHERE star_id = $6 AND id = $7
       RETURNING *"#,
       name.as_deref(),
-      value.as_deref(),
+      value.as_ref().map(|value| value.ciphertext.as_slice()),
+      value.as_ref().and_then(|value| value.data_key.as_deref()),
+      value.as_ref().and_then(|value| value.key_id.as_deref()),
+      kind.map(|kind| kind.as_str()),
       star_id,
-      var_id
+      var_id,
+      external.map(|external| external.provider.as_str()),
+      external.map(|external| external.path.as_str()),
+      external.map(|external| external.key.as_str())
     )
     .fetch_one(conn)
     .await?;
 
-    Ok(new_var)
+    Ok(Variable::from(new_var))
   }
 
   async fn delete(conn: &mut Connection, ident: &Self::ResourceIdent) -> DbResult<Self> {
     let VariablePath(_, star_id, var_id) = ident;
 
-    let deleted_star = sqlx::query_as!(
-      Variable,
+    let deleted_var = sqlx::query_as!(
+      VariableRow,
       "DELETE FROM variables WHERE star_id = $1 AND id = $2 RETURNING *",
       star_id,
       var_id
@@ -133,6 +802,6 @@ impl CrudOperations for Variable {
     .fetch_one(conn)
     .await?;
 
-    Ok(deleted_star)
+    Ok(Variable::from(deleted_var))
   }
 }
@@ -1,4 +1,4 @@
-use crate::{auth, error, models, routes};
+use crate::{auth, error, models, placement, routes, storage};
 use utoipa::{
   openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
   Modify, OpenApi,
@@ -38,22 +38,81 @@ impl Modify for SecurityAddon {
     routes::star::create_star,
     routes::star::update_star,
     routes::star::delete_star,
+    routes::star::stop_star,
+    routes::star::start_star,
+    routes::star::restart_star,
+    routes::rollout::get_rollout,
+    routes::rollout::promote_star,
+    routes::rollout::abort_rollout,
+    routes::cron::get_cron_runs,
+    routes::event::get_star_events,
+    routes::event::get_planet_events,
+    routes::metrics::get_star_usage,
+    routes::metrics::get_star_metrics_history,
+    routes::metrics::get_galaxy_metrics_history,
+    routes::run::get_all_runs,
+    routes::run::create_run,
+    routes::run::get_run,
+    routes::run::get_run_logs,
+    routes::build::get_all_builds,
+    routes::build::create_build,
+    routes::build::get_build,
+    routes::build::get_build_logs,
+    routes::image::get_all_images,
+    routes::image::upload_image,
+    routes::image::collect_images,
+    routes::placement::get_all_placements,
+    routes::storage::get_all_storage_tiers,
     routes::var::get_all_star_vars,
     routes::var::get_star_var,
     routes::var::create_star_var,
     routes::var::update_star_var,
     routes::var::delete_star_var,
+    routes::var::reveal_star_var,
+    routes::var::get_star_var_reveals,
+    routes::var::get_star_var_versions,
+    routes::var::get_star_vars_history,
+    routes::var::restore_star_var_version,
+    routes::var::import_star_vars,
+    routes::var::export_star_vars,
+    routes::var::get_star_pending_vars,
+    routes::var::apply_star_vars,
+    routes::group::get_all_groups,
+    routes::group::get_group,
+    routes::group::create_group,
+    routes::group::update_group,
+    routes::group::delete_group,
+    routes::group::set_group_variable,
+    routes::group::delete_group_variable,
+    routes::group::get_star_groups,
+    routes::group::attach_star_group,
+    routes::group::detach_star_group,
+    routes::file::get_all_star_files,
+    routes::file::create_star_file,
+    routes::file::get_star_file,
+    routes::file::update_star_file,
+    routes::file::delete_star_file,
     routes::planet::get_all_planets,
     routes::planet::get_planet,
     routes::planet::create_planet,
     routes::planet::update_planet,
     routes::planet::delete_planet,
+    routes::snapshot::get_all_snapshots,
+    routes::snapshot::get_galaxy_snapshots,
+    routes::snapshot::create_snapshot,
+    routes::snapshot::get_snapshot,
+    routes::snapshot::delete_snapshot,
+    routes::snapshot::restore_snapshot,
   ),
   components(
     schemas(
       error::ErrorMessage,
       routes::auth::AuthData,
       routes::star::StarStatus,
+      routes::cron::CronRunStatus,
+      routes::cron::CronRun,
+      routes::event::EventType,
+      routes::event::ResourceEvent,
       models::user::User,
       auth::Password,
       models::user::Credentials,
@@ -61,19 +120,79 @@ impl Modify for SecurityAddon {
       models::galaxy::CreateGalaxyData,
       models::galaxy::UpdateGalaxyData,
       models::star::Star,
+      models::star::StarState,
       models::star::DomainName,
+      models::star::IdleTimeout,
+      models::star::StarKind,
+      models::star::ConcurrencyPolicy,
+      models::star::CronSchedule,
+      models::star::StrategyKind,
+      models::star::DeploymentStrategy,
+      models::star::SecurityOptions,
+      models::star::Placement,
+      placement::PlacementProfile,
+      placement::Toleration,
+      placement::TolerationOperator,
+      placement::TaintEffect,
+      placement::TopologySpread,
+      placement::WhenUnsatisfiable,
+      placement::AntiAffinity,
+      storage::StorageTier,
+      models::rollout::Rollout,
+      models::metrics::ReplicaUsage,
+      models::metrics::MetricsSample,
+      models::metrics::MetricsRange,
+      models::star::ContainerKind,
+      models::star::ContainerPlanet,
+      models::star::StarContainer,
+      models::star::StarContainers,
       models::star::CreateStarData,
       models::star::UpdateStarData,
       models::var::Variable,
+      models::var::VariableKind,
+      models::var::RevealedVariable,
+      models::var::VariableReveal,
+      models::var::VariableVersion,
       models::var::CreateVariableData,
       models::var::UpdateVariableData,
+      models::var::BulkMode,
+      models::var::BulkFormat,
+      models::var::VariableChange,
+      models::var::PendingVariableChange,
+      models::var::ExternalProvider,
+      models::var::ExternalReference,
+      models::group::VariableGroup,
+      models::group::CreateVariableGroupData,
+      models::group::UpdateVariableGroupData,
+      models::group::SetGroupVariableData,
+      models::group::AttachedVariableGroup,
+      models::group::VariableOverride,
+      models::file::ConfigFile,
+      models::file::ConfigFileMount,
+      models::file::StarConfigFiles,
+      models::file::FileMode,
+      models::file::CreateConfigFileData,
+      models::file::UpdateConfigFileData,
+      models::run::Run,
+      models::run::RunStatus,
+      models::run::CreateRunData,
+      models::build::Build,
+      models::build::BuildStatus,
+      routes::image::Image,
       models::planet::Planet,
       models::planet::ConnectPlanetToStar,
       models::planet::CreatePlanetData,
       models::planet::UpdatePlanetData,
+      models::planet::StorageTierName,
+      models::planet::PlanetResizeState,
+      models::snapshot::PlanetSnapshot,
+      models::snapshot::SnapshotStatus,
+      models::snapshot::CreateSnapshotData,
+      models::snapshot::RestoreSnapshotData,
     ),
     responses(
       error::UnauthorizeResponse,
+      error::ForbiddenResponse,
       error::NotFoundResponse,
       error::AlreadyExistsResponse,
       error::ValidationResponse,
@@ -90,16 +209,67 @@ impl Modify for SecurityAddon {
       routes::star::StarCreated,
       routes::star::StarUpdated,
       routes::star::StarDeleted,
+      routes::star::StarStopped,
+      routes::star::StarStarted,
+      routes::star::StarRestarted,
+      routes::rollout::SpecificRollout,
+      routes::rollout::StarPromoted,
+      routes::rollout::TrafficSwitched,
+      routes::rollout::StarRolledBack,
+      routes::cron::CronRunsList,
+      routes::event::ResourceEvents,
+      routes::metrics::StarUsageList,
+      routes::metrics::MetricsSeries,
+      routes::run::RunsList,
+      routes::run::RunCreated,
+      routes::run::SpecificRun,
+      routes::run::RunLogs,
+      routes::build::BuildsList,
+      routes::build::BuildCreated,
+      routes::build::SpecificBuild,
+      routes::build::BuildLogs,
+      routes::image::ImagesList,
+      routes::image::ImageUploaded,
+      routes::image::ImagesCollected,
+      routes::placement::PlacementsList,
+      routes::storage::StorageTiersList,
       routes::var::StarVariablesList,
       routes::var::SpecificStarVariable,
       routes::var::StarVariableCreated,
       routes::var::StarVariableUpdated,
       routes::var::StarVariableDeleted,
+      routes::var::StarVariableRevealed,
+      routes::var::StarVariableReveals,
+      routes::var::StarVariableVersions,
+      routes::var::StarVariablesHistory,
+      routes::var::StarVariableRestored,
+      routes::var::StarVariablesImported,
+      routes::var::StarPendingChanges,
+      routes::var::StarChangesApplied,
+      routes::group::VariableGroupsList,
+      routes::group::VariableGroupCreated,
+      routes::group::SpecificVariableGroup,
+      routes::group::VariableGroupUpdated,
+      routes::group::VariableGroupDeleted,
+      routes::group::GroupVariableSet,
+      routes::group::GroupVariableDeleted,
+      routes::group::StarVariableGroups,
+      routes::file::StarConfigFilesList,
+      routes::file::StarConfigFileCreated,
+      routes::file::SpecificStarConfigFile,
+      routes::file::StarConfigFileUpdated,
+      routes::file::StarConfigFileDeleted,
       routes::planet::PlanetsList,
       routes::planet::SpecificPlanet,
       routes::planet::PlanetCreated,
       routes::planet::PlanetUpdated,
       routes::planet::PlanetDeleted,
+      routes::snapshot::SnapshotsList,
+      routes::snapshot::GalaxySnapshotsList,
+      routes::snapshot::SnapshotCreated,
+      routes::snapshot::SpecificSnapshot,
+      routes::snapshot::SnapshotDeleted,
+      routes::snapshot::SnapshotRestored,
     )
   )
 )]
@@ -0,0 +1,117 @@
+//! placement profiles defined by the platform, the stars can only select one of them
+
+use std::{
+  collections::{BTreeMap, HashSet},
+  fs,
+  sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::config;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+pub enum TolerationOperator {
+  #[default]
+  Equal,
+  Exists,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub enum TaintEffect {
+  NoSchedule,
+  PreferNoSchedule,
+  NoExecute,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Toleration {
+  /// taint key tolerated, every taint when missing with the `Exists` operator
+  #[schema(example = "dedicated")]
+  pub key: Option<String>,
+  #[serde(default)]
+  pub operator: TolerationOperator,
+  #[schema(example = "high-memory")]
+  pub value: Option<String>,
+  /// taint effect tolerated, every effect when missing
+  pub effect: Option<TaintEffect>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+pub enum WhenUnsatisfiable {
+  DoNotSchedule,
+  #[default]
+  ScheduleAnyway,
+}
+
+/// replicas of a star spread over the domains of a node label
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TopologySpread {
+  #[schema(example = "topology.kubernetes.io/zone")]
+  pub topology_key: String,
+  /// largest difference of replicas between two domains
+  #[schema(minimum = 1)]
+  #[serde(default = "default_max_skew")]
+  pub max_skew: i32,
+  #[serde(default)]
+  pub when_unsatisfiable: WhenUnsatisfiable,
+}
+
+fn default_max_skew() -> i32 {
+  1
+}
+
+/// replicas of a star kept away from each other
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AntiAffinity {
+  #[schema(example = "kubernetes.io/hostname")]
+  pub topology_key: String,
+  /// replicas stay pending instead of sharing a domain
+  #[serde(default)]
+  pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PlacementProfile {
+  #[schema(example = "high-memory")]
+  pub name: String,
+  pub description: Option<String>,
+  #[serde(default)]
+  pub node_selector: BTreeMap<String, String>,
+  #[serde(default)]
+  pub tolerations: Vec<Toleration>,
+  #[serde(default)]
+  pub topology_spread: Vec<TopologySpread>,
+  pub anti_affinity: Option<AntiAffinity>,
+}
+
+impl PlacementProfile {
+  pub fn find(name: &str) -> Option<&'static Self> {
+    profiles().iter().find(|profile| profile.name == name)
+  }
+}
+
+fn load_profiles() -> Vec<PlacementProfile> {
+  let Some(path) = &config().placement_profiles else {
+    return vec![];
+  };
+
+  let content = fs::read_to_string(path).expect("Unable to read the placement profiles");
+  let profiles: Vec<PlacementProfile> =
+    serde_json::from_str(&content).expect("Invalid placement profiles");
+
+  let mut names = HashSet::new();
+  if !profiles.iter().all(|profile| names.insert(&profile.name)) {
+    panic!("Placement profile names must be unique");
+  }
+
+  profiles
+}
+
+static PROFILES: OnceLock<Vec<PlacementProfile>> = OnceLock::new();
+
+/// profiles of the json file configured by the platform, none when there is no file
+pub fn profiles() -> &'static [PlacementProfile] {
+  PROFILES.get_or_init(load_profiles)
+}
@@ -0,0 +1,239 @@
+use std::{
+  collections::HashMap,
+  io::{Cursor, Read},
+};
+
+use derive_more::Display;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+pub const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+const OCI_CONFIG: &str = "application/vnd.oci.image.config.v1+json";
+const OCI_LAYER: &str = "application/vnd.oci.image.layer.v1.tar";
+const DOCKER_MANIFEST_LIST: &str = "application/vnd.docker.distribution.manifest.list.v2+json";
+
+#[derive(Debug, Display)]
+pub enum ArchiveError {
+  #[display(fmt = "the archive is not a valid tar file")]
+  InvalidTar,
+  #[display(fmt = "the archive is neither an OCI layout nor a docker save output")]
+  UnknownLayout,
+  #[display(fmt = "the archive contains no image")]
+  NoImage,
+  #[display(fmt = "the archive is missing the file {_0}")]
+  MissingFile(String),
+  #[display(fmt = "the file {_0} of the archive is not valid json")]
+  InvalidJson(String),
+}
+
+pub fn digest(data: &[u8]) -> String {
+  format!("sha256:{}", hex::encode(Sha256::digest(data)))
+}
+
+/// content addressed blob of an image
+pub struct Blob<'a> {
+  pub digest: String,
+  pub data: &'a [u8],
+}
+
+/// image manifest with the blobs it references, ready to be pushed
+pub struct ImageContent<'a> {
+  pub blobs: Vec<Blob<'a>>,
+  pub media_type: String,
+  pub manifest: Vec<u8>,
+}
+
+/// files of an uploaded `docker save` or OCI layout tarball, optionally gzipped
+pub struct ImageArchive {
+  files: HashMap<String, Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerManifest {
+  config: String,
+  layers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+  media_type: Option<String>,
+  digest: String,
+}
+
+#[derive(Deserialize)]
+struct Index {
+  manifests: Vec<Descriptor>,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+  config: Descriptor,
+  layers: Vec<Descriptor>,
+}
+
+impl ImageArchive {
+  pub fn read(data: &[u8]) -> Result<Self, ArchiveError> {
+    let reader: Box<dyn Read> = match data.starts_with(&GZIP_MAGIC) {
+      true => Box::new(GzDecoder::new(Cursor::new(data))),
+      false => Box::new(Cursor::new(data)),
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    let mut files = HashMap::new();
+
+    let entries = archive.entries().map_err(|_| ArchiveError::InvalidTar)?;
+
+    for entry in entries {
+      let mut entry = entry.map_err(|_| ArchiveError::InvalidTar)?;
+
+      if !entry.header().entry_type().is_file() {
+        continue;
+      }
+
+      let path = entry
+        .path()
+        .map_err(|_| ArchiveError::InvalidTar)?
+        .to_string_lossy()
+        .trim_start_matches("./")
+        .to_string();
+
+      let mut content = Vec::with_capacity(entry.size() as usize);
+      entry
+        .read_to_end(&mut content)
+        .map_err(|_| ArchiveError::InvalidTar)?;
+
+      files.insert(path, content);
+    }
+
+    Ok(Self { files })
+  }
+
+  fn file(&self, path: &str) -> Result<&[u8], ArchiveError> {
+    self
+      .files
+      .get(path)
+      .map(Vec::as_slice)
+      .ok_or_else(|| ArchiveError::MissingFile(path.to_string()))
+  }
+
+  fn json<T: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<T, ArchiveError> {
+    serde_json::from_slice(self.file(path)?)
+      .map_err(|_| ArchiveError::InvalidJson(path.to_string()))
+  }
+
+  fn blob_path(digest: &str) -> String {
+    format!("blobs/{}", digest.replacen(':', "/", 1))
+  }
+
+  /// first image of the archive, the OCI layout is preferred when both are present
+  pub fn image(&self) -> Result<ImageContent<'_>, ArchiveError> {
+    if self.files.contains_key("oci-layout") && self.files.contains_key("index.json") {
+      return self.oci_image();
+    }
+
+    if self.files.contains_key("manifest.json") {
+      return self.docker_image();
+    }
+
+    Err(ArchiveError::UnknownLayout)
+  }
+
+  /// image manifest of an OCI layout, the first available platform of an index is taken
+  fn oci_image(&self) -> Result<ImageContent<'_>, ArchiveError> {
+    let index: Index = self.json("index.json")?;
+    let mut descriptors = index.manifests;
+
+    while !descriptors.is_empty() {
+      let descriptor = descriptors.remove(0);
+      let path = Self::blob_path(&descriptor.digest);
+
+      // multi platform saves contain only the blobs of some platforms
+      if !self.files.contains_key(&path) {
+        continue;
+      }
+
+      let media_type = descriptor.media_type.unwrap_or(OCI_MANIFEST.to_string());
+
+      if media_type == OCI_INDEX || media_type == DOCKER_MANIFEST_LIST {
+        let nested: Index = self.json(&path)?;
+        descriptors.splice(0..0, nested.manifests);
+        continue;
+      }
+
+      let manifest: Manifest = self.json(&path)?;
+
+      let blobs = std::iter::once(&manifest.config)
+        .chain(manifest.layers.iter())
+        .map(|blob| {
+          Ok(Blob {
+            digest: blob.digest.clone(),
+            data: self.file(&Self::blob_path(&blob.digest))?,
+          })
+        })
+        .collect::<Result<Vec<_>, ArchiveError>>()?;
+
+      return Ok(ImageContent {
+        blobs,
+        media_type,
+        manifest: self.file(&path)?.to_vec(),
+      });
+    }
+
+    Err(ArchiveError::NoImage)
+  }
+
+  /// OCI manifest built from the uncompressed layers of a `docker save` output
+  fn docker_image(&self) -> Result<ImageContent<'_>, ArchiveError> {
+    let manifests: Vec<DockerManifest> = self.json("manifest.json")?;
+    let image = manifests.first().ok_or(ArchiveError::NoImage)?;
+
+    let config = Blob {
+      digest: digest(self.file(&image.config)?),
+      data: self.file(&image.config)?,
+    };
+
+    let layers = image
+      .layers
+      .iter()
+      .map(|layer| {
+        let data = self.file(layer)?;
+
+        Ok(Blob {
+          digest: digest(data),
+          data,
+        })
+      })
+      .collect::<Result<Vec<_>, ArchiveError>>()?;
+
+    let manifest = json!({
+      "schemaVersion": 2,
+      "mediaType": OCI_MANIFEST,
+      "config": {
+        "mediaType": OCI_CONFIG,
+        "digest": config.digest,
+        "size": config.data.len(),
+      },
+      "layers": layers
+        .iter()
+        .map(|layer| json!({
+          "mediaType": OCI_LAYER,
+          "digest": layer.digest,
+          "size": layer.data.len(),
+        }))
+        .collect::<Vec<Value>>(),
+    });
+
+    Ok(ImageContent {
+      blobs: std::iter::once(config).chain(layers).collect(),
+      media_type: OCI_MANIFEST.to_string(),
+      manifest: serde_json::to_vec(&manifest).expect("Error serializing image manifest"),
+    })
+  }
+}
@@ -0,0 +1,391 @@
+//! client of the registry receiving the images built or uploaded for the stars
+
+use derive_more::{Display, Error, From};
+use reqwest::{header, Client, StatusCode};
+use serde::Deserialize;
+
+use crate::config::config;
+use crate::models::star::Star;
+
+mod archive;
+mod reference;
+
+pub use archive::{ArchiveError, ImageArchive, ImageContent};
+pub use reference::{NebulaReference, ReferenceError};
+
+/// manifests accepted when resolving a reference
+const MANIFEST_TYPES: &str = "application/vnd.oci.image.manifest.v1+json, \
+  application/vnd.oci.image.index.v1+json, \
+  application/vnd.docker.distribution.manifest.v2+json, \
+  application/vnd.docker.distribution.manifest.list.v2+json";
+const CATALOG_PAGE_SIZE: usize = 1000;
+/// host serving the api of the default registry
+const DEFAULT_REGISTRY_API: &str = "registry-1.docker.io";
+
+#[derive(Debug, Display, Error, From)]
+pub enum RegistryError {
+  #[display(fmt = "Registry request failed: {_0}")]
+  Http(reqwest::Error),
+  #[display(fmt = "Registry answered {_0} to {_1}")]
+  #[from(ignore)]
+  Status(
+    #[error(not(source))] StatusCode,
+    #[error(not(source))] String,
+  ),
+  /// the registry storage runs without `REGISTRY_STORAGE_DELETE_ENABLED`
+  #[display(fmt = "The registry does not support deletion")]
+  DeleteDisabled,
+}
+
+pub type RegistryResult<T> = Result<T, RegistryError>;
+
+/// image stored in the platform registry
+pub struct ImageReference<'a> {
+  pub repository: &'a str,
+  pub tag: Option<&'a str>,
+  pub digest: Option<&'a str>,
+}
+
+impl<'a> ImageReference<'a> {
+  /// reference of a nebula pointing to the platform registry
+  pub fn parse(nebula: &'a str) -> Option<Self> {
+    let name = nebula
+      .strip_prefix(config().registry_host.as_str())?
+      .strip_prefix('/')?;
+
+    let (name, digest) = match name.split_once('@') {
+      Some((name, digest)) => (name, Some(digest)),
+      None => (name, None),
+    };
+
+    // a colon after the last slash separates the tag
+    let (repository, tag) = match name.rsplit_once(':') {
+      Some((repository, tag)) if !tag.contains('/') => (repository, Some(tag)),
+      _ => (name, None),
+    };
+
+    Some(Self {
+      repository,
+      tag,
+      digest,
+    })
+  }
+}
+
+impl Star {
+  /// repository of the images built or uploaded for the star
+  pub fn repository(&self) -> String {
+    format!("galaxy-{}/star-{}", self.galaxy_id, self.id)
+  }
+}
+
+/// name of a repository of the platform registry as pulled by the cluster
+pub fn image_name(repository: &str) -> String {
+  format!("{}/{}", config().registry_host, repository)
+}
+
+/// full reference of an image of the platform registry with a tag
+pub fn tagged(repository: &str, tag: &str) -> String {
+  format!("{}:{}", image_name(repository), tag)
+}
+
+/// full reference of an image of the platform registry pinned to its digest
+pub fn nebula(repository: &str, digest: &str) -> String {
+  format!("{}@{}", image_name(repository), digest)
+}
+
+#[derive(Deserialize)]
+struct Catalog {
+  repositories: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TagsList {
+  tags: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct AnonymousToken {
+  #[serde(alias = "access_token")]
+  token: String,
+}
+
+pub struct RegistryClient {
+  http: Client,
+  base_url: String,
+}
+
+impl Default for RegistryClient {
+  fn default() -> Self {
+    let scheme = match config().registry_insecure {
+      true => "http",
+      false => "https",
+    };
+
+    Self {
+      http: Client::new(),
+      base_url: format!("{}://{}", scheme, config().registry_host),
+    }
+  }
+}
+
+/// parameters of a bearer challenge, `Bearer realm="...",service="...",scope="..."`
+fn bearer_challenge(response: &reqwest::Response) -> Option<Vec<(String, String)>> {
+  let challenge = response
+    .headers()
+    .get(header::WWW_AUTHENTICATE)?
+    .to_str()
+    .ok()?
+    .strip_prefix("Bearer ")?;
+
+  Some(
+    challenge
+      .split(',')
+      .filter_map(|param| {
+        let (key, value) = param.trim().split_once('=')?;
+        Some((key.to_string(), value.trim_matches('"').to_string()))
+      })
+      .collect(),
+  )
+}
+
+/// error of the registry for an unexpected status
+fn check(
+  response: reqwest::Response,
+  expected: &[StatusCode],
+) -> RegistryResult<reqwest::Response> {
+  match expected.contains(&response.status()) {
+    true => Ok(response),
+    false => Err(RegistryError::Status(
+      response.status(),
+      response.url().path().to_string(),
+    )),
+  }
+}
+
+impl RegistryClient {
+  /// client of the registry serving the domain of a reference,
+  /// other registries than the platform one are only read anonymously
+  pub fn for_domain(domain: &str) -> Self {
+    if domain == config().registry_host {
+      return Self::default();
+    }
+
+    let host = match domain {
+      reference::DEFAULT_DOMAIN => DEFAULT_REGISTRY_API,
+      _ => domain,
+    };
+
+    Self {
+      http: Client::new(),
+      base_url: format!("https://{}", host),
+    }
+  }
+
+  fn url(&self, path: &str) -> String {
+    format!("{}/v2/{}", self.base_url, path)
+  }
+
+  async fn blob_exists(&self, repository: &str, digest: &str) -> RegistryResult<bool> {
+    let response = self
+      .http
+      .head(self.url(&format!("{}/blobs/{}", repository, digest)))
+      .send()
+      .await?;
+
+    Ok(response.status() == StatusCode::OK)
+  }
+
+  /// monolithic upload of a blob missing from the repository
+  async fn push_blob(&self, repository: &str, digest: &str, data: &[u8]) -> RegistryResult<()> {
+    if self.blob_exists(repository, digest).await? {
+      return Ok(());
+    }
+
+    let response = self
+      .http
+      .post(self.url(&format!("{}/blobs/uploads/", repository)))
+      .send()
+      .await?;
+    let response = check(response, &[StatusCode::ACCEPTED])?;
+
+    let location = response
+      .headers()
+      .get(header::LOCATION)
+      .and_then(|location| location.to_str().ok())
+      .unwrap_or_default();
+
+    // the location is relative to the registry or already absolute
+    let mut upload_url = match location.starts_with('/') {
+      true => reqwest::Url::parse(&format!("{}{}", self.base_url, location)),
+      false => reqwest::Url::parse(location),
+    }
+    .map_err(|_| RegistryError::Status(response.status(), location.to_string()))?;
+    upload_url.query_pairs_mut().append_pair("digest", digest);
+
+    let response = self
+      .http
+      .put(upload_url)
+      .header(header::CONTENT_TYPE, "application/octet-stream")
+      .body(data.to_vec())
+      .send()
+      .await?;
+    check(response, &[StatusCode::CREATED])?;
+
+    Ok(())
+  }
+
+  /// push the blobs and the manifest of an image, the digest of the manifest is returned
+  pub async fn push(
+    &self,
+    repository: &str,
+    tag: &str,
+    image: &ImageContent<'_>,
+  ) -> RegistryResult<String> {
+    for blob in &image.blobs {
+      self.push_blob(repository, &blob.digest, blob.data).await?;
+    }
+
+    let response = self
+      .http
+      .put(self.url(&format!("{}/manifests/{}", repository, tag)))
+      .header(header::CONTENT_TYPE, &image.media_type)
+      .body(image.manifest.clone())
+      .send()
+      .await?;
+    check(response, &[StatusCode::CREATED])?;
+
+    Ok(archive::digest(&image.manifest))
+  }
+
+  /// repositories starting with the prefix
+  pub async fn repositories(&self, prefix: &str) -> RegistryResult<Vec<String>> {
+    let mut repositories = Vec::new();
+    let mut last = String::new();
+
+    loop {
+      let mut request = self
+        .http
+        .get(self.url("_catalog"))
+        .query(&[("n", CATALOG_PAGE_SIZE.to_string())]);
+
+      if !last.is_empty() {
+        request = request.query(&[("last", &last)]);
+      }
+
+      let response = request.send().await?;
+      let catalog: Catalog = check(response, &[StatusCode::OK])?.json().await?;
+
+      let page_size = catalog.repositories.len();
+      last = catalog.repositories.last().cloned().unwrap_or_default();
+
+      repositories.extend(
+        catalog
+          .repositories
+          .into_iter()
+          .filter(|repository| repository.starts_with(prefix)),
+      );
+
+      if page_size < CATALOG_PAGE_SIZE {
+        return Ok(repositories);
+      }
+    }
+  }
+
+  pub async fn tags(&self, repository: &str) -> RegistryResult<Vec<String>> {
+    let response = self
+      .http
+      .get(self.url(&format!("{}/tags/list", repository)))
+      .send()
+      .await?;
+
+    // the repository disappears with its last manifest
+    if response.status() == StatusCode::NOT_FOUND {
+      return Ok(vec![]);
+    }
+
+    let tags: TagsList = check(response, &[StatusCode::OK])?.json().await?;
+
+    Ok(tags.tags.unwrap_or_default())
+  }
+
+  /// anonymous token answering the challenge of a registry refusing a request
+  async fn anonymous_token(&self, response: &reqwest::Response) -> RegistryResult<Option<String>> {
+    let Some(params) = bearer_challenge(response) else {
+      return Ok(None);
+    };
+
+    let Some((_, realm)) = params.iter().find(|(key, _)| key == "realm") else {
+      return Ok(None);
+    };
+
+    let query = params
+      .iter()
+      .filter(|(key, _)| key != "realm")
+      .collect::<Vec<_>>();
+
+    let response = self.http.get(realm).query(&query).send().await?;
+    let token: AnonymousToken = check(response, &[StatusCode::OK])?.json().await?;
+
+    Ok(Some(token.token))
+  }
+
+  /// digest of the manifest the reference points to
+  pub async fn manifest_digest(
+    &self,
+    repository: &str,
+    reference: &str,
+  ) -> RegistryResult<Option<String>> {
+    let url = self.url(&format!("{}/manifests/{}", repository, reference));
+    let mut response = self
+      .http
+      .head(&url)
+      .header(header::ACCEPT, MANIFEST_TYPES)
+      .send()
+      .await?;
+
+    // public images of most registries still require a token
+    if response.status() == StatusCode::UNAUTHORIZED {
+      if let Some(token) = self.anonymous_token(&response).await? {
+        response = self
+          .http
+          .head(&url)
+          .header(header::ACCEPT, MANIFEST_TYPES)
+          .bearer_auth(token)
+          .send()
+          .await?;
+      }
+    }
+
+    if response.status() == StatusCode::NOT_FOUND {
+      return Ok(None);
+    }
+
+    let response = check(response, &[StatusCode::OK])?;
+
+    Ok(
+      response
+        .headers()
+        .get("Docker-Content-Digest")
+        .and_then(|digest| digest.to_str().ok())
+        .map(str::to_string),
+    )
+  }
+
+  /// remove a manifest and all its tags, the blobs are freed by the garbage collection of the registry
+  pub async fn delete_manifest(&self, repository: &str, digest: &str) -> RegistryResult<()> {
+    let response = self
+      .http
+      .delete(self.url(&format!("{}/manifests/{}", repository, digest)))
+      .send()
+      .await?;
+
+    if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+      return Err(RegistryError::DeleteDisabled);
+    }
+
+    check(response, &[StatusCode::ACCEPTED, StatusCode::NOT_FOUND])?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,176 @@
+use derive_more::Display;
+
+/// registry of the references without a domain
+pub const DEFAULT_DOMAIN: &str = "docker.io";
+/// namespace of the official images of the default registry
+const OFFICIAL_NAMESPACE: &str = "library";
+const MAX_NAME_LENGTH: usize = 255;
+const MAX_TAG_LENGTH: usize = 128;
+
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum ReferenceError {
+  #[display(fmt = "the reference is empty")]
+  Empty,
+  #[display(fmt = "the reference is longer than {} characters", MAX_NAME_LENGTH)]
+  TooLong,
+  #[display(fmt = "invalid registry domain {_0}")]
+  Domain(String),
+  #[display(
+    fmt = "invalid repository {_0}, repositories use lowercase letters, digits and separators"
+  )]
+  Repository(String),
+  #[display(fmt = "invalid tag {_0}")]
+  Tag(String),
+  #[display(fmt = "invalid digest {_0}")]
+  Digest(String),
+}
+
+/// OCI image reference, `[domain/]repository[:tag][@digest]`
+#[derive(Debug, PartialEq, Eq)]
+pub struct NebulaReference<'a> {
+  /// name as written, without the tag nor the digest
+  pub name: &'a str,
+  pub domain: &'a str,
+  /// repository in the registry, official images of the default registry are namespaced
+  pub repository: String,
+  pub tag: Option<&'a str>,
+  pub digest: Option<&'a str>,
+}
+
+impl<'a> NebulaReference<'a> {
+  pub fn parse(reference: &'a str) -> Result<Self, ReferenceError> {
+    if reference.is_empty() {
+      return Err(ReferenceError::Empty);
+    }
+
+    let (name, digest) = match reference.split_once('@') {
+      Some((name, digest)) => (name, Some(digest)),
+      None => (reference, None),
+    };
+
+    // a colon after the last slash separates the tag from the name
+    let (name, tag) = match name.rsplit_once(':') {
+      Some((name, tag)) if !tag.contains('/') => (name, Some(tag)),
+      _ => (name, None),
+    };
+
+    if name.len() > MAX_NAME_LENGTH {
+      return Err(ReferenceError::TooLong);
+    }
+
+    let (domain, path) = match name.split_once('/') {
+      Some((domain, path)) if is_domain(domain) => (domain, path),
+      _ => (DEFAULT_DOMAIN, name),
+    };
+
+    if !domain
+      .split_once(':')
+      .map_or(valid_host(domain), |(host, port)| {
+        valid_host(host) && !port.is_empty() && port.chars().all(|c| c.is_ascii_digit())
+      })
+    {
+      return Err(ReferenceError::Domain(domain.to_string()));
+    }
+
+    if !path.split('/').all(valid_path_component) {
+      return Err(ReferenceError::Repository(path.to_string()));
+    }
+
+    if let Some(tag) = tag.filter(|tag| !valid_tag(tag)) {
+      return Err(ReferenceError::Tag(tag.to_string()));
+    }
+
+    if let Some(digest) = digest.filter(|digest| !valid_digest(digest)) {
+      return Err(ReferenceError::Digest(digest.to_string()));
+    }
+
+    let repository = match domain == DEFAULT_DOMAIN && !path.contains('/') {
+      true => format!("{}/{}", OFFICIAL_NAMESPACE, path),
+      false => path.to_string(),
+    };
+
+    Ok(Self {
+      name,
+      domain,
+      repository,
+      tag,
+      digest,
+    })
+  }
+
+  /// reference to the manifest, the digest is preferred over the tag
+  pub fn manifest(&self) -> &str {
+    self.digest.or(self.tag).unwrap_or("latest")
+  }
+
+  /// same image pinned to a digest, the tag is dropped
+  pub fn pinned(&self, digest: &str) -> String {
+    format!("{}@{}", self.name, digest)
+  }
+}
+
+/// the first component of a name is a domain when it cannot be a repository
+fn is_domain(component: &str) -> bool {
+  component.contains(['.', ':'])
+    || component == "localhost"
+    || component.chars().any(|c| c.is_ascii_uppercase())
+}
+
+fn valid_host(host: &str) -> bool {
+  !host.is_empty()
+    && host.split('.').all(|label| {
+      !label.is_empty()
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+    })
+}
+
+/// lowercase alphanumeric runs joined by `.`, `_`, `__` or any number of `-`
+fn valid_path_component(component: &str) -> bool {
+  let mut started = false;
+  let mut separator = String::new();
+
+  for c in component.chars() {
+    match c {
+      'a'..='z' | '0'..='9' => {
+        let valid_separator = matches!(separator.as_str(), "" | "." | "_" | "__")
+          || separator.chars().all(|c| c == '-');
+
+        if !valid_separator || (!started && !separator.is_empty()) {
+          return false;
+        }
+
+        started = true;
+        separator.clear();
+      }
+      '.' | '_' | '-' => separator.push(c),
+      _ => return false,
+    }
+  }
+
+  started && separator.is_empty()
+}
+
+fn valid_tag(tag: &str) -> bool {
+  tag.len() <= MAX_TAG_LENGTH
+    && tag
+      .chars()
+      .next()
+      .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+    && tag
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+fn valid_digest(digest: &str) -> bool {
+  let Some((algorithm, encoded)) = digest.split_once(':') else {
+    return false;
+  };
+
+  match algorithm {
+    "sha256" => encoded.len() == 64 && encoded.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')),
+    "sha512" => encoded.len() == 128 && encoded.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')),
+    _ => false,
+  }
+}
@@ -0,0 +1,185 @@
+use actix_web::{
+  get,
+  http::StatusCode,
+  post,
+  web::{Path, Payload, ServiceConfig},
+};
+use actix_web_lab::sse::{self, Sse};
+use derive_more::From;
+use futures_util::{AsyncBufReadExt, Stream, StreamExt};
+use log::warn;
+use serde::Serialize;
+
+use crate::impl_json_responder;
+use crate::models::{
+  build::{Build, BuildPath},
+  star::{Star, StarPath},
+  CrudOperations,
+};
+use crate::registry;
+use crate::routes::{invalid_upload, read_upload};
+use crate::{database::Transaction, error::UnauthorizeResponse};
+use crate::{
+  error::{ApiError, ApiResult, InternalErrorResponse, NotFoundResponse, ValidationResponse},
+  k8s::BuildRequestResolver,
+};
+
+/// largest source archive accepted for a build, in MiB
+const MAX_SOURCE_SIZE: usize = 100;
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "all builds of the star, newest first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct BuildsList(Vec<Build>);
+impl_json_responder!(BuildsList, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = BuildsList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/builds")]
+pub async fn get_all_builds(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<BuildsList> {
+  let builds = Build::all(&mut tx, &path).await?;
+
+  Ok(BuildsList::from(builds))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "build successfully started, the star uses its image once it succeeds",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct BuildCreated(Build);
+impl_json_responder!(BuildCreated, StatusCode::CREATED);
+
+#[utoipa::path(
+  params(StarPath),
+  request_body(
+    content = Vec<u8>,
+    description = "gzipped tar archive of the source with a Dockerfile at its root",
+    content_type = "application/gzip"
+  ),
+  responses(
+    (status = CREATED, response = BuildCreated),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/builds")]
+pub async fn create_build(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+  payload: Payload,
+) -> ApiResult<BuildCreated> {
+  let source = read_upload(payload, "source", MAX_SOURCE_SIZE).await?;
+
+  if !source.starts_with(&GZIP_MAGIC) {
+    return Err(invalid_upload(
+      "source",
+      "the source must be a gzipped tar archive with a Dockerfile at its root".to_string(),
+    ));
+  }
+
+  let star = Star::get(&mut tx, &path).await?;
+
+  let build = Build::create(&mut tx, &path, &registry::image_name(&star.repository())).await?;
+
+  let api = BuildRequestResolver::try_default(&star.galaxy_id).await?;
+  build.launch(&star, &api).await?;
+
+  // the builder reads the source from its stdin once its pod is running
+  let uploaded_build = build.clone();
+
+  actix_web::rt::spawn(async move {
+    if let Err(err) = uploaded_build.upload(source, api).await {
+      warn!(
+        "Unable to send the source of build {}: {}",
+        uploaded_build.id, err
+      );
+    }
+  });
+
+  Ok(BuildCreated::from(build))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "specific build of the star",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct SpecificBuild(Build);
+impl_json_responder!(SpecificBuild, StatusCode::OK);
+
+#[utoipa::path(
+  params(BuildPath),
+  responses(
+    (status = OK, response = SpecificBuild),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/builds/{build_id}")]
+pub async fn get_build(mut tx: Transaction, path: Path<BuildPath>) -> ApiResult<SpecificBuild> {
+  let build = Build::get(&mut tx, &path).await?;
+
+  Ok(SpecificBuild::from(build))
+}
+
+#[allow(dead_code)]
+#[derive(utoipa::ToResponse)]
+#[response(
+  description = "output of the builder, one `log` event for each line",
+  content_type = "text/event-stream"
+)]
+pub struct BuildLogs(String);
+
+#[utoipa::path(
+  params(BuildPath),
+  responses(
+    (status = OK, response = BuildLogs),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/builds/{build_id}/logs")]
+pub async fn get_build_logs(
+  mut tx: Transaction,
+  path: Path<BuildPath>,
+) -> ApiResult<Sse<impl Stream<Item = Result<sse::Event, std::io::Error>>>> {
+  let build = Build::get(&mut tx, &path).await?;
+
+  // the pod of the build never started or was already removed
+  let logs = build
+    .logs(BuildRequestResolver::try_default(&path.0).await?)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+  let stream = Box::pin(logs)
+    .lines()
+    .map(|line| line.map(|line| sse::Event::Data(sse::Data::new(line).event("log"))));
+
+  Ok(Sse::from_stream(stream))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg
+    .service(get_all_builds)
+    .service(create_build)
+    .service(get_build)
+    .service(get_build_logs);
+}
@@ -0,0 +1,98 @@
+use actix_web::{
+  get,
+  http::StatusCode,
+  web::{Path, ServiceConfig},
+};
+use chrono::{DateTime, Utc};
+use derive_more::From;
+use k8s_openapi::api::batch::v1::Job;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::impl_json_responder;
+use crate::models::star::{CrudOperations, Star, StarPath};
+use crate::{database::Transaction, error::UnauthorizeResponse};
+use crate::{
+  error::{ApiResult, InternalErrorResponse, NotFoundResponse},
+  k8s::CronRequestResolver,
+};
+
+#[derive(Serialize, ToSchema)]
+pub enum CronRunStatus {
+  Running,
+  Succeeded,
+  Failed,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CronRun {
+  pub name: String,
+  pub status: CronRunStatus,
+  pub started_at: Option<DateTime<Utc>>,
+  pub finished_at: Option<DateTime<Utc>>,
+  /// tail of the logs of the last pod of the run
+  pub logs: Option<String>,
+}
+
+impl CronRun {
+  fn new(job: Job, logs: Option<String>) -> Self {
+    let status = job.status.unwrap_or_default();
+
+    let failed_at = status
+      .conditions
+      .iter()
+      .flatten()
+      .find(|condition| condition.type_ == "Failed" && condition.status == "True")
+      .map(|condition| condition.last_transition_time.as_ref().map(|time| time.0));
+
+    let (status_kind, finished_at) = match (status.completion_time, failed_at) {
+      (Some(completed_at), _) => (CronRunStatus::Succeeded, Some(completed_at.0)),
+      (None, Some(failed_at)) => (CronRunStatus::Failed, failed_at),
+      (None, None) => (CronRunStatus::Running, None),
+    };
+
+    Self {
+      name: job.metadata.name.unwrap_or_default(),
+      status: status_kind,
+      started_at: status.start_time.map(|time| time.0),
+      finished_at,
+      logs,
+    }
+  }
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "recent runs of the cron star, newest first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct CronRunsList(Vec<CronRun>);
+impl_json_responder!(CronRunsList, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = CronRunsList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/cron-runs")]
+pub async fn get_cron_runs(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<CronRunsList> {
+  let star = Star::get(&mut tx, &path).await?;
+
+  let runs = star
+    .cron_runs(CronRequestResolver::try_default(&star.galaxy_id).await?)
+    .await?
+    .into_iter()
+    .map(|(job, logs)| CronRun::new(job, logs))
+    .collect::<Vec<_>>();
+
+  Ok(CronRunsList::from(runs))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg.service(get_cron_runs);
+}
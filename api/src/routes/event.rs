@@ -0,0 +1,175 @@
+use actix_web::{
+  get,
+  http::StatusCode,
+  web::{Path, Query, ServiceConfig},
+  Either,
+};
+use actix_web_lab::sse::{self, Sse};
+use chrono::{DateTime, Utc};
+use derive_more::From;
+use futures_util::{Stream, StreamExt};
+use k8s_openapi::api::core::v1::Event;
+use kube::runtime::watcher;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::impl_json_responder;
+use crate::models::{
+  planet::{Planet, PlanetPath},
+  star::{CrudOperations, Star, StarPath},
+};
+use crate::routes::star::WatchQuery;
+use crate::{database::Transaction, error::UnauthorizeResponse};
+use crate::{
+  error::{ApiResult, InternalErrorResponse, NotFoundResponse},
+  k8s::{EventFilter, EventRequestResolver},
+};
+
+#[derive(Serialize, ToSchema)]
+pub enum EventType {
+  Normal,
+  Warning,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ResourceEvent {
+  /// kind of the kubernetes object the event is about, like `Pod`
+  pub kind: Option<String>,
+  pub name: Option<String>,
+  #[serde(rename = "type")]
+  pub event_type: EventType,
+  pub reason: Option<String>,
+  pub message: Option<String>,
+  #[schema(minimum = 1)]
+  pub count: i32,
+  pub first_timestamp: Option<DateTime<Utc>>,
+  pub last_timestamp: Option<DateTime<Utc>>,
+}
+
+impl From<Event> for ResourceEvent {
+  fn from(event: Event) -> Self {
+    // events from the newer api carry only the event time and the series
+    let event_time = event.event_time.map(|time| time.0);
+    let series_time = event
+      .series
+      .as_ref()
+      .and_then(|series| series.last_observed_time.as_ref())
+      .map(|time| time.0);
+
+    Self {
+      kind: event.involved_object.kind,
+      name: event.involved_object.name,
+      event_type: match event.type_.as_deref() {
+        Some("Warning") => EventType::Warning,
+        _ => EventType::Normal,
+      },
+      reason: event.reason,
+      message: event.message,
+      count: event
+        .count
+        .or(event.series.and_then(|series| series.count))
+        .unwrap_or(1),
+      first_timestamp: event.first_timestamp.map(|time| time.0).or(event_time),
+      last_timestamp: event
+        .last_timestamp
+        .map(|time| time.0)
+        .or(series_time)
+        .or(event_time),
+    }
+  }
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[serde(untagged)]
+#[response(description = "kubernetes events of the resource, oldest first")]
+pub enum ResourceEvents {
+  List(#[content("application/json")] Vec<ResourceEvent>),
+  Event(#[content("text/event-stream")] ResourceEvent),
+}
+impl_json_responder!(ResourceEvents, StatusCode::OK);
+
+type EventsResponse =
+  Either<ResourceEvents, Sse<Box<dyn Stream<Item = Result<sse::Event, watcher::Error>> + Unpin>>>;
+
+async fn events(
+  filter: EventFilter,
+  api: EventRequestResolver,
+  watch: Option<bool>,
+) -> ApiResult<EventsResponse> {
+  if !watch.unwrap_or(false) {
+    let events = api.list(&filter).await?;
+
+    return Ok(Either::Left(ResourceEvents::from(
+      events
+        .into_iter()
+        .map(ResourceEvent::from)
+        .collect::<Vec<_>>(),
+    )));
+  }
+
+  let stream = api.watch(filter).map(|event| {
+    event.map(|event| {
+      sse::Event::Data(
+        sse::Data::new_json(ResourceEvent::from(event))
+          .expect("Error serializing event")
+          .event("event"),
+      )
+    })
+  });
+
+  Ok(Either::Right(Sse::from_stream(Box::new(Box::pin(stream)))))
+}
+
+#[utoipa::path(
+  params(StarPath, WatchQuery),
+  responses(
+    (status = OK, response = ResourceEvents),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/events")]
+pub async fn get_star_events(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+  Query(WatchQuery { watch }): Query<WatchQuery>,
+) -> ApiResult<EventsResponse> {
+  let star = Star::get(&mut tx, &path).await?;
+
+  events(
+    EventFilter::from(&star),
+    EventRequestResolver::try_default(&star.galaxy_id).await?,
+    watch,
+  )
+  .await
+}
+
+#[utoipa::path(
+  params(PlanetPath, WatchQuery),
+  responses(
+    (status = OK, response = ResourceEvents),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/planets/{planet_id}/events")]
+pub async fn get_planet_events(
+  mut tx: Transaction,
+  path: Path<PlanetPath>,
+  Query(WatchQuery { watch }): Query<WatchQuery>,
+) -> ApiResult<EventsResponse> {
+  let planet = Planet::get(&mut tx, &path).await?;
+
+  events(
+    EventFilter::from(&planet),
+    EventRequestResolver::try_default(&planet.galaxy_id).await?,
+    watch,
+  )
+  .await
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg.service(get_star_events).service(get_planet_events);
+}
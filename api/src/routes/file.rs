@@ -0,0 +1,260 @@
+use actix_web::{
+  delete, get,
+  http::StatusCode,
+  post, put,
+  web::{Json, Path, ServiceConfig},
+};
+use derive_more::From;
+use serde::Serialize;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::{
+  database::Transaction,
+  error::{
+    AlreadyExistsResponse, ApiError, ApiResult, InternalErrorResponse, NotFoundResponse,
+    UnauthorizeResponse, ValidationResponse,
+  },
+  impl_json_responder,
+  k8s::{ConfigFileRequestResolver, StarRequestResolver},
+  models::{
+    file::{ConfigFile, ConfigFilePath, CreateConfigFileData, UpdateConfigFileData},
+    star::{Star, StarPath},
+    CrudOperations,
+  },
+  routes::star::deploy_update,
+};
+
+/// the directory of a file is mounted over the one of the image, never over the root
+fn validate_mount(file: &ConfigFile) -> ApiResult<()> {
+  if !file.mount_directory || file.mount().split_path().0 != "/" {
+    return Ok(());
+  }
+
+  let mut errors = ValidationErrors::new();
+  errors.add(
+    "mount_directory",
+    ValidationError::new("mount_directory")
+      .with_message(Cow::from("the root directory cannot be mounted")),
+  );
+
+  Err(ApiError::Validation(errors))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "all config files of a star",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarConfigFilesList(Vec<ConfigFile>);
+impl_json_responder!(StarConfigFilesList, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarConfigFilesList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/files")]
+pub async fn get_all_star_files(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+) -> ApiResult<StarConfigFilesList> {
+  let files = ConfigFile::all(&mut tx, &path).await?;
+
+  Ok(StarConfigFilesList::from(files))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "config file successfully created and mounted in the star",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarConfigFileCreated(ConfigFile);
+impl_json_responder!(StarConfigFileCreated, StatusCode::CREATED);
+
+#[utoipa::path(
+  params(StarPath),
+  request_body(
+    content = CreateConfigFileData,
+    description = "data for creating the config file",
+    content_type = "application/json"
+  ),
+  responses(
+    (status = CREATED, response = StarConfigFileCreated),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = CONFLICT, response = AlreadyExistsResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/files")]
+pub async fn create_star_file(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+  Json(data): Json<CreateConfigFileData>,
+) -> ApiResult<StarConfigFileCreated> {
+  data.validate()?;
+
+  let star = Star::get(&mut tx, &path).await?;
+  let file = <ConfigFile as CrudOperations>::create(&mut tx, &path, &data).await?;
+  validate_mount(&file)?;
+
+  file
+    .apply(
+      &data.contents,
+      &ConfigFileRequestResolver::try_default(&star.galaxy_id).await?,
+    )
+    .await?;
+
+  // the new mount changes the pod spec
+  let updated_star = Star::refresh_config_files(&mut tx, &path).await?;
+  deploy_update(&mut tx, &star, &updated_star).await?;
+
+  Ok(StarConfigFileCreated::from(file))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "specific config file",
+  content_type = "application/json"
+)]
+pub struct SpecificStarConfigFile(ConfigFile);
+impl_json_responder!(SpecificStarConfigFile, StatusCode::OK);
+
+#[utoipa::path(
+  params(ConfigFilePath),
+  responses(
+    (status = OK, response = SpecificStarConfigFile),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/files/{file_id}")]
+pub async fn get_star_file(
+  mut tx: Transaction,
+  path: Path<ConfigFilePath>,
+) -> ApiResult<SpecificStarConfigFile> {
+  let file = ConfigFile::get(&mut tx, &path).await?;
+
+  Ok(SpecificStarConfigFile::from(file))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "config file successfully updated",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarConfigFileUpdated(ConfigFile);
+impl_json_responder!(StarConfigFileUpdated, StatusCode::OK);
+
+#[utoipa::path(
+  params(ConfigFilePath),
+  request_body(
+    content = UpdateConfigFileData,
+    description = "data for updating the config file",
+    content_type = "application/json"
+  ),
+  responses(
+    (status = OK, response = StarConfigFileUpdated),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = CONFLICT, response = AlreadyExistsResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[put("/galaxies/{galaxy_id}/stars/{star_id}/files/{file_id}")]
+pub async fn update_star_file(
+  mut tx: Transaction,
+  path: Path<ConfigFilePath>,
+  Json(data): Json<UpdateConfigFileData>,
+) -> ApiResult<StarConfigFileUpdated> {
+  data.validate()?;
+
+  let star_path = StarPath(path.0, path.1);
+  let star = Star::get(&mut tx, &star_path).await?;
+  let previous = ConfigFile::get(&mut tx, &path).await?;
+  let file = <ConfigFile as CrudOperations>::update(&mut tx, &path, &data).await?;
+  validate_mount(&file)?;
+
+  let contents_changed = data.contents.is_some();
+
+  if contents_changed || file.kind != previous.kind {
+    file
+      .apply(
+        &file.open_contents()?,
+        &ConfigFileRequestResolver::try_default(&star.galaxy_id).await?,
+      )
+      .await?;
+  }
+
+  let updated_star = Star::refresh_config_files(&mut tx, &star_path).await?;
+
+  if updated_star.config_files != star.config_files {
+    deploy_update(&mut tx, &star, &updated_star).await?;
+  } else if contents_changed && !file.mount_directory {
+    // a file mounted alone keeps its old contents until the pods restart,
+    // a mounted directory is updated in place
+    updated_star
+      .restart(StarRequestResolver::try_default(&star.galaxy_id).await?)
+      .await?;
+  }
+
+  Ok(StarConfigFileUpdated::from(file))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "config file successfully deleted and unmounted from the star",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarConfigFileDeleted(ConfigFile);
+impl_json_responder!(StarConfigFileDeleted, StatusCode::OK);
+
+#[utoipa::path(
+  params(ConfigFilePath),
+  responses(
+    (status = OK, response = StarConfigFileDeleted),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[delete("/galaxies/{galaxy_id}/stars/{star_id}/files/{file_id}")]
+pub async fn delete_star_file(
+  mut tx: Transaction,
+  path: Path<ConfigFilePath>,
+) -> ApiResult<StarConfigFileDeleted> {
+  let star_path = StarPath(path.0, path.1);
+  let star = Star::get(&mut tx, &star_path).await?;
+  let file = <ConfigFile as CrudOperations>::delete(&mut tx, &path).await?;
+
+  // unmounted first so the pods never miss the contents of a mounted file
+  let updated_star = Star::refresh_config_files(&mut tx, &star_path).await?;
+  deploy_update(&mut tx, &star, &updated_star).await?;
+
+  file
+    .remove(&ConfigFileRequestResolver::try_default(&star.galaxy_id).await?)
+    .await?;
+
+  Ok(StarConfigFileDeleted::from(file))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg
+    .service(get_all_star_files)
+    .service(create_star_file)
+    .service(get_star_file)
+    .service(update_star_file)
+    .service(delete_star_file);
+}
@@ -0,0 +1,432 @@
+use actix_web::{
+  delete, get,
+  http::StatusCode,
+  post, put,
+  web::{Json, Path, ServiceConfig},
+};
+use derive_more::From;
+use serde::Serialize;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+  database::{Connection, Transaction},
+  error::{
+    AlreadyExistsResponse, ApiResult, InternalErrorResponse, NotFoundResponse, UnauthorizeResponse,
+    ValidationResponse,
+  },
+  impl_json_responder,
+  k8s::VariableGroupRequestResolver,
+  models::{
+    galaxy::GalaxyPath,
+    group::{
+      AttachedVariableGroup, CreateVariableGroupData, GroupVariablePath, SetGroupVariableData,
+      StarVariableGroupPath, UpdateVariableGroupData, VariableGroup, VariableGroupPath,
+    },
+    star::{Star, StarPath},
+    var::Variable,
+    CrudOperations,
+  },
+  routes::star::deploy_update,
+};
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "all variable groups of the galaxy",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct VariableGroupsList(Vec<VariableGroup>);
+impl_json_responder!(VariableGroupsList, StatusCode::OK);
+
+#[utoipa::path(
+  params(GalaxyPath),
+  responses(
+    (status = OK, response = VariableGroupsList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/groups")]
+pub async fn get_all_groups(
+  mut tx: Transaction,
+  path: Path<GalaxyPath>,
+) -> ApiResult<VariableGroupsList> {
+  let groups = VariableGroup::all(&mut tx, &path).await?;
+
+  Ok(VariableGroupsList::from(groups))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "variable group successfully created",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct VariableGroupCreated(VariableGroup);
+impl_json_responder!(VariableGroupCreated, StatusCode::CREATED);
+
+#[utoipa::path(
+  params(GalaxyPath),
+  request_body(
+    content = CreateVariableGroupData,
+    description = "data for creating the variable group",
+    content_type = "application/json"
+  ),
+  responses(
+    (status = CREATED, response = VariableGroupCreated),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = CONFLICT, response = AlreadyExistsResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/groups")]
+pub async fn create_group(
+  mut tx: Transaction,
+  path: Path<GalaxyPath>,
+  Json(data): Json<CreateVariableGroupData>,
+) -> ApiResult<VariableGroupCreated> {
+  data.validate()?;
+
+  let group = <VariableGroup as CrudOperations>::create(&mut tx, &path, &data).await?;
+  let values = VariableGroup::values(&mut tx, &group.id).await?;
+
+  group
+    .apply(
+      &values,
+      &[],
+      VariableGroupRequestResolver::try_default(&group.galaxy_id).await?,
+    )
+    .await?;
+
+  Ok(VariableGroupCreated::from(group))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "specific variable group",
+  content_type = "application/json"
+)]
+pub struct SpecificVariableGroup(VariableGroup);
+impl_json_responder!(SpecificVariableGroup, StatusCode::OK);
+
+#[utoipa::path(
+  params(VariableGroupPath),
+  responses(
+    (status = OK, response = SpecificVariableGroup),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/groups/{group_id}")]
+pub async fn get_group(
+  mut tx: Transaction,
+  path: Path<VariableGroupPath>,
+) -> ApiResult<SpecificVariableGroup> {
+  let group = VariableGroup::get(&mut tx, &path).await?;
+
+  Ok(SpecificVariableGroup::from(group))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "variable group successfully updated",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct VariableGroupUpdated(VariableGroup);
+impl_json_responder!(VariableGroupUpdated, StatusCode::OK);
+
+#[utoipa::path(
+  params(VariableGroupPath),
+  request_body(
+    content = UpdateVariableGroupData,
+    description = "data for updating the variable group",
+    content_type = "application/json"
+  ),
+  responses(
+    (status = OK, response = VariableGroupUpdated),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = CONFLICT, response = AlreadyExistsResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[put("/galaxies/{galaxy_id}/groups/{group_id}")]
+pub async fn update_group(
+  mut tx: Transaction,
+  path: Path<VariableGroupPath>,
+  Json(data): Json<UpdateVariableGroupData>,
+) -> ApiResult<VariableGroupUpdated> {
+  data.validate()?;
+
+  let group = <VariableGroup as CrudOperations>::update(&mut tx, &path, &data).await?;
+  apply_group(&mut tx, &group).await?;
+
+  Ok(VariableGroupUpdated::from(group))
+}
+
+/// write the values of the group and restart the stars it is attached to
+async fn apply_group(conn: &mut Connection, group: &VariableGroup) -> ApiResult<()> {
+  let values = VariableGroup::values(conn, &group.id).await?;
+  let stars = Star::all_with_variable_group(conn, &group.galaxy_id, &group.id).await?;
+
+  group
+    .apply(
+      &values,
+      &stars.iter().map(|star| star.id).collect::<Vec<_>>(),
+      VariableGroupRequestResolver::try_default(&group.galaxy_id).await?,
+    )
+    .await?;
+
+  Ok(())
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "variable of the group successfully set",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct GroupVariableSet(VariableGroup);
+impl_json_responder!(GroupVariableSet, StatusCode::OK);
+
+#[utoipa::path(
+  params(GroupVariablePath),
+  request_body(
+    content = SetGroupVariableData,
+    description = "value of the variable",
+    content_type = "application/json"
+  ),
+  responses(
+    (status = OK, response = GroupVariableSet),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[put("/galaxies/{galaxy_id}/groups/{group_id}/vars/{name}")]
+pub async fn set_group_variable(
+  mut tx: Transaction,
+  path: Path<GroupVariablePath>,
+  Json(data): Json<SetGroupVariableData>,
+) -> ApiResult<GroupVariableSet> {
+  data.validate_for(&path.2)?;
+
+  let group = VariableGroup::set_variable(&mut tx, &path, &data).await?;
+  apply_group(&mut tx, &group).await?;
+
+  Ok(GroupVariableSet::from(group))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "variable of the group successfully deleted",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct GroupVariableDeleted(VariableGroup);
+impl_json_responder!(GroupVariableDeleted, StatusCode::OK);
+
+#[utoipa::path(
+  params(GroupVariablePath),
+  responses(
+    (status = OK, response = GroupVariableDeleted),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[delete("/galaxies/{galaxy_id}/groups/{group_id}/vars/{name}")]
+pub async fn delete_group_variable(
+  mut tx: Transaction,
+  path: Path<GroupVariablePath>,
+) -> ApiResult<GroupVariableDeleted> {
+  let group = VariableGroup::delete_variable(&mut tx, &path).await?;
+  apply_group(&mut tx, &group).await?;
+
+  Ok(GroupVariableDeleted::from(group))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "variable group successfully deleted, it is detached from its stars",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct VariableGroupDeleted(VariableGroup);
+impl_json_responder!(VariableGroupDeleted, StatusCode::OK);
+
+#[utoipa::path(
+  params(VariableGroupPath),
+  responses(
+    (status = OK, response = VariableGroupDeleted),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[delete("/galaxies/{galaxy_id}/groups/{group_id}")]
+pub async fn delete_group(
+  mut tx: Transaction,
+  path: Path<VariableGroupPath>,
+) -> ApiResult<VariableGroupDeleted> {
+  let group = VariableGroup::get(&mut tx, &path).await?;
+
+  for star in Star::all_with_variable_group(&mut tx, &group.galaxy_id, &group.id).await? {
+    set_star_groups(&mut tx, &star, |groups| {
+      groups.retain(|group_id| *group_id != group.id)
+    })
+    .await?;
+  }
+
+  let group = <VariableGroup as CrudOperations>::delete(&mut tx, &path).await?;
+
+  group
+    .remove(VariableGroupRequestResolver::try_default(&group.galaxy_id).await?)
+    .await?;
+
+  Ok(VariableGroupDeleted::from(group))
+}
+
+/// change the groups of the star and deploy it with them
+async fn set_star_groups(
+  conn: &mut Connection,
+  star: &Star,
+  change: impl FnOnce(&mut Vec<Uuid>),
+) -> ApiResult<()> {
+  let mut groups = star.variable_groups.clone();
+  change(&mut groups);
+
+  if groups == star.variable_groups {
+    return Ok(());
+  }
+
+  let path = StarPath(star.galaxy_id, star.id);
+  let updated_star = Star::set_variable_groups(conn, &path, &groups).await?;
+
+  deploy_update(conn, star, &updated_star).await
+}
+
+/// groups of the star with the variables overridden by later groups or the star
+async fn attached_groups(
+  conn: &mut Connection,
+  path: &StarPath,
+) -> ApiResult<Vec<AttachedVariableGroup>> {
+  let star = Star::get(conn, path).await?;
+  let groups = VariableGroup::all(conn, &GalaxyPath(star.galaxy_id)).await?;
+  let vars = Variable::all(conn, path).await?;
+
+  Ok(VariableGroup::attached(
+    groups,
+    &star.variable_groups,
+    &vars,
+  ))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "variable groups of the star in override order",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarVariableGroups(Vec<AttachedVariableGroup>);
+impl_json_responder!(StarVariableGroups, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarVariableGroups),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/groups")]
+pub async fn get_star_groups(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+) -> ApiResult<StarVariableGroups> {
+  let groups = attached_groups(&mut tx, &path).await?;
+
+  Ok(StarVariableGroups::from(groups))
+}
+
+#[utoipa::path(
+  params(StarVariableGroupPath),
+  responses(
+    (status = OK, response = StarVariableGroups),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[put("/galaxies/{galaxy_id}/stars/{star_id}/groups/{group_id}")]
+pub async fn attach_star_group(
+  mut tx: Transaction,
+  path: Path<StarVariableGroupPath>,
+) -> ApiResult<StarVariableGroups> {
+  let StarVariableGroupPath(galaxy_id, star_id, group_id) = path.into_inner();
+  let star_path = StarPath(galaxy_id, star_id);
+
+  // the group must belong to the galaxy of the star
+  let group = VariableGroup::get(&mut tx, &VariableGroupPath(galaxy_id, group_id)).await?;
+  let star = Star::get(&mut tx, &star_path).await?;
+
+  // attached again it moves last and overrides the other groups
+  set_star_groups(&mut tx, &star, |groups| {
+    groups.retain(|id| *id != group.id);
+    groups.push(group.id);
+  })
+  .await?;
+
+  let groups = attached_groups(&mut tx, &star_path).await?;
+
+  Ok(StarVariableGroups::from(groups))
+}
+
+#[utoipa::path(
+  params(StarVariableGroupPath),
+  responses(
+    (status = OK, response = StarVariableGroups),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[delete("/galaxies/{galaxy_id}/stars/{star_id}/groups/{group_id}")]
+pub async fn detach_star_group(
+  mut tx: Transaction,
+  path: Path<StarVariableGroupPath>,
+) -> ApiResult<StarVariableGroups> {
+  let StarVariableGroupPath(galaxy_id, star_id, group_id) = path.into_inner();
+  let star_path = StarPath(galaxy_id, star_id);
+
+  let star = Star::get(&mut tx, &star_path).await?;
+
+  set_star_groups(&mut tx, &star, |groups| groups.retain(|id| *id != group_id)).await?;
+
+  let groups = attached_groups(&mut tx, &star_path).await?;
+
+  Ok(StarVariableGroups::from(groups))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg
+    .service(get_all_groups)
+    .service(get_group)
+    .service(create_group)
+    .service(update_group)
+    .service(delete_group)
+    .service(set_group_variable)
+    .service(delete_group_variable)
+    .service(get_star_groups)
+    .service(attach_star_group)
+    .service(detach_star_group);
+}
@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+
+use actix_web::{
+  get,
+  http::StatusCode,
+  post,
+  web::{Path, Payload, ServiceConfig},
+};
+use chrono::Utc;
+use derive_more::From;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::{ApiResult, InternalErrorResponse, NotFoundResponse, ValidationResponse};
+use crate::impl_json_responder;
+use crate::models::{
+  build::Build,
+  galaxy::GalaxyPath,
+  rollout::Rollout,
+  star::{Star, StarPath},
+  CrudOperations,
+};
+use crate::registry::{self, ImageArchive, ImageReference, RegistryClient};
+use crate::routes::{
+  read_upload,
+  star::{deploy_update, pin_nebula},
+};
+use crate::{
+  database::{Connection, Transaction},
+  error::UnauthorizeResponse,
+};
+
+/// largest image archive accepted, in MiB
+const MAX_ARCHIVE_SIZE: usize = 512;
+
+#[derive(Serialize, ToSchema)]
+pub struct Image {
+  #[schema(example = "galaxy-0c6b6f4e/star-4f4f1d2a")]
+  pub repository: String,
+  #[schema(example = "upload-20240501120000")]
+  pub tag: String,
+  #[schema(example = "sha256:4b5a1e2f")]
+  pub digest: Option<String>,
+  /// reference to use as nebula of a star
+  pub nebula: String,
+  /// stars running the image, keeping it for the abort of a rollout or building it
+  pub used_by: Vec<Uuid>,
+}
+
+impl Image {
+  fn is_used(&self) -> bool {
+    !self.used_by.is_empty()
+  }
+}
+
+/// images of the galaxy in the platform registry with the stars using them
+async fn galaxy_images(
+  conn: &mut Connection,
+  galaxy_id: &Uuid,
+  registry: &RegistryClient,
+) -> ApiResult<Vec<Image>> {
+  let stars = Star::all(conn, &GalaxyPath(*galaxy_id)).await?;
+  let star_ids = stars.iter().map(|star| star.id).collect::<HashSet<_>>();
+
+  let mut references = stars
+    .iter()
+    .map(|star| (star.id, star.nebula.clone()))
+    .collect::<Vec<_>>();
+
+  references.extend(
+    Rollout::all_in_galaxy(conn, &GalaxyPath(*galaxy_id))
+      .await?
+      .into_iter()
+      .filter_map(|rollout| {
+        let nebula = rollout.previous["nebula"].as_str()?.to_string();
+        Some((rollout.star_id, nebula))
+      }),
+  );
+
+  // the image of a running build is pushed before the star uses it
+  references.extend(
+    Build::all_running(conn)
+      .await?
+      .into_iter()
+      .filter(|build| star_ids.contains(&build.star_id))
+      .map(|build| (build.star_id, build.image)),
+  );
+
+  let mut images = Vec::new();
+
+  for repository in registry
+    .repositories(&format!("galaxy-{}/", galaxy_id))
+    .await?
+  {
+    for tag in registry.tags(&repository).await? {
+      let digest = registry.manifest_digest(&repository, &tag).await?;
+
+      let mut used_by = references
+        .iter()
+        .filter(|(_, nebula)| {
+          ImageReference::parse(nebula).is_some_and(|reference| {
+            reference.repository == repository
+              && (reference.tag == Some(tag.as_str())
+                || (reference.digest.is_some() && reference.digest == digest.as_deref()))
+          })
+        })
+        .map(|(star_id, _)| *star_id)
+        .collect::<Vec<_>>();
+      used_by.sort();
+      used_by.dedup();
+
+      images.push(Image {
+        nebula: match &digest {
+          Some(digest) => registry::nebula(&repository, digest),
+          None => registry::tagged(&repository, &tag),
+        },
+        repository: repository.clone(),
+        tag,
+        digest,
+        used_by,
+      });
+    }
+  }
+
+  Ok(images)
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "all images of the galaxy in the platform registry",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct ImagesList(Vec<Image>);
+impl_json_responder!(ImagesList, StatusCode::OK);
+
+#[utoipa::path(
+  params(GalaxyPath),
+  responses(
+    (status = OK, response = ImagesList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/images")]
+pub async fn get_all_images(mut tx: Transaction, path: Path<GalaxyPath>) -> ApiResult<ImagesList> {
+  let images = galaxy_images(&mut tx, &path.0, &RegistryClient::default()).await?;
+
+  Ok(ImagesList::from(images))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "image successfully pushed, the star is deployed with it",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct ImageUploaded(Image);
+impl_json_responder!(ImageUploaded, StatusCode::CREATED);
+
+#[utoipa::path(
+  params(StarPath),
+  request_body(
+    content = Vec<u8>,
+    description = "`docker save` output or OCI image layout as a tar archive, optionally gzipped",
+    content_type = "application/x-tar"
+  ),
+  responses(
+    (status = CREATED, response = ImageUploaded),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/images")]
+pub async fn upload_image(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+  payload: Payload,
+) -> ApiResult<ImageUploaded> {
+  let upload = read_upload(payload, "archive", MAX_ARCHIVE_SIZE).await?;
+
+  let star = Star::get(&mut tx, &path).await?;
+
+  let archive = ImageArchive::read(&upload)?;
+  let content = archive.image()?;
+
+  let repository = star.repository();
+  // uploads within the same second get different tags unless they carry the same archive
+  let tag = format!(
+    "upload-{}-{}",
+    Utc::now().format("%Y%m%d%H%M%S"),
+    hex::encode(&Sha256::digest(&upload)[..6])
+  );
+  let digest = RegistryClient::default()
+    .push(&repository, &tag, &content)
+    .await?;
+
+  let nebula = registry::nebula(&repository, &digest);
+  let updated_star = Star::set_nebula(&mut tx, &path, &nebula).await?;
+  let updated_star = pin_nebula(&mut tx, Some(&star), updated_star).await?;
+  deploy_update(&mut tx, &star, &updated_star).await?;
+
+  Ok(ImageUploaded::from(Image {
+    repository,
+    tag,
+    digest: Some(digest),
+    nebula,
+    used_by: vec![star.id],
+  }))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "images no star uses anymore, removed from the registry",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct ImagesCollected(Vec<Image>);
+impl_json_responder!(ImagesCollected, StatusCode::OK);
+
+#[utoipa::path(
+  params(GalaxyPath),
+  responses(
+    (status = OK, response = ImagesCollected),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/images/gc")]
+pub async fn collect_images(
+  mut tx: Transaction,
+  path: Path<GalaxyPath>,
+) -> ApiResult<ImagesCollected> {
+  let registry = RegistryClient::default();
+  let images = galaxy_images(&mut tx, &path.0, &registry).await?;
+
+  // deleting a manifest removes all its tags, it is kept while any of them is used
+  let used_digests = images
+    .iter()
+    .filter(|image| image.is_used())
+    .filter_map(|image| Some((image.repository.clone(), image.digest.clone()?)))
+    .collect::<HashSet<_>>();
+
+  let unused = images
+    .into_iter()
+    .filter(|image| {
+      !image.is_used()
+        && image
+          .digest
+          .as_ref()
+          .is_some_and(|digest| !used_digests.contains(&(image.repository.clone(), digest.clone())))
+    })
+    .collect::<Vec<_>>();
+
+  let mut deleted = HashSet::new();
+
+  for image in &unused {
+    let digest = image.digest.as_ref().expect("Unused image without digest");
+
+    if deleted.insert((&image.repository, digest)) {
+      registry.delete_manifest(&image.repository, digest).await?;
+    }
+  }
+
+  Ok(ImagesCollected::from(unused))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg
+    .service(get_all_images)
+    .service(upload_image)
+    .service(collect_images);
+}
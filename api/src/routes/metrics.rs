@@ -0,0 +1,104 @@
+use actix_web::{
+  get,
+  http::StatusCode,
+  web::{Path, Query, ServiceConfig},
+};
+use derive_more::From;
+use serde::Serialize;
+
+use crate::impl_json_responder;
+use crate::models::{
+  galaxy::GalaxyPath,
+  metrics::{MetricsQuery, MetricsSample, ReplicaUsage},
+  star::{CrudOperations, Star, StarPath},
+};
+use crate::{database::Transaction, error::UnauthorizeResponse};
+use crate::{
+  error::{ApiResult, InternalErrorResponse, NotFoundResponse},
+  k8s::MetricsRequestResolver,
+};
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "current usage of each replica of the star",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarUsageList(Vec<ReplicaUsage>);
+impl_json_responder!(StarUsageList, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarUsageList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/metrics")]
+pub async fn get_star_usage(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<StarUsageList> {
+  let star = Star::get(&mut tx, &path).await?;
+
+  let usage = star
+    .usage(MetricsRequestResolver::try_default(&star.galaxy_id).await?)
+    .await?;
+
+  Ok(StarUsageList::from(usage))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "usage over time, oldest sample first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct MetricsSeries(Vec<MetricsSample>);
+impl_json_responder!(MetricsSeries, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath, MetricsQuery),
+  responses(
+    (status = OK, response = MetricsSeries),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/metrics/history")]
+pub async fn get_star_metrics_history(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+  Query(MetricsQuery { range }): Query<MetricsQuery>,
+) -> ApiResult<MetricsSeries> {
+  let series = MetricsSample::star_series(&mut tx, &path, range).await?;
+
+  Ok(MetricsSeries::from(series))
+}
+
+#[utoipa::path(
+  params(GalaxyPath, MetricsQuery),
+  responses(
+    (status = OK, response = MetricsSeries),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/metrics/history")]
+pub async fn get_galaxy_metrics_history(
+  mut tx: Transaction,
+  path: Path<GalaxyPath>,
+  Query(MetricsQuery { range }): Query<MetricsQuery>,
+) -> ApiResult<MetricsSeries> {
+  let series = MetricsSample::galaxy_series(&mut tx, &path, range).await?;
+
+  Ok(MetricsSeries::from(series))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg
+    .service(get_star_usage)
+    .service(get_star_metrics_history)
+    .service(get_galaxy_metrics_history);
+}
@@ -1,7 +1,27 @@
+use std::borrow::Cow;
+
+use actix_web::web::{Bytes, BytesMut, Payload};
+use futures_util::StreamExt;
+use validator::{ValidationError, ValidationErrors};
+
+use crate::error::{ApiError, ApiResult};
+
 pub mod auth;
+pub mod build;
+pub mod cron;
+pub mod event;
+pub mod file;
 pub mod galaxy;
+pub mod group;
+pub mod image;
+pub mod metrics;
+pub mod placement;
 pub mod planet;
+pub mod rollout;
+pub mod run;
+pub mod snapshot;
 pub mod star;
+pub mod storage;
 pub mod user;
 pub mod var;
 
@@ -17,3 +37,38 @@ macro_rules! impl_json_responder {
     }
   };
 }
+
+/// validation error on an uploaded file
+pub(crate) fn invalid_upload(field: &'static str, message: String) -> ApiError {
+  let mut errors = ValidationErrors::new();
+  errors.add(
+    field,
+    ValidationError::new("upload").with_message(Cow::from(message)),
+  );
+
+  ApiError::Validation(errors)
+}
+
+/// body of an upload, refused above the size limit
+pub(crate) async fn read_upload(
+  mut payload: Payload,
+  field: &'static str,
+  limit_mib: usize,
+) -> ApiResult<Bytes> {
+  let mut upload = BytesMut::new();
+
+  while let Some(chunk) = payload.next().await {
+    let chunk = chunk.map_err(|_| ApiError::InternalError)?;
+
+    if upload.len() + chunk.len() > limit_mib * 1024 * 1024 {
+      return Err(invalid_upload(
+        field,
+        format!("the {} is larger than {}MiB", field, limit_mib),
+      ));
+    }
+
+    upload.extend_from_slice(&chunk);
+  }
+
+  Ok(upload.freeze())
+}
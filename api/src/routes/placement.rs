@@ -0,0 +1,32 @@
+use actix_web::{get, http::StatusCode, web::ServiceConfig};
+use derive_more::From;
+use serde::Serialize;
+
+use crate::error::{ApiResult, InternalErrorResponse, UnauthorizeResponse};
+use crate::impl_json_responder;
+use crate::placement::{profiles, PlacementProfile};
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "placement profiles the stars can select",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct PlacementsList(Vec<PlacementProfile>);
+impl_json_responder!(PlacementsList, StatusCode::OK);
+
+#[utoipa::path(
+  responses(
+    (status = OK, response = PlacementsList),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/placements")]
+pub async fn get_all_placements() -> ApiResult<PlacementsList> {
+  Ok(PlacementsList::from(profiles().to_vec()))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg.service(get_all_placements);
+}
@@ -6,7 +6,8 @@ use actix_web::{
 };
 use derive_more::From;
 use serde::Serialize;
-use validator::Validate;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::impl_json_responder;
 use crate::models::planet::{
@@ -15,11 +16,67 @@ use crate::models::planet::{
 use crate::{database::Transaction, error::UnauthorizeResponse};
 use crate::{
   error::{
-    AlreadyExistsResponse, ApiResult, InternalErrorResponse, NotFoundResponse, ValidationResponse,
+    AlreadyExistsResponse, ApiError, ApiResult, InternalErrorResponse, NotFoundResponse,
+    ValidationResponse,
   },
   k8s::{PlanetRequestResolver, ResourceBind},
+  storage::StorageTier,
 };
 
+pub(crate) fn invalid_storage(field: &'static str, message: String) -> ApiError {
+  let mut errors = ValidationErrors::new();
+  errors.add(
+    field,
+    ValidationError::new(field).with_message(Cow::from(message)),
+  );
+
+  ApiError::Validation(errors)
+}
+
+/// the capacity must fit in the tier, and once the volume is provisioned
+/// it can only grow on the classes allowing expansion
+pub(crate) fn validate_capacity(
+  tier: &StorageTier,
+  capacity: i32,
+  previous: Option<&Planet>,
+) -> ApiResult<()> {
+  if let Some(max_capacity) = tier.max_capacity {
+    if capacity > max_capacity {
+      return Err(invalid_storage(
+        "capacity",
+        format!(
+          "the {} storage tier is limited to {}MB",
+          tier.name, max_capacity
+        ),
+      ));
+    }
+  }
+
+  if let Some(previous) = previous {
+    if capacity < previous.capacity {
+      return Err(invalid_storage(
+        "capacity",
+        format!(
+          "the capacity of a planet cannot decrease below {}MB",
+          previous.capacity
+        ),
+      ));
+    }
+
+    if capacity > previous.capacity && !tier.allow_expansion {
+      return Err(invalid_storage(
+        "capacity",
+        format!(
+          "the {} storage tier does not allow volume expansion",
+          tier.name
+        ),
+      ));
+    }
+  }
+
+  Ok(())
+}
+
 #[derive(Serialize, From, utoipa::ToResponse)]
 #[response(
   description = "all planets in the galaxy",
@@ -81,6 +138,10 @@ pub async fn create_planet(
 ) -> ApiResult<PlanetCreated> {
   data.validate()?;
 
+  if let Some(tier) = StorageTier::find(&data.storage_tier.0) {
+    validate_capacity(tier, data.capacity, None)?;
+  }
+
   let new_planet = <Planet as CrudOperations>::create(&mut tx, &path, &data).await?;
 
   ResourceBind::create(
@@ -148,6 +209,35 @@ pub async fn update_planet(
 ) -> ApiResult<PlanetUpdated> {
   data.validate()?;
 
+  let planet = Planet::get(&mut tx, &path).await?;
+
+  if let Some(storage_tier) = &data.storage_tier {
+    if storage_tier.0 != planet.storage_tier {
+      return Err(invalid_storage(
+        "storage_tier",
+        "the storage tier of a planet cannot change".to_string(),
+      ));
+    }
+  }
+
+  // a planet of a tier removed from the platform keeps its volume as it is
+  if let Some(capacity) = data
+    .capacity
+    .filter(|capacity| *capacity != planet.capacity)
+  {
+    let Some(tier) = StorageTier::find(&planet.storage_tier) else {
+      return Err(invalid_storage(
+        "capacity",
+        format!(
+          "the {} storage tier is no longer available, the capacity cannot change",
+          planet.storage_tier
+        ),
+      ));
+    };
+
+    validate_capacity(tier, capacity, Some(&planet))?;
+  }
+
   let updated_planet = <Planet as CrudOperations>::update(&mut tx, &path, &data).await?;
 
   ResourceBind::update(
@@ -184,6 +274,7 @@ pub async fn delete_planet(
 ) -> ApiResult<PlanetDeleted> {
   let deleted_planet = <Planet as CrudOperations>::delete(&mut tx, &path).await?;
 
+  // the snapshots of the planet stay, they are deleted through their own route
   ResourceBind::update(
     &deleted_planet,
     PlanetRequestResolver::try_default(&deleted_planet.galaxy_id).await?,
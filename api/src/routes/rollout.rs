@@ -0,0 +1,152 @@
+use actix_web::{
+  get,
+  http::StatusCode,
+  post,
+  web::{Path, ServiceConfig},
+  Either,
+};
+use derive_more::From;
+use serde::Serialize;
+
+use crate::impl_json_responder;
+use crate::models::{
+  planet::Planet,
+  rollout::Rollout,
+  star::{CrudOperations, Star, StarPath, StrategyKind},
+};
+use crate::{database::Transaction, error::UnauthorizeResponse};
+use crate::{
+  error::{ApiError, ApiResult, InternalErrorResponse, NotFoundResponse},
+  k8s::StarRequestResolver,
+};
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "rollout of the star waiting for promotion",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct SpecificRollout(Rollout);
+impl_json_responder!(SpecificRollout, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = SpecificRollout),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/rollout")]
+pub async fn get_rollout(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<SpecificRollout> {
+  let rollout = Rollout::get(&mut tx, &path)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+  Ok(SpecificRollout::from(rollout))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "traffic of the blue-green star on the preview, promote again to confirm once the stable deployment runs the new version",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct TrafficSwitched(Rollout);
+impl_json_responder!(TrafficSwitched, StatusCode::ACCEPTED);
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "new version of the star successfully promoted",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarPromoted(Star);
+impl_json_responder!(StarPromoted, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarPromoted),
+    (status = ACCEPTED, response = TrafficSwitched),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/promote")]
+pub async fn promote_star(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+) -> ApiResult<Either<TrafficSwitched, StarPromoted>> {
+  let rollout = Rollout::get(&mut tx, &path)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+  let star = Star::get(&mut tx, &path).await?;
+  let api = StarRequestResolver::try_default(&star.galaxy_id).await?;
+
+  // the first promotion of a blue-green star only moves the traffic,
+  // the previous version keeps running until the second one confirms the switch
+  if rollout.strategy == StrategyKind::BlueGreen && !rollout.is_switched() {
+    star.switch_to_preview(&api).await?;
+    let rollout = Rollout::switch(&mut tx, &path).await?;
+
+    return Ok(Either::Left(TrafficSwitched::from(rollout)));
+  }
+
+  // the pods are not awaited, a later promotion confirms once the stable deployment is ready
+  let planets = Planet::all_in_star(&mut tx, &path).await?;
+
+  if !star.promote(&planets, api).await? {
+    return Ok(Either::Left(TrafficSwitched::from(rollout)));
+  }
+
+  let _ = Rollout::delete(&mut tx, &path).await?;
+
+  Ok(Either::Right(StarPromoted::from(star)))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "rollout successfully aborted, the star is back to its previous version",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarRolledBack(Star);
+impl_json_responder!(StarRolledBack, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarRolledBack),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/abort")]
+pub async fn abort_rollout(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<StarRolledBack> {
+  let rollout = Rollout::delete(&mut tx, &path).await?;
+
+  // the stable deployment still runs the previous version
+  let star = Star::restore(&mut tx, &path, &rollout.previous).await?;
+  let api = StarRequestResolver::try_default(&star.galaxy_id).await?;
+
+  if rollout.is_switched() {
+    let planets = Planet::all_in_star(&mut tx, &path).await?;
+    star.switch_to_stable(&planets, &api).await?;
+  }
+
+  star.end_rollout(&api).await?;
+
+  Ok(StarRolledBack::from(star))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg
+    .service(get_rollout)
+    .service(promote_star)
+    .service(abort_rollout);
+}
@@ -0,0 +1,201 @@
+use actix_web::{
+  get,
+  http::StatusCode,
+  post,
+  web::{Json, Path, ServiceConfig},
+};
+use actix_web_lab::sse::{self, Sse};
+use derive_more::From;
+use futures_util::{AsyncBufReadExt, Stream, StreamExt};
+use serde::Serialize;
+use validator::Validate;
+
+use crate::impl_json_responder;
+use crate::models::{
+  planet::Planet,
+  run::{CreateRunData, Run, RunPath},
+  star::{Star, StarPath},
+  CrudOperations,
+};
+use crate::{
+  database::{Connection, Transaction},
+  error::UnauthorizeResponse,
+};
+use crate::{
+  error::{ApiError, ApiResult, InternalErrorResponse, NotFoundResponse, ValidationResponse},
+  k8s::RunRequestResolver,
+};
+
+/// record the outcome of the run if its job is over
+async fn sync_run(conn: &mut Connection, run: Run, api: &RunRequestResolver) -> ApiResult<Run> {
+  if !run.is_running() {
+    return Ok(run);
+  }
+
+  match run.outcome(api).await? {
+    Some(outcome) => Ok(Run::finish(conn, &run.id, &outcome).await?),
+    None => Ok(run),
+  }
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "all runs of the star, newest first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct RunsList(Vec<Run>);
+impl_json_responder!(RunsList, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = RunsList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/runs")]
+pub async fn get_all_runs(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<RunsList> {
+  let runs = Run::all(&mut tx, &path).await?;
+
+  if !runs.iter().any(Run::is_running) {
+    return Ok(RunsList::from(runs));
+  }
+
+  let api = RunRequestResolver::try_default(&path.0).await?;
+
+  let mut synced_runs = Vec::with_capacity(runs.len());
+
+  for run in runs {
+    synced_runs.push(sync_run(&mut tx, run, &api).await?);
+  }
+
+  Ok(RunsList::from(synced_runs))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "run successfully started",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct RunCreated(Run);
+impl_json_responder!(RunCreated, StatusCode::CREATED);
+
+#[utoipa::path(
+  params(StarPath),
+  request_body(
+    content = CreateRunData,
+    description = "command to run in the star environment",
+    content_type = "application/json"
+  ),
+  responses(
+    (status = CREATED, response = RunCreated),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/runs")]
+pub async fn create_run(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+  Json(data): Json<CreateRunData>,
+) -> ApiResult<RunCreated> {
+  data.validate()?;
+
+  let star = Star::get(&mut tx, &path).await?;
+  let planets = Planet::all_in_star(&mut tx, &path).await?;
+
+  let run = <Run as CrudOperations>::create(&mut tx, &path, &data).await?;
+
+  run
+    .launch(
+      &star,
+      &planets,
+      RunRequestResolver::try_default(&star.galaxy_id).await?,
+    )
+    .await?;
+
+  Ok(RunCreated::from(run))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "specific run of the star",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct SpecificRun(Run);
+impl_json_responder!(SpecificRun, StatusCode::OK);
+
+#[utoipa::path(
+  params(RunPath),
+  responses(
+    (status = OK, response = SpecificRun),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/runs/{run_id}")]
+pub async fn get_run(mut tx: Transaction, path: Path<RunPath>) -> ApiResult<SpecificRun> {
+  let run = Run::get(&mut tx, &path).await?;
+
+  if !run.is_running() {
+    return Ok(SpecificRun::from(run));
+  }
+
+  let api = RunRequestResolver::try_default(&path.0).await?;
+  let run = sync_run(&mut tx, run, &api).await?;
+
+  Ok(SpecificRun::from(run))
+}
+
+#[allow(dead_code)]
+#[derive(utoipa::ToResponse)]
+#[response(
+  description = "output of the run, one `log` event for each line",
+  content_type = "text/event-stream"
+)]
+pub struct RunLogs(String);
+
+#[utoipa::path(
+  params(RunPath),
+  responses(
+    (status = OK, response = RunLogs),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/runs/{run_id}/logs")]
+pub async fn get_run_logs(
+  mut tx: Transaction,
+  path: Path<RunPath>,
+) -> ApiResult<Sse<impl Stream<Item = Result<sse::Event, std::io::Error>>>> {
+  let run = Run::get(&mut tx, &path).await?;
+
+  // the pod of the run never started or was already removed
+  let logs = run
+    .logs(RunRequestResolver::try_default(&path.0).await?)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+  let stream = Box::pin(logs)
+    .lines()
+    .map(|line| line.map(|line| sse::Event::Data(sse::Data::new(line).event("log"))));
+
+  Ok(Sse::from_stream(stream))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg
+    .service(get_all_runs)
+    .service(create_run)
+    .service(get_run)
+    .service(get_run_logs);
+}
@@ -0,0 +1,284 @@
+use actix_web::{
+  delete, get,
+  http::StatusCode,
+  post,
+  web::{Json, Path, ServiceConfig},
+};
+use derive_more::From;
+use serde::Serialize;
+use validator::Validate;
+
+use crate::{
+  database::Transaction,
+  error::{
+    AlreadyExistsResponse, ApiResult, InternalErrorResponse, NotFoundResponse, UnauthorizeResponse,
+    ValidationResponse,
+  },
+  impl_json_responder,
+  k8s::{PlanetRequestResolver, SnapshotRequestResolver},
+  models::{
+    planet::{GalaxyPath, Planet, PlanetPath},
+    snapshot::{CreateSnapshotData, PlanetSnapshot, RestoreSnapshotData, SnapshotPath},
+    CrudOperations,
+  },
+  routes::planet::{invalid_storage, validate_capacity},
+  storage::StorageTier,
+};
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "all snapshots of a planet, newest first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct SnapshotsList(Vec<PlanetSnapshot>);
+impl_json_responder!(SnapshotsList, StatusCode::OK);
+
+#[utoipa::path(
+  params(PlanetPath),
+  responses(
+    (status = OK, response = SnapshotsList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/planets/{planet_id}/snapshots")]
+pub async fn get_all_snapshots(
+  mut tx: Transaction,
+  path: Path<PlanetPath>,
+) -> ApiResult<SnapshotsList> {
+  let snapshots = PlanetSnapshot::all(&mut tx, &path).await?;
+
+  Ok(SnapshotsList::from(snapshots))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "all snapshots of the galaxy with the ones of its deleted planets, newest first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct GalaxySnapshotsList(Vec<PlanetSnapshot>);
+impl_json_responder!(GalaxySnapshotsList, StatusCode::OK);
+
+#[utoipa::path(
+  params(GalaxyPath),
+  responses(
+    (status = OK, response = GalaxySnapshotsList),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/snapshots")]
+pub async fn get_galaxy_snapshots(
+  mut tx: Transaction,
+  path: Path<GalaxyPath>,
+) -> ApiResult<GalaxySnapshotsList> {
+  let snapshots = PlanetSnapshot::all_in_galaxy(&mut tx, &path).await?;
+
+  Ok(GalaxySnapshotsList::from(snapshots))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "snapshot successfully requested, it is pending until its volume snapshot is ready",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct SnapshotCreated(PlanetSnapshot);
+impl_json_responder!(SnapshotCreated, StatusCode::CREATED);
+
+#[utoipa::path(
+  params(PlanetPath),
+  request_body(
+    content = CreateSnapshotData,
+    description = "data for creating the snapshot",
+    content_type = "application/json"
+  ),
+  responses(
+    (status = CREATED, response = SnapshotCreated),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/planets/{planet_id}/snapshots")]
+pub async fn create_snapshot(
+  mut tx: Transaction,
+  path: Path<PlanetPath>,
+  Json(data): Json<CreateSnapshotData>,
+) -> ApiResult<SnapshotCreated> {
+  data.validate()?;
+
+  let planet = Planet::get(&mut tx, &path).await?;
+  let Some(snapshot_class) =
+    StorageTier::find(&planet.storage_tier).and_then(|tier| tier.snapshot_class.as_deref())
+  else {
+    return Err(invalid_storage(
+      "storage_tier",
+      format!(
+        "the {} storage tier does not support snapshots",
+        planet.storage_tier
+      ),
+    ));
+  };
+
+  let snapshot = <PlanetSnapshot as CrudOperations>::create(&mut tx, &path, &data).await?;
+
+  snapshot
+    .take(
+      snapshot_class,
+      &SnapshotRequestResolver::try_default(&snapshot.galaxy_id).await?,
+    )
+    .await?;
+
+  Ok(SnapshotCreated::from(snapshot))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(description = "specific snapshot", content_type = "application/json")]
+pub struct SpecificSnapshot(PlanetSnapshot);
+impl_json_responder!(SpecificSnapshot, StatusCode::OK);
+
+#[utoipa::path(
+  params(SnapshotPath),
+  responses(
+    (status = OK, response = SpecificSnapshot),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/snapshots/{snapshot_id}")]
+pub async fn get_snapshot(
+  mut tx: Transaction,
+  path: Path<SnapshotPath>,
+) -> ApiResult<SpecificSnapshot> {
+  let snapshot = PlanetSnapshot::get(&mut tx, &path).await?;
+
+  Ok(SpecificSnapshot::from(snapshot))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "snapshot successfully deleted",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct SnapshotDeleted(PlanetSnapshot);
+impl_json_responder!(SnapshotDeleted, StatusCode::OK);
+
+#[utoipa::path(
+  params(SnapshotPath),
+  responses(
+    (status = OK, response = SnapshotDeleted),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[delete("/galaxies/{galaxy_id}/snapshots/{snapshot_id}")]
+pub async fn delete_snapshot(
+  mut tx: Transaction,
+  path: Path<SnapshotPath>,
+) -> ApiResult<SnapshotDeleted> {
+  let snapshot = <PlanetSnapshot as CrudOperations>::delete(&mut tx, &path).await?;
+
+  snapshot
+    .remove(&SnapshotRequestResolver::try_default(&snapshot.galaxy_id).await?)
+    .await?;
+
+  Ok(SnapshotDeleted::from(snapshot))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "planet successfully restored from the snapshot",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct SnapshotRestored(Planet);
+impl_json_responder!(SnapshotRestored, StatusCode::CREATED);
+
+#[utoipa::path(
+  params(SnapshotPath),
+  request_body(
+    content = RestoreSnapshotData,
+    description = "data for creating the restored planet",
+    content_type = "application/json"
+  ),
+  responses(
+    (status = CREATED, response = SnapshotRestored),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = CONFLICT, response = AlreadyExistsResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/snapshots/{snapshot_id}/restore")]
+pub async fn restore_snapshot(
+  mut tx: Transaction,
+  path: Path<SnapshotPath>,
+  Json(data): Json<RestoreSnapshotData>,
+) -> ApiResult<SnapshotRestored> {
+  data.validate()?;
+
+  let snapshot = PlanetSnapshot::get(&mut tx, &path).await?;
+
+  if !snapshot.is_ready() {
+    return Err(invalid_storage(
+      "snapshot",
+      "only a ready snapshot can be restored".to_string(),
+    ));
+  }
+
+  let data = data.planet_data(&snapshot);
+
+  // the restored volume holds the whole snapshot
+  if data.capacity < snapshot.capacity {
+    return Err(invalid_storage(
+      "capacity",
+      format!(
+        "the restored planet needs at least the {}MB of the snapshot",
+        snapshot.capacity
+      ),
+    ));
+  }
+
+  let Some(tier) = StorageTier::find(&snapshot.storage_tier) else {
+    return Err(invalid_storage(
+      "storage_tier",
+      format!(
+        "the {} storage tier is no longer available",
+        snapshot.storage_tier
+      ),
+    ));
+  };
+  validate_capacity(tier, data.capacity, None)?;
+
+  let new_planet =
+    <Planet as CrudOperations>::create(&mut tx, &GalaxyPath(snapshot.galaxy_id), &data).await?;
+
+  new_planet
+    .restore(
+      &snapshot,
+      PlanetRequestResolver::try_default(&new_planet.galaxy_id).await?,
+    )
+    .await?;
+
+  Ok(SnapshotRestored::from(new_planet))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg
+    .service(get_all_snapshots)
+    .service(get_galaxy_snapshots)
+    .service(create_snapshot)
+    .service(get_snapshot)
+    .service(delete_snapshot)
+    .service(restore_snapshot);
+}
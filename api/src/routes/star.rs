@@ -7,25 +7,33 @@ use actix_web::{
 };
 use actix_web_lab::sse::{self, Sse};
 use derive_more::From;
-use futures_util::{stream::Map, Stream, StreamExt};
-use k8s_openapi::api::apps::v1::{Deployment, DeploymentStatus};
+use futures_util::{Stream, StreamExt};
+use k8s_openapi::api::{apps::v1::Deployment, batch::v1::CronJob};
 use kube::{
-  runtime::{utils::EventFlatten, watcher, WatchStreamExt},
+  runtime::{watcher, WatchStreamExt},
   Api, Client,
 };
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::impl_json_responder;
 use crate::models::star::{
-  CreateStarData, CrudOperations, GalaxyPath, Star, StarPath, UpdateStarData,
+  CreateStarData, CrudOperations, GalaxyPath, Star, StarKind, StarPath, StarState, UpdateStarData,
+};
+use crate::models::{planet::Planet, rollout::Rollout};
+use crate::registry::{NebulaReference, RegistryClient};
+use crate::routes::var::{galaxy_graph, render_vars};
+use crate::{
+  database::{Connection, Transaction},
+  error::UnauthorizeResponse,
 };
-use crate::{database::Transaction, error::UnauthorizeResponse};
 use crate::{
   error::{
-    AlreadyExistsResponse, ApiResult, InternalErrorResponse, NotFoundResponse, ValidationResponse,
+    AlreadyExistsResponse, ApiError, ApiResult, InternalErrorResponse, NotFoundResponse,
+    ValidationResponse,
   },
-  k8s::{ResourceBind, StarRequestResolver},
+  k8s::{ResourceBind, StarRequestResolver, IDLE_ANNOTATION},
 };
 
 #[derive(Serialize, From, utoipa::ToResponse)]
@@ -88,18 +96,24 @@ pub async fn create_star(
 
   let new_star = <Star as CrudOperations>::create(&mut tx, &path, &data).await?;
 
-  ResourceBind::create(
-    &new_star,
-    StarRequestResolver::try_default(&new_star.galaxy_id).await?,
-  )
-  .await?;
+  new_star.validate_kind()?;
+
+  let new_star = pin_nebula(&mut tx, None, new_star).await?;
+
+  let planets = Planet::all(&mut tx, &GalaxyPath(new_star.galaxy_id)).await?;
+  new_star.validate_containers(&planets)?;
+
+  let api = StarRequestResolver::try_default(&new_star.galaxy_id).await?;
+  new_star.validate_sidecars(api.native_sidecars().await?)?;
+
+  ResourceBind::create(&new_star, api).await?;
 
   Ok(StarCreated::from(new_star))
 }
 
 #[derive(Deserialize, utoipa::IntoParams)]
 pub struct WatchQuery {
-  watch: Option<bool>,
+  pub watch: Option<bool>,
 }
 
 #[derive(Serialize, utoipa::ToSchema)]
@@ -107,25 +121,67 @@ pub struct WatchQuery {
 pub enum StarStatus {
   Active,
   Failure,
+  Stopped,
+  Idle,
 }
 
 #[derive(Serialize, From, utoipa::ToResponse)]
 #[serde(untagged)]
 #[response(description = "specific star in the galaxy")]
 pub enum SpecificStar {
-  Star(#[content("application/json")] Star),
+  Star(#[content("application/json")] Box<Star>),
   Status(#[content("text/event-stream")] StarStatus),
 }
 impl_json_responder!(SpecificStar, StatusCode::OK);
 
 impl SpecificStar {
-  fn status(status: DeploymentStatus) -> Self {
+  fn status(deploy: Deployment) -> Self {
+    let replicas = deploy.spec.and_then(|spec| spec.replicas).unwrap_or(1);
+
+    if replicas == 0 {
+      // the activator marks the stars it scaled to zero
+      let idle = deploy
+        .metadata
+        .annotations
+        .is_some_and(|annotations| annotations.contains_key(IDLE_ANNOTATION));
+
+      return Self::Status(match idle {
+        true => StarStatus::Idle,
+        false => StarStatus::Stopped,
+      });
+    }
+
+    let status = deploy.status.expect("missing status field from watcher");
+
     // TODO fix this is valid only for deployments with replication set to 1
     Self::Status(match status.available_replicas {
       Some(_) => StarStatus::Active,
       None => StarStatus::Failure,
     })
   }
+
+  fn cron_status(cronjob: CronJob) -> Self {
+    let suspended = cronjob.spec.and_then(|spec| spec.suspend).unwrap_or(false);
+
+    if suspended {
+      return Self::Status(StarStatus::Stopped);
+    }
+
+    let status = cronjob.status.unwrap_or_default();
+    let running = status.active.is_some_and(|active| !active.is_empty());
+
+    // the last scheduled job ended without succeeding
+    let failed = match (status.last_schedule_time, status.last_successful_time) {
+      (Some(scheduled), Some(succeeded)) => scheduled.0 > succeeded.0,
+      (Some(_), None) => true,
+      _ => false,
+    };
+
+    Self::Status(match !running && failed {
+      true => StarStatus::Failure,
+      false => StarStatus::Active,
+    })
+  }
 }
 
 #[utoipa::path(
@@ -145,44 +201,47 @@ pub async fn get_star(
   path: Path<StarPath>,
   Query(WatchQuery { watch }): Query<WatchQuery>,
 ) -> ApiResult<
-  Either<
-    SpecificStar,
-    Sse<
-      Map<
-        EventFlatten<
-          impl Stream<Item = Result<watcher::Event<Deployment>, watcher::Error>> + Send,
-          Deployment,
-        >,
-        impl FnMut(Result<Deployment, watcher::Error>) -> Result<sse::Event, watcher::Error>,
-      >,
-    >,
-  >,
+  Either<SpecificStar, Sse<Box<dyn Stream<Item = Result<sse::Event, watcher::Error>> + Unpin>>>,
 > {
   let star = Star::get(&mut tx, &path).await?;
 
   if !watch.unwrap_or(false) {
-    return Ok(Either::Left(SpecificStar::from(star)));
+    return Ok(Either::Left(SpecificStar::from(Box::new(star))));
   }
 
   let client = Client::try_default().await?;
-  let api: Api<Deployment> = Api::namespaced(client, &format!("galaxy-{}", star.galaxy_id));
+  let galaxy_ns = format!("galaxy-{}", star.galaxy_id);
   let config = watcher::Config::default().labels(&format!("star_id={}", star.id));
 
-  let stream = watcher(api, config).applied_objects().map(|deploy| {
-    deploy.map(|deploy| {
+  // cron stars have no deployment, their status comes from the cronjob
+  let statuses = match star.kind {
+    StarKind::Cron => {
+      let api: Api<CronJob> = Api::namespaced(client, &galaxy_ns);
+      watcher(api, config)
+        .applied_objects()
+        .map(|cronjob| cronjob.map(SpecificStar::cron_status))
+        .boxed()
+    }
+    _ => {
+      let api: Api<Deployment> = Api::namespaced(client, &galaxy_ns);
+      watcher(api, config)
+        .applied_objects()
+        .map(|deploy| deploy.map(SpecificStar::status))
+        .boxed()
+    }
+  };
+
+  let stream = statuses.map(|status| {
+    status.map(|status| {
       sse::Event::Data(
-        sse::Data::new_json(SpecificStar::status(
-          deploy.status.expect("missing status field from watcher"),
-        ))
-        .expect("Error serializing star status")
-        .event("status"),
+        sse::Data::new_json(status)
+          .expect("Error serializing star status")
+          .event("status"),
       )
     })
   });
 
-  let sse_stream = Sse::from_stream(stream);
-
-  Ok(Either::Right(sse_stream))
+  Ok(Either::Right(Sse::from_stream(Box::new(Box::pin(stream)))))
 }
 
 #[derive(Serialize, From, utoipa::ToResponse)]
@@ -218,17 +277,126 @@ pub async fn update_star(
 ) -> ApiResult<StarUpdated> {
   data.validate()?;
 
+  let star = Star::get(&mut tx, &path).await?;
+
+  data.validate_for(&star)?;
+
   let updated_star = <Star as CrudOperations>::update(&mut tx, &path, &data).await?;
 
-  ResourceBind::update(
-    &updated_star,
-    StarRequestResolver::try_default(&updated_star.galaxy_id).await?,
-  )
-  .await?;
+  updated_star.validate_kind()?;
+
+  let planets = Planet::all(&mut tx, &GalaxyPath(updated_star.galaxy_id)).await?;
+  updated_star.validate_containers(&planets)?;
+
+  let api = StarRequestResolver::try_default(&updated_star.galaxy_id).await?;
+  updated_star.validate_sidecars(api.native_sidecars().await?)?;
+
+  let updated_star = pin_nebula(&mut tx, Some(&star), updated_star).await?;
+
+  // other stars may read the name, the domains or the port of the star
+  let referenced = star.name != updated_star.name
+    || star.private_domain != updated_star.private_domain
+    || star.public_domain != updated_star.public_domain
+    || star.port != updated_star.port;
+
+  if referenced {
+    // the stars reading the previous name are refused like the ones reading the new one
+    let graph = galaxy_graph(&mut tx, &updated_star.galaxy_id).await?;
+    graph.check(&graph.readers(&star.name))?;
+
+    // variable changes staged on the star wait for their apply, the new values wait with them
+    render_vars(
+      &mut tx,
+      &updated_star.galaxy_id,
+      &updated_star.id,
+      !star.vars_pending,
+    )
+    .await?;
+  }
+
+  deploy_update(&mut tx, &star, &updated_star).await?;
 
   Ok(StarUpdated::from(updated_star))
 }
 
+/// validation error on a nebula whose digest cannot be resolved
+fn unresolved_nebula(message: String) -> ApiError {
+  let mut errors = ValidationErrors::new();
+  errors.add(
+    "nebula",
+    ValidationError::new("unresolved").with_message(Cow::from(message)),
+  );
+
+  ApiError::Validation(errors)
+}
+
+/// record the digest the nebula points to when the star is pinned,
+/// a tag is only resolved again when the nebula changes
+pub async fn pin_nebula(
+  conn: &mut Connection,
+  star: Option<&Star>,
+  updated_star: Star,
+) -> ApiResult<Star> {
+  let path = StarPath(updated_star.galaxy_id, updated_star.id);
+
+  if !updated_star.pin_digest {
+    return match updated_star.nebula_digest {
+      Some(_) => Ok(Star::set_nebula_digest(conn, &path, None).await?),
+      None => Ok(updated_star),
+    };
+  }
+
+  let already_pinned = updated_star.nebula_digest.is_some()
+    && star.is_some_and(|star| star.nebula == updated_star.nebula);
+
+  if already_pinned {
+    return Ok(updated_star);
+  }
+
+  let reference = NebulaReference::parse(&updated_star.nebula)
+    .map_err(|err| unresolved_nebula(err.to_string()))?;
+
+  let digest = match reference.digest {
+    Some(digest) => digest.to_string(),
+    None => RegistryClient::for_domain(reference.domain)
+      .manifest_digest(&reference.repository, reference.manifest())
+      .await
+      .map_err(|err| unresolved_nebula(format!("unable to resolve the nebula: {}", err)))?
+      .ok_or_else(|| unresolved_nebula("the nebula does not exist in its registry".to_string()))?,
+  };
+
+  Ok(Star::set_nebula_digest(conn, &path, Some(&digest)).await?)
+}
+
+/// deploy the updated star, through a rollout when its strategy waits for a promotion
+pub async fn deploy_update(
+  conn: &mut Connection,
+  star: &Star,
+  updated_star: &Star,
+) -> ApiResult<()> {
+  let path = StarPath(updated_star.galaxy_id, updated_star.id);
+  let api = StarRequestResolver::try_default(&updated_star.galaxy_id).await?;
+  let rollout = Rollout::get(conn, &path).await?;
+
+  if let Some(rollout) = &rollout {
+    rollout.validate_update(star, updated_star)?;
+  }
+
+  if updated_star.strategy.has_rollout() {
+    // the first update keeps the star as it was to restore it on abort
+    if rollout.is_none() {
+      Rollout::create(conn, star, updated_star.strategy).await?;
+    }
+
+    let planets = Planet::all_in_star(conn, &path).await?;
+    updated_star.start_rollout(&planets, api).await?;
+  } else {
+    ResourceBind::update(updated_star, api).await?;
+  }
+
+  Ok(())
+}
+
 #[derive(Serialize, From, utoipa::ToResponse)]
 #[response(
   description = "star successfully deleted",
@@ -243,6 +411,7 @@ impl_json_responder!(StarDeleted, StatusCode::OK);
   responses(
     (status = OK, response = StarDeleted),
     (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
     (status = UNAUTHORIZED, response = UnauthorizeResponse),
     (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
   )
@@ -251,6 +420,10 @@ impl_json_responder!(StarDeleted, StatusCode::OK);
 pub async fn delete_star(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<StarDeleted> {
   let deleted_star = <Star as CrudOperations>::delete(&mut tx, &path).await?;
 
+  // refused while other stars reference it
+  let graph = galaxy_graph(&mut tx, &deleted_star.galaxy_id).await?;
+  graph.check(&graph.readers(&deleted_star.name))?;
+
   ResourceBind::delete(
     &deleted_star,
     StarRequestResolver::try_default(&deleted_star.galaxy_id).await?,
@@ -260,11 +433,104 @@ pub async fn delete_star(mut tx: Transaction, path: Path<StarPath>) -> ApiResult
   Ok(StarDeleted::from(deleted_star))
 }
 
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "star successfully stopped",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarStopped(Star);
+impl_json_responder!(StarStopped, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarStopped),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/stop")]
+pub async fn stop_star(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<StarStopped> {
+  let stopped_star = Star::set_state(&mut tx, &path, StarState::Stopped).await?;
+
+  stopped_star
+    .scale(StarRequestResolver::try_default(&stopped_star.galaxy_id).await?)
+    .await?;
+
+  Ok(StarStopped::from(stopped_star))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "star successfully started",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarStarted(Star);
+impl_json_responder!(StarStarted, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarStarted),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/start")]
+pub async fn start_star(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<StarStarted> {
+  let started_star = Star::set_state(&mut tx, &path, StarState::Running).await?;
+
+  started_star
+    .scale(StarRequestResolver::try_default(&started_star.galaxy_id).await?)
+    .await?;
+
+  Ok(StarStarted::from(started_star))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "star successfully restarted",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarRestarted(Star);
+impl_json_responder!(StarRestarted, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarRestarted),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/restart")]
+pub async fn restart_star(mut tx: Transaction, path: Path<StarPath>) -> ApiResult<StarRestarted> {
+  let star = Star::get(&mut tx, &path).await?;
+
+  // a stopped star has no pods, it will start fresh on the next start
+  if star.state == StarState::Running {
+    star
+      .restart(StarRequestResolver::try_default(&star.galaxy_id).await?)
+      .await?;
+  }
+
+  Ok(StarRestarted::from(star))
+}
+
 pub fn config(cfg: &mut ServiceConfig) {
   cfg
     .service(get_all_stars)
     .service(get_star)
     .service(create_star)
     .service(update_star)
-    .service(delete_star);
+    .service(delete_star)
+    .service(stop_star)
+    .service(start_star)
+    .service(restart_star);
 }
@@ -0,0 +1,32 @@
+use actix_web::{get, http::StatusCode, web::ServiceConfig};
+use derive_more::From;
+use serde::Serialize;
+
+use crate::error::{ApiResult, InternalErrorResponse, UnauthorizeResponse};
+use crate::impl_json_responder;
+use crate::storage::{tiers, StorageTier};
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "storage tiers the planets can select",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StorageTiersList(Vec<StorageTier>);
+impl_json_responder!(StorageTiersList, StatusCode::OK);
+
+#[utoipa::path(
+  responses(
+    (status = OK, response = StorageTiersList),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/storage-tiers")]
+pub async fn get_all_storage_tiers() -> ApiResult<StorageTiersList> {
+  Ok(StorageTiersList::from(tiers().to_vec()))
+}
+
+pub fn config(cfg: &mut ServiceConfig) {
+  cfg.service(get_all_storage_tiers);
+}
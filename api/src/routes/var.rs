@@ -1,26 +1,126 @@
+use std::collections::{BTreeMap, HashSet};
+
 use actix_web::{
   delete, get,
-  http::StatusCode,
+  http::{header::ContentType, StatusCode},
   post, put,
-  web::{Json, Path, ServiceConfig},
+  web::{Json, Path, Payload, Query, ReqData, ServiceConfig},
+  HttpMessage, HttpRequest, HttpResponse,
 };
 use derive_more::From;
 use serde::Serialize;
 use validator::Validate;
 
-use crate::{database::Transaction, error::UnauthorizeResponse};
+use uuid::Uuid;
+
+use crate::{
+  database::{Connection, DbError, Transaction},
+  error::UnauthorizeResponse,
+  interpolation::VariableGraph,
+  secrets::SecretStoreClient,
+};
+use crate::{
+  env_file,
+  routes::{invalid_upload, read_upload},
+};
 use crate::{
   error::{
-    AlreadyExistsResponse, ApiResult, InternalErrorResponse, NotFoundResponse, ValidationResponse,
+    AlreadyExistsResponse, ApiError, ApiResult, ForbiddenResponse, InternalErrorResponse,
+    NotFoundResponse, ValidationResponse,
+  },
+  models::var::{
+    ApplyQuery, BulkExportQuery, BulkFormat, BulkImportQuery, BulkMode, CreateVariableData,
+    PendingVariableChange, RevealedVariable, UpdateVariableData, VariableKind, VariablePath,
+    VariableReveal, VariableVersion, VariableVersionPath,
   },
-  models::var::{CreateVariableData, UpdateVariableData, VariablePath},
 };
 use crate::{impl_json_responder, models::var::Variable};
 use crate::{
-  k8s::{ResourceBind, VariableRequestResolver},
-  models::{star::StarPath, CrudOperations},
+  k8s::{pending_star_vars, sync_star_vars, VariableRequestResolver},
+  models::{
+    galaxy::{Galaxy, GalaxyPath, UserId},
+    star::{Star, StarPath},
+    CrudOperations,
+  },
 };
 
+/// the secrets of the stars are read by the owner of the galaxy only
+async fn check_galaxy_owner(
+  conn: &mut Connection,
+  galaxy_id: &Uuid,
+  user_id: &UserId,
+) -> ApiResult<()> {
+  let galaxy = Galaxy::get(conn, &GalaxyPath(*galaxy_id)).await?;
+
+  if galaxy.user_id != **user_id {
+    return Err(ApiError::Forbidden);
+  }
+
+  Ok(())
+}
+
+/// references of every variable of the galaxy
+pub(crate) async fn galaxy_graph(
+  conn: &mut Connection,
+  galaxy_id: &Uuid,
+) -> ApiResult<VariableGraph> {
+  let stars = Star::all(conn, &GalaxyPath(*galaxy_id)).await?;
+  let vars = Variable::all_in_galaxy(conn, galaxy_id).await?;
+
+  Ok(VariableGraph::new(&stars, &vars)?)
+}
+
+/// references of the galaxy, refused when a reference of the star or of a star
+/// reading it is unknown or loops, a broken star elsewhere does not block the edit
+pub(crate) async fn variable_graph(
+  conn: &mut Connection,
+  galaxy_id: &Uuid,
+  star_id: &Uuid,
+) -> ApiResult<VariableGraph> {
+  let graph = galaxy_graph(conn, galaxy_id).await?;
+  graph.check(&graph.affected(star_id))?;
+
+  Ok(graph)
+}
+
+/// render the variables of the star and of the stars referencing it, without a restart
+/// the change is only checked and the star is pending until an apply,
+/// the referencing stars with pending changes of their own keep them for their apply
+pub(crate) async fn render_vars(
+  conn: &mut Connection,
+  galaxy_id: &Uuid,
+  star_id: &Uuid,
+  restart: bool,
+) -> ApiResult<()> {
+  let graph = variable_graph(conn, galaxy_id, star_id).await?;
+  let path = StarPath(*galaxy_id, *star_id);
+
+  if !restart {
+    Star::set_vars_pending(conn, &path, true).await?;
+
+    return Ok(());
+  }
+
+  let pending: HashSet<Uuid> = Star::all(conn, &GalaxyPath(*galaxy_id))
+    .await?
+    .into_iter()
+    .filter(|star| star.vars_pending && star.id != *star_id)
+    .map(|star| star.id)
+    .collect();
+
+  let api = VariableRequestResolver::try_default(*galaxy_id).await?;
+
+  for id in graph.affected(star_id) {
+    if !pending.contains(&id) {
+      sync_star_vars(&id, &graph.render(&id)?, &api).await?;
+    }
+  }
+
+  Star::set_vars_pending(conn, &path, false).await?;
+
+  Ok(())
+}
+
 #[derive(Serialize, From, utoipa::ToResponse)]
 #[response(
   description = "all variables of a star",
@@ -59,7 +159,7 @@ pub struct StarVariableCreated(Variable);
 impl_json_responder!(StarVariableCreated, StatusCode::CREATED);
 
 #[utoipa::path(
-  params(StarPath),
+  params(StarPath, ApplyQuery),
   request_body(
     content = CreateVariableData,
     description = "data for creating the star",
@@ -78,13 +178,19 @@ impl_json_responder!(StarVariableCreated, StatusCode::CREATED);
 pub async fn create_star_var(
   mut tx: Transaction,
   path: Path<StarPath>,
-  Json(data): Json<CreateVariableData>,
+  Query(apply): Query<ApplyQuery>,
+  Json(mut data): Json<CreateVariableData>,
 ) -> ApiResult<StarVariableCreated> {
+  if let Some(external) = data.external_reference()? {
+    data.value = SecretStoreClient::for_provider(external.provider)?
+      .read(external)
+      .await?;
+  }
   data.validate()?;
 
   let var = <Variable as CrudOperations>::create(&mut tx, &path, &data).await?;
 
-  ResourceBind::create(&var, VariableRequestResolver::try_default(path.0).await?).await?;
+  render_vars(&mut tx, &path.0, &path.1, apply.restart).await?;
 
   Ok(StarVariableCreated::from(var))
 }
@@ -128,7 +234,7 @@ pub struct StarVariableUpdated(Variable);
 impl_json_responder!(StarVariableUpdated, StatusCode::OK);
 
 #[utoipa::path(
-  params(VariablePath),
+  params(VariablePath, ApplyQuery),
   request_body(
     content = UpdateStarData,
     description = "data for updating the star",
@@ -140,6 +246,7 @@ impl_json_responder!(StarVariableUpdated, StatusCode::OK);
     (status = CONFLICT, response = AlreadyExistsResponse),
     (status = BAD_REQUEST, response = ValidationResponse),
     (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = FORBIDDEN, response = ForbiddenResponse),
     (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
   )
 )]
@@ -147,13 +254,35 @@ impl_json_responder!(StarVariableUpdated, StatusCode::OK);
 pub async fn update_star_var(
   mut tx: Transaction,
   path: Path<VariablePath>,
-  Json(data): Json<UpdateVariableData>,
+  Query(apply): Query<ApplyQuery>,
+  user_id: ReqData<UserId>,
+  Json(mut data): Json<UpdateVariableData>,
 ) -> ApiResult<StarVariableUpdated> {
+  if let Some(external) = data.external_reference()? {
+    data.value = Some(
+      SecretStoreClient::for_provider(external.provider)?
+        .read(external)
+        .await?,
+    );
+  }
   data.validate()?;
 
+  let previous = Variable::get(&mut tx, &path).await?;
+
+  // a secret turned plain is shown to every reader of the star, it is read like a reveal
+  if previous.is_secret() && data.kind == Some(VariableKind::Plain) {
+    check_galaxy_owner(&mut tx, &path.0, &user_id).await?;
+
+    if data.value.is_none() {
+      VariableReveal::create(&mut tx, &previous, &user_id).await?;
+    }
+  }
+
+  VariableVersion::create(&mut tx, &previous, Some(&user_id)).await?;
+
   let var = <Variable as CrudOperations>::update(&mut tx, &path, &data).await?;
 
-  ResourceBind::update(&var, VariableRequestResolver::try_default(path.0).await?).await?;
+  render_vars(&mut tx, &path.0, &path.1, apply.restart).await?;
 
   Ok(StarVariableUpdated::from(var))
 }
@@ -168,10 +297,11 @@ pub struct StarVariableDeleted(Variable);
 impl_json_responder!(StarVariableDeleted, StatusCode::OK);
 
 #[utoipa::path(
-  params(VariablePath),
+  params(VariablePath, ApplyQuery),
   responses(
     (status = OK, response = StarVariableDeleted),
     (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
     (status = UNAUTHORIZED, response = UnauthorizeResponse),
     (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
   )
@@ -180,19 +310,423 @@ impl_json_responder!(StarVariableDeleted, StatusCode::OK);
 pub async fn delete_star_var(
   mut tx: Transaction,
   path: Path<VariablePath>,
+  Query(apply): Query<ApplyQuery>,
+  user_id: ReqData<UserId>,
 ) -> ApiResult<StarVariableDeleted> {
+  // the last state of the variable stays in the history of the star
+  let previous = Variable::get(&mut tx, &path).await?;
+  VariableVersion::create(&mut tx, &previous, Some(&user_id)).await?;
+
   let var = <Variable as CrudOperations>::delete(&mut tx, &path).await?;
 
-  ResourceBind::delete(&var, VariableRequestResolver::try_default(path.0).await?).await?;
+  render_vars(&mut tx, &path.0, &path.1, apply.restart).await?;
 
   Ok(StarVariableDeleted::from(var))
 }
 
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "value of the star variable, the read is recorded",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarVariableRevealed(RevealedVariable);
+impl_json_responder!(StarVariableRevealed, StatusCode::OK);
+
+#[utoipa::path(
+  params(VariablePath),
+  responses(
+    (status = OK, response = StarVariableRevealed),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = FORBIDDEN, response = ForbiddenResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/vars/{variable_id}/reveal")]
+pub async fn reveal_star_var(
+  mut tx: Transaction,
+  path: Path<VariablePath>,
+  user_id: ReqData<UserId>,
+) -> ApiResult<StarVariableRevealed> {
+  check_galaxy_owner(&mut tx, &path.0, &user_id).await?;
+
+  let var = Variable::get(&mut tx, &path).await?;
+
+  VariableReveal::create(&mut tx, &var, &user_id).await?;
+
+  Ok(StarVariableRevealed::from(
+    var.reveal().map_err(DbError::from)?,
+  ))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "reads of the star variable value, newest first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarVariableReveals(Vec<VariableReveal>);
+impl_json_responder!(StarVariableReveals, StatusCode::OK);
+
+#[utoipa::path(
+  params(VariablePath),
+  responses(
+    (status = OK, response = StarVariableReveals),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = FORBIDDEN, response = ForbiddenResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/vars/{variable_id}/reveals")]
+pub async fn get_star_var_reveals(
+  mut tx: Transaction,
+  path: Path<VariablePath>,
+  user_id: ReqData<UserId>,
+) -> ApiResult<StarVariableReveals> {
+  // the audit trail is for the owner of the galaxy, like the secrets it records
+  check_galaxy_owner(&mut tx, &path.0, &user_id).await?;
+
+  let reveals = VariableReveal::all(&mut tx, &path).await?;
+
+  Ok(StarVariableReveals::from(reveals))
+}
+
+/// size limit of a bulk import in MiB
+const MAX_BULK_SIZE: usize = 1;
+
+/// variables of a json object of strings, or of a dotenv file
+fn parse_bulk(req: &HttpRequest, body: &[u8]) -> ApiResult<Vec<(String, String)>> {
+  let content = std::str::from_utf8(body)
+    .map_err(|_| invalid_upload("vars", "the variables must be utf-8 encoded".to_string()))?;
+
+  if req.content_type() != "application/json" {
+    return env_file::parse(content).map_err(|err| invalid_upload("vars", err.to_string()));
+  }
+
+  let vars: BTreeMap<String, String> = serde_json::from_str(content).map_err(|_| {
+    invalid_upload(
+      "vars",
+      "the variables must be an object of strings".to_string(),
+    )
+  })?;
+
+  for (name, value) in &vars {
+    if !env_file::valid_name(name) {
+      return Err(invalid_upload(
+        "vars",
+        format!("{}: invalid variable name", name),
+      ));
+    }
+
+    if value.is_empty() {
+      return Err(invalid_upload("vars", format!("{}: cannot be empty", name)));
+    }
+  }
+
+  Ok(vars.into_iter().collect())
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "all variables of the star after the import",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarVariablesImported(Vec<Variable>);
+impl_json_responder!(StarVariablesImported, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath, BulkImportQuery, ApplyQuery),
+  request_body(
+    content = String,
+    description = "dotenv file, or an object of strings with the `application/json` content type",
+    content_type = "text/plain"
+  ),
+  responses(
+    (status = OK, response = StarVariablesImported),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[put("/galaxies/{galaxy_id}/stars/{star_id}/vars:bulk")]
+pub async fn import_star_vars(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+  Query(query): Query<BulkImportQuery>,
+  Query(apply): Query<ApplyQuery>,
+  user_id: ReqData<UserId>,
+  req: HttpRequest,
+  payload: Payload,
+) -> ApiResult<StarVariablesImported> {
+  let body = read_upload(payload, "vars", MAX_BULK_SIZE).await?;
+  let vars = parse_bulk(&req, &body)?;
+
+  let star = Star::get(&mut tx, &path).await?;
+
+  // the variables the import overwrites or removes keep their previous value
+  for previous in Variable::all(&mut tx, &path).await? {
+    if query.mode == BulkMode::Replace || vars.iter().any(|(name, _)| *name == previous.name) {
+      VariableVersion::create(&mut tx, &previous, Some(&user_id)).await?;
+    }
+  }
+  let vars = Variable::import(&mut tx, &path, &vars, &query).await?;
+
+  // the whole import is one apply and one restart of the star
+  render_vars(&mut tx, &path.0, &star.id, apply.restart).await?;
+
+  Ok(StarVariablesImported::from(vars))
+}
+
+#[utoipa::path(
+  params(StarPath, BulkExportQuery),
+  responses(
+    (
+      status = OK,
+      description = "values of all the variables, the reads of the secrets are recorded",
+      content(
+        ("text/plain" = String),
+        ("application/json" = BTreeMap<String, String>)
+      )
+    ),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = FORBIDDEN, response = ForbiddenResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/vars:bulk")]
+pub async fn export_star_vars(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+  Query(query): Query<BulkExportQuery>,
+  user_id: ReqData<UserId>,
+) -> ApiResult<HttpResponse> {
+  // exporting reveals the secrets
+  check_galaxy_owner(&mut tx, &path.0, &user_id).await?;
+
+  let vars = Variable::all(&mut tx, &path).await?;
+  let mut values = Vec::with_capacity(vars.len());
+
+  for var in vars {
+    if var.is_secret() {
+      VariableReveal::create(&mut tx, &var, &user_id).await?;
+    }
+
+    let var = var.reveal().map_err(DbError::from)?;
+    values.push((var.name, var.value));
+  }
+
+  let response = match query.format {
+    BulkFormat::Dotenv => HttpResponse::Ok()
+      .content_type(ContentType::plaintext())
+      .body(env_file::render(
+        values
+          .iter()
+          .map(|(name, value)| (name.as_str(), value.as_str())),
+      )),
+    BulkFormat::Json => HttpResponse::Ok().json(values.into_iter().collect::<BTreeMap<_, _>>()),
+  };
+
+  Ok(response)
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "previous states of the star variable, newest first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarVariableVersions(Vec<VariableVersion>);
+impl_json_responder!(StarVariableVersions, StatusCode::OK);
+
+#[utoipa::path(
+  params(VariablePath),
+  responses(
+    (status = OK, response = StarVariableVersions),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/vars/{variable_id}/versions")]
+pub async fn get_star_var_versions(
+  mut tx: Transaction,
+  path: Path<VariablePath>,
+) -> ApiResult<StarVariableVersions> {
+  let versions = VariableVersion::all(&mut tx, &path).await?;
+
+  Ok(StarVariableVersions::from(versions))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "previous states of every variable of the star with the deleted ones, newest first",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarVariablesHistory(Vec<VariableVersion>);
+impl_json_responder!(StarVariablesHistory, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarVariablesHistory),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/vars:versions")]
+pub async fn get_star_vars_history(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+) -> ApiResult<StarVariablesHistory> {
+  // the versions are found by star, the star is checked against the galaxy first
+  let _ = Star::get(&mut tx, &path).await?;
+
+  let versions = VariableVersion::all_in_star(&mut tx, &path).await?;
+
+  Ok(StarVariablesHistory::from(versions))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "star variable restored to a previous version",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarVariableRestored(Variable);
+impl_json_responder!(StarVariableRestored, StatusCode::OK);
+
+#[utoipa::path(
+  params(VariableVersionPath, ApplyQuery),
+  responses(
+    (status = OK, response = StarVariableRestored),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = CONFLICT, response = AlreadyExistsResponse),
+    (status = BAD_REQUEST, response = ValidationResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/vars/{variable_id}/versions/{version_id}/restore")]
+pub async fn restore_star_var_version(
+  mut tx: Transaction,
+  path: Path<VariableVersionPath>,
+  Query(apply): Query<ApplyQuery>,
+  user_id: ReqData<UserId>,
+) -> ApiResult<StarVariableRestored> {
+  let VariableVersionPath(galaxy_id, star_id, var_id, _) = *path;
+  let var_path = VariablePath(galaxy_id, star_id, var_id);
+
+  let version = VariableVersion::get(&mut tx, &path).await?;
+
+  // the restore is a change too, the current value becomes a version
+  let previous = Variable::get(&mut tx, &var_path).await?;
+  VariableVersion::create(&mut tx, &previous, Some(&user_id)).await?;
+
+  let var = Variable::restore(&mut tx, &var_path, &version).await?;
+
+  render_vars(&mut tx, &galaxy_id, &star_id, apply.restart).await?;
+
+  Ok(StarVariableRestored::from(var))
+}
+
+/// staged changes of the star against the variables its pods read
+async fn pending_changes(
+  conn: &mut Connection,
+  path: &StarPath,
+) -> ApiResult<Vec<PendingVariableChange>> {
+  let star = Star::get(conn, path).await?;
+  let graph = variable_graph(conn, &star.galaxy_id, &star.id).await?;
+
+  let changes = pending_star_vars(
+    &star.id,
+    &graph.render(&star.id)?,
+    &VariableRequestResolver::try_default(star.galaxy_id).await?,
+  )
+  .await?;
+
+  Ok(changes)
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "variable changes the star does not read yet",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarPendingChanges(Vec<PendingVariableChange>);
+impl_json_responder!(StarPendingChanges, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarPendingChanges),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[get("/galaxies/{galaxy_id}/stars/{star_id}/vars:pending")]
+pub async fn get_star_pending_vars(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+) -> ApiResult<StarPendingChanges> {
+  let changes = pending_changes(&mut tx, &path).await?;
+
+  Ok(StarPendingChanges::from(changes))
+}
+
+#[derive(Serialize, From, utoipa::ToResponse)]
+#[response(
+  description = "variable changes applied with a single restart of the star",
+  content_type = "application/json"
+)]
+#[serde(transparent)]
+pub struct StarChangesApplied(Vec<PendingVariableChange>);
+impl_json_responder!(StarChangesApplied, StatusCode::OK);
+
+#[utoipa::path(
+  params(StarPath),
+  responses(
+    (status = OK, response = StarChangesApplied),
+    (status = NOT_FOUND, response = NotFoundResponse),
+    (status = UNAUTHORIZED, response = UnauthorizeResponse),
+    (status = INTERNAL_SERVER_ERROR, response = InternalErrorResponse)
+  )
+)]
+#[post("/galaxies/{galaxy_id}/stars/{star_id}/vars:apply")]
+pub async fn apply_star_vars(
+  mut tx: Transaction,
+  path: Path<StarPath>,
+) -> ApiResult<StarChangesApplied> {
+  let changes = pending_changes(&mut tx, &path).await?;
+
+  // stars referencing this one may have been staged with it
+  render_vars(&mut tx, &path.0, &path.1, true).await?;
+
+  Ok(StarChangesApplied::from(changes))
+}
+
 pub fn config(cfg: &mut ServiceConfig) {
   cfg
     .service(get_all_star_vars)
+    .service(import_star_vars)
+    .service(export_star_vars)
+    .service(get_star_pending_vars)
+    .service(apply_star_vars)
+    .service(get_star_vars_history)
     .service(get_star_var)
     .service(create_star_var)
     .service(update_star_var)
-    .service(delete_star_var);
+    .service(delete_star_var)
+    .service(reveal_star_var)
+    .service(get_star_var_reveals)
+    .service(get_star_var_versions)
+    .service(restore_star_var_version);
 }
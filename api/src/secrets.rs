@@ -0,0 +1,123 @@
+//! client of the external secret stores the variables can read their value from,
+//! only stores speaking the kv v2 api of vault are supported
+
+use std::{collections::HashMap, fs};
+
+use derive_more::{Display, Error, From};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+use crate::{
+  config::config,
+  models::var::{ExternalProvider, ExternalReference},
+};
+
+#[derive(Debug, Display, Error, From)]
+pub enum SecretStoreError {
+  #[display(fmt = "No address or token configured for the {_0} store")]
+  #[from(ignore)]
+  NotConfigured(#[error(not(source))] &'static str),
+  #[display(fmt = "Secret store request failed: {_0}")]
+  Http(reqwest::Error),
+  #[display(fmt = "Secret store answered {_0} for {_1}")]
+  #[from(ignore)]
+  Status(
+    #[error(not(source))] StatusCode,
+    #[error(not(source))] String,
+  ),
+  #[display(fmt = "Secret {_0} has no string key {_1}")]
+  #[from(ignore)]
+  MissingKey(#[error(not(source))] String, #[error(not(source))] String),
+}
+
+pub type SecretStoreResult<T> = Result<T, SecretStoreError>;
+
+#[derive(Deserialize)]
+struct KvResponse {
+  data: KvData,
+}
+
+#[derive(Deserialize)]
+struct KvData {
+  data: HashMap<String, serde_json::Value>,
+}
+
+pub struct SecretStoreClient {
+  http: Client,
+  base_url: String,
+  token: String,
+  mount: String,
+}
+
+impl SecretStoreClient {
+  /// client of the store configured for the provider
+  pub fn for_provider(provider: ExternalProvider) -> SecretStoreResult<Self> {
+    match provider {
+      ExternalProvider::Vault => {
+        let config = config();
+        let token = match (&config.vault_token, &config.vault_token_file) {
+          (Some(token), _) => Some(token.clone()),
+          (None, Some(path)) => fs::read_to_string(path)
+            .ok()
+            .map(|token| token.trim().to_string()),
+          (None, None) => None,
+        };
+
+        match (&config.vault_addr, token) {
+          (Some(addr), Some(token)) => Ok(Self {
+            http: Client::new(),
+            base_url: addr.trim_end_matches('/').to_string(),
+            token,
+            mount: config.vault_mount.clone(),
+          }),
+          _ => Err(SecretStoreError::NotConfigured(provider.as_str())),
+        }
+      }
+    }
+  }
+
+  /// latest version of every key of a secret
+  pub async fn read_secret(&self, path: &str) -> SecretStoreResult<HashMap<String, String>> {
+    let path = path.trim_matches('/');
+    let response = self
+      .http
+      .get(format!("{}/v1/{}/data/{}", self.base_url, self.mount, path))
+      .header("X-Vault-Token", &self.token)
+      .send()
+      .await?;
+
+    if response.status() != StatusCode::OK {
+      return Err(SecretStoreError::Status(
+        response.status(),
+        path.to_string(),
+      ));
+    }
+
+    let secret = response.json::<KvResponse>().await?;
+
+    // numbers and booleans are read like the strings they are written as
+    Ok(
+      secret
+        .data
+        .data
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+          serde_json::Value::String(value) => Some((key, value)),
+          serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+            Some((key, value.to_string()))
+          }
+          _ => None,
+        })
+        .collect(),
+    )
+  }
+
+  /// value of the key the reference points to
+  pub async fn read(&self, reference: &ExternalReference) -> SecretStoreResult<String> {
+    self
+      .read_secret(&reference.path)
+      .await?
+      .remove(&reference.key)
+      .ok_or_else(|| SecretStoreError::MissingKey(reference.path.clone(), reference.key.clone()))
+  }
+}
@@ -0,0 +1,83 @@
+//! storage tiers defined by the platform, each tier is a storage class the planets can select
+
+use std::{collections::HashSet, fs, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::config;
+
+/// class provisioning the planets when the platform defines no tier
+const DEFAULT_STORAGE_CLASS: &str = "local-path";
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StorageTier {
+  #[schema(example = "fast-ssd")]
+  pub name: String,
+  pub description: Option<String>,
+  /// storage class of the claims of the planets
+  #[schema(example = "ssd-expandable")]
+  pub storage_class: String,
+  /// the class allows volume expansion so the capacity of a planet can grow
+  #[serde(default)]
+  pub allow_expansion: bool,
+  /// largest capacity of a planet in megabytes, unbounded when missing
+  #[schema(minimum = 1)]
+  pub max_capacity: Option<i32>,
+  /// volume snapshot class of the snapshots of the planets, the tier cannot snapshot when missing
+  #[schema(example = "ssd-snapshots")]
+  pub snapshot_class: Option<String>,
+}
+
+impl StorageTier {
+  pub fn find(name: &str) -> Option<&'static Self> {
+    tiers().iter().find(|tier| tier.name == name)
+  }
+
+  /// tier provisioning its claims with the storage class
+  pub fn find_by_class(storage_class: &str) -> Option<&'static Self> {
+    tiers()
+      .iter()
+      .find(|tier| tier.storage_class == storage_class)
+  }
+
+  /// tier of the planets created without one
+  pub fn default_tier() -> &'static Self {
+    &tiers()[0]
+  }
+}
+
+fn load_tiers() -> Vec<StorageTier> {
+  let Some(path) = &config().storage_tiers else {
+    // rancher.io/local-path provisions node local volumes which cannot grow
+    return vec![StorageTier {
+      name: DEFAULT_STORAGE_CLASS.to_string(),
+      description: Some("volumes on the disk of the node running the star".to_string()),
+      storage_class: DEFAULT_STORAGE_CLASS.to_string(),
+      allow_expansion: false,
+      max_capacity: None,
+      snapshot_class: None,
+    }];
+  };
+
+  let content = fs::read_to_string(path).expect("Unable to read the storage tiers");
+  let tiers: Vec<StorageTier> = serde_json::from_str(&content).expect("Invalid storage tiers");
+
+  if tiers.is_empty() {
+    panic!("At least one storage tier must be defined");
+  }
+
+  let mut names = HashSet::new();
+  if !tiers.iter().all(|tier| names.insert(&tier.name)) {
+    panic!("Storage tier names must be unique");
+  }
+
+  tiers
+}
+
+static TIERS: OnceLock<Vec<StorageTier>> = OnceLock::new();
+
+/// tiers of the json file configured by the platform, the first one is the default
+pub fn tiers() -> &'static [StorageTier] {
+  TIERS.get_or_init(load_tiers)
+}
@@ -0,0 +1,86 @@
+use std::{sync::Arc, time::Duration};
+
+use kube::Client;
+use log::warn;
+
+use crate::{
+  database::{DbError, Pool},
+  error::ApiResult,
+  k8s::BuildRequestResolver,
+  models::{
+    build::{Build, BuildStatus},
+    star::{Star, StarPath},
+  },
+  routes::star::{deploy_update, pin_nebula},
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// record the outcome of the build and deploy its image on success,
+/// nothing is recorded if the star cannot be deployed so it is tried again
+async fn sync_build(pool: &Pool, build: &Build, client: &Client) -> ApiResult<()> {
+  // the job runs in the build namespace of the galaxy of the star
+  let galaxy_id = {
+    let mut conn = pool.acquire().await.map_err(DbError::from)?;
+    build.star(&mut conn).await?.galaxy_id
+  };
+  let api = BuildRequestResolver::new(client.clone(), &galaxy_id);
+
+  let Some(outcome) = build.outcome(&api).await? else {
+    return Ok(());
+  };
+
+  let mut tx = pool.begin().await.map_err(DbError::from)?;
+
+  let build = Build::finish(&mut tx, &build.id, &outcome).await?;
+
+  if let (BuildStatus::Succeeded, Some(nebula)) = (build.status, build.nebula()) {
+    let star = build.star(&mut tx).await?;
+    let path = StarPath(star.galaxy_id, star.id);
+
+    let updated_star = Star::set_nebula(&mut tx, &path, &nebula).await?;
+    let updated_star = pin_nebula(&mut tx, Some(&star), updated_star).await?;
+    deploy_update(&mut tx, &star, &updated_star).await?;
+  }
+
+  tx.commit().await.map_err(DbError::from)?;
+
+  Ok(())
+}
+
+async fn sync(pool: &Pool) -> ApiResult<()> {
+  let builds = {
+    let mut conn = pool.acquire().await.map_err(DbError::from)?;
+    Build::all_running(&mut conn).await?
+  };
+
+  if builds.is_empty() {
+    return Ok(());
+  }
+
+  let client = Client::try_default().await?;
+
+  for build in builds {
+    if let Err(err) = sync_build(pool, &build, &client).await {
+      warn!(
+        "Unable to record the outcome of build {}: {:?}",
+        build.id, err
+      );
+    }
+  }
+
+  Ok(())
+}
+
+/// follow the running builds and deploy the images they push
+pub async fn run(pool: Arc<Pool>) {
+  let mut interval = actix_web::rt::time::interval(SYNC_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(err) = sync(&pool).await {
+      warn!("Unable to follow the builds: {:?}", err);
+    }
+  }
+}
@@ -0,0 +1,103 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use uuid::Uuid;
+
+use crate::{
+  database::{DbResult, Pool},
+  k8s::{sample_stars, StarUsage},
+  models::metrics::MetricsSample,
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// network counters of the previous sample, the rates are computed between two samples
+struct Counters {
+  rx_bytes: u64,
+  tx_bytes: u64,
+  sampled_at: DateTime<Utc>,
+}
+
+fn rate(current: u64, previous: u64, seconds: f64) -> Option<f64> {
+  // the counters start again from zero when the pods are replaced
+  (current >= previous && seconds > 0.0).then(|| (current - previous) as f64 / seconds)
+}
+
+async fn sample(pool: &Pool, counters: &mut HashMap<Uuid, Counters>) -> DbResult<()> {
+  let usage = match sample_stars().await {
+    Ok(usage) => usage,
+    Err(err) => {
+      warn!("Unable to sample the usage of the stars: {}", err);
+      return Ok(());
+    }
+  };
+
+  let sampled_at = Utc::now();
+  let mut conn = pool.acquire().await?;
+
+  for (
+    star_id,
+    StarUsage {
+      replicas,
+      cpu_millicores,
+      memory_bytes,
+      rx_bytes,
+      tx_bytes,
+    },
+  ) in usage
+  {
+    let previous = counters.insert(
+      star_id,
+      Counters {
+        rx_bytes,
+        tx_bytes,
+        sampled_at,
+      },
+    );
+
+    let (network_rx_rate, network_tx_rate) = match previous {
+      Some(previous) => {
+        let seconds = (sampled_at - previous.sampled_at).num_milliseconds() as f64 / 1000.0;
+
+        (
+          rate(rx_bytes, previous.rx_bytes, seconds),
+          rate(tx_bytes, previous.tx_bytes, seconds),
+        )
+      }
+      None => (None, None),
+    };
+
+    let sample = MetricsSample {
+      sampled_at,
+      replicas,
+      cpu_millicores,
+      memory_bytes,
+      network_rx_rate,
+      network_tx_rate,
+    };
+
+    // the star may have been deleted since the pods were listed
+    if let Err(err) = MetricsSample::create(&mut conn, &star_id, &sample).await {
+      warn!("Unable to store the usage of star {}: {:?}", star_id, err);
+    }
+  }
+
+  MetricsSample::prune(&mut conn).await?;
+
+  Ok(())
+}
+
+/// store the usage of the stars at a regular interval
+pub async fn run(pool: Arc<Pool>) {
+  let mut counters = HashMap::new();
+  let mut interval = actix_web::rt::time::interval(SAMPLE_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(err) = sample(&pool, &mut counters).await {
+      warn!("Unable to store the usage of the stars: {:?}", err);
+    }
+  }
+}
@@ -0,0 +1,19 @@
+//! background tasks spawned next to the http server
+
+use std::sync::Arc;
+
+use crate::database::Pool;
+
+mod builds;
+mod metrics;
+mod planets;
+mod secrets;
+mod snapshots;
+
+pub fn spawn(pool: Arc<Pool>) {
+  actix_web::rt::spawn(builds::run(Arc::clone(&pool)));
+  actix_web::rt::spawn(metrics::run(Arc::clone(&pool)));
+  actix_web::rt::spawn(planets::run(Arc::clone(&pool)));
+  actix_web::rt::spawn(secrets::run(Arc::clone(&pool)));
+  actix_web::rt::spawn(snapshots::run(pool));
+}
@@ -0,0 +1,105 @@
+use std::{sync::Arc, time::Duration};
+
+use log::warn;
+
+use crate::{
+  database::{DbError, Pool},
+  error::ApiResult,
+  k8s::PlanetRequestResolver,
+  models::planet::Planet,
+  storage::{tiers, StorageTier},
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+async fn sync_planet(pool: &Pool, planet: &Planet) -> ApiResult<()> {
+  let api = PlanetRequestResolver::try_default(&planet.galaxy_id).await?;
+  let state = planet.resize_state(&api).await?;
+
+  if state != planet.resize_state {
+    let mut conn = pool.acquire().await.map_err(DbError::from)?;
+    Planet::set_resize_state(&mut conn, &planet.id, state).await?;
+  }
+
+  Ok(())
+}
+
+async fn sync(pool: &Pool) -> ApiResult<()> {
+  let planets = {
+    let mut conn = pool.acquire().await.map_err(DbError::from)?;
+    Planet::all_resizing(&mut conn).await?
+  };
+
+  for planet in planets {
+    if let Err(err) = sync_planet(pool, &planet).await {
+      warn!(
+        "Unable to follow the resize of planet {}: {:?}",
+        planet.id, err
+      );
+    }
+  }
+
+  Ok(())
+}
+
+/// the tier provisioning the claim of the planet, the default tier before the claim exists,
+/// none when the claim uses a class no tier defines as its class cannot change
+async fn claim_tier(planet: &Planet) -> ApiResult<Option<&'static StorageTier>> {
+  let api = PlanetRequestResolver::try_default(&planet.galaxy_id).await?;
+
+  let tier = match planet.storage_class(&api).await? {
+    Some(storage_class) => StorageTier::find_by_class(&storage_class),
+    None => Some(StorageTier::default_tier()),
+  };
+
+  Ok(tier)
+}
+
+/// planets created before the tiers or left by a removed tier move to the tier of their claim,
+/// the tier gives the class of the claim and bounds the capacity of the planet
+async fn assign_tiers(pool: &Pool) -> ApiResult<()> {
+  let names = tiers()
+    .iter()
+    .map(|tier| tier.name.clone())
+    .collect::<Vec<_>>();
+
+  let mut conn = pool.acquire().await.map_err(DbError::from)?;
+
+  for planet in Planet::all_outside_tiers(&mut conn, &names).await? {
+    match claim_tier(&planet).await {
+      Ok(Some(tier)) => {
+        Planet::set_storage_tier(&mut conn, &planet.id, &tier.name).await?;
+      }
+      Ok(None) => warn!(
+        "No storage tier provisions the claim of planet {}, it keeps the {} tier",
+        planet.id, planet.storage_tier
+      ),
+      Err(err) => warn!(
+        "Unable to find the storage tier of planet {}: {:?}",
+        planet.id, err
+      ),
+    }
+  }
+
+  Ok(())
+}
+
+/// follow the volumes expanding after the capacity of their planet grew
+pub async fn run(pool: Arc<Pool>) {
+  if let Err(err) = assign_tiers(&pool).await {
+    warn!(
+      "Unable to assign the storage tiers of the planets: {:?}",
+      err
+    );
+  }
+
+  let mut interval = actix_web::rt::time::interval(SYNC_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(err) = sync(&pool).await {
+      warn!("Unable to follow the planet resizes: {:?}", err);
+    }
+  }
+}
@@ -0,0 +1,141 @@
+use std::{
+  collections::{hash_map::Entry, BTreeMap, HashMap},
+  sync::Arc,
+  time::Duration,
+};
+
+use log::warn;
+use uuid::Uuid;
+
+use crate::{
+  config::config,
+  database::{DbError, Pool},
+  encryption::SealedValue,
+  error::ApiResult,
+  models::{
+    star::{CrudOperations, Star, StarPath},
+    var::{ExternalProvider, ExternalVariable, Variable, VariableVersion},
+  },
+  routes::var::render_vars,
+  secrets::SecretStoreClient,
+};
+
+/// new values of the external variables of a star
+type StarChanges = Vec<(Variable, String)>;
+
+/// write the new values and render the variables of the star, it restarts only when its secret
+/// or its config map changed, a star with staged changes gets the new values staged with them
+async fn apply_changes(
+  pool: &Pool,
+  galaxy_id: &Uuid,
+  star_id: &Uuid,
+  changes: &StarChanges,
+) -> ApiResult<()> {
+  let mut tx = pool.begin().await.map_err(DbError::from)?;
+
+  for (var, value) in changes {
+    VariableVersion::create(&mut tx, var, None).await?;
+    Variable::set_sealed_value(
+      &mut tx,
+      &var.id,
+      &SealedValue::seal(value).map_err(DbError::from)?,
+    )
+    .await?;
+  }
+
+  let star = Star::get(&mut tx, &StarPath(*galaxy_id, *star_id)).await?;
+
+  render_vars(&mut tx, galaxy_id, star_id, !star.vars_pending).await?;
+
+  tx.commit().await.map_err(DbError::from)?;
+
+  Ok(())
+}
+
+async fn sync(pool: &Pool) -> ApiResult<()> {
+  let vars = {
+    let mut conn = pool.acquire().await.map_err(DbError::from)?;
+    Variable::all_external(&mut conn).await?
+  };
+
+  if vars.is_empty() {
+    return Ok(());
+  }
+
+  let mut clients: HashMap<ExternalProvider, SecretStoreClient> = HashMap::new();
+  // each secret is read once even when several variables use it
+  let mut secrets: HashMap<(ExternalProvider, String), HashMap<String, String>> = HashMap::new();
+  let mut changes: BTreeMap<(Uuid, Uuid), StarChanges> = BTreeMap::new();
+
+  for ExternalVariable {
+    galaxy_id,
+    variable,
+  } in vars
+  {
+    let Some(external) = variable.external.clone() else {
+      continue;
+    };
+    let secret_key = (external.provider, external.path.clone());
+
+    if !secrets.contains_key(&secret_key) {
+      let client = match clients.entry(external.provider) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(SecretStoreClient::for_provider(external.provider)?),
+      };
+
+      // the star keeps the last value read until the store answers again
+      match client.read_secret(&external.path).await {
+        Ok(secret) => {
+          secrets.insert(secret_key.clone(), secret);
+        }
+        Err(err) => {
+          warn!(
+            "Unable to read the external secret {}: {}",
+            external.path, err
+          );
+          continue;
+        }
+      }
+    }
+
+    let Some(value) = secrets[&secret_key].get(&external.key) else {
+      warn!(
+        "External secret {} has no key {} for variable {}",
+        external.path, external.key, variable.id
+      );
+      continue;
+    };
+
+    if *value != variable.value.open().map_err(DbError::from)? {
+      changes
+        .entry((galaxy_id, variable.star_id))
+        .or_default()
+        .push((variable, value.clone()));
+    }
+  }
+
+  for ((galaxy_id, star_id), star_changes) in changes {
+    if let Err(err) = apply_changes(pool, &galaxy_id, &star_id, &star_changes).await {
+      warn!(
+        "Unable to refresh the external variables of star {}: {:?}",
+        star_id, err
+      );
+    }
+  }
+
+  Ok(())
+}
+
+/// follow the values of the external variables in their stores
+pub async fn run(pool: Arc<Pool>) {
+  let mut interval =
+    actix_web::rt::time::interval(Duration::from_secs(config().external_secrets_interval));
+
+  loop {
+    interval.tick().await;
+
+    if let Err(err) = sync(&pool).await {
+      warn!("Unable to refresh the external variables: {:?}", err);
+    }
+  }
+}
@@ -0,0 +1,56 @@
+use std::{sync::Arc, time::Duration};
+
+use log::warn;
+
+use crate::{
+  database::{DbError, Pool},
+  error::ApiResult,
+  k8s::SnapshotRequestResolver,
+  models::snapshot::PlanetSnapshot,
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+async fn sync_snapshot(pool: &Pool, snapshot: &PlanetSnapshot) -> ApiResult<()> {
+  let api = SnapshotRequestResolver::try_default(&snapshot.galaxy_id).await?;
+
+  let Some(outcome) = snapshot.outcome(&api).await? else {
+    return Ok(());
+  };
+
+  let mut conn = pool.acquire().await.map_err(DbError::from)?;
+  PlanetSnapshot::finish(&mut conn, &snapshot.id, &outcome).await?;
+
+  Ok(())
+}
+
+async fn sync(pool: &Pool) -> ApiResult<()> {
+  let snapshots = {
+    let mut conn = pool.acquire().await.map_err(DbError::from)?;
+    PlanetSnapshot::all_pending(&mut conn).await?
+  };
+
+  for snapshot in snapshots {
+    if let Err(err) = sync_snapshot(pool, &snapshot).await {
+      warn!(
+        "Unable to record the state of snapshot {}: {:?}",
+        snapshot.id, err
+      );
+    }
+  }
+
+  Ok(())
+}
+
+/// follow the pending snapshots until their volume snapshot is ready or failed
+pub async fn run(pool: Arc<Pool>) {
+  let mut interval = actix_web::rt::time::interval(SYNC_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(err) = sync(&pool).await {
+      warn!("Unable to follow the snapshots: {:?}", err);
+    }
+  }
+}
@@ -0,0 +1,40 @@
+use actix_web::{middleware::Logger, web, App, HttpServer};
+use kube::Client;
+use std::{env, sync::Arc};
+
+use crate::{routes::RouteTable, scaler::Scaler};
+
+mod proxy;
+mod routes;
+mod scaler;
+
+const PORT: u16 = 8012;
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+  env_logger::init_from_env(env_logger::Env::default().default_filter_or("info"));
+
+  let namespace = env::var("POD_NAMESPACE").expect("POD_NAMESPACE must be set");
+
+  let client = Client::try_default()
+    .await
+    .expect("Unable connect to kubernetes");
+
+  let table = Arc::new(RouteTable::default());
+  let scaler = Arc::new(Scaler::new(client.clone(), &namespace));
+
+  actix_web::rt::spawn(routes::watch(client, namespace, Arc::clone(&table)));
+  actix_web::rt::spawn(scaler::reap_idle(Arc::clone(&scaler), Arc::clone(&table)));
+
+  HttpServer::new(move || {
+    App::new()
+      .app_data(web::Data::from(Arc::clone(&table)))
+      .app_data(web::Data::from(Arc::clone(&scaler)))
+      .app_data(web::Data::new(awc::Client::default()))
+      .default_service(web::to(proxy::forward))
+      .wrap(Logger::default())
+  })
+  .bind(("0.0.0.0", PORT))?
+  .run()
+  .await
+}
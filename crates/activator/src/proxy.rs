@@ -0,0 +1,72 @@
+use actix_web::{error, web, Error, HttpRequest, HttpResponse};
+use log::warn;
+
+use crate::{
+  routes::RouteTable,
+  scaler::{ScaleError, Scaler},
+};
+
+/// hold the request until the star is awake and forward it to the star service,
+/// only http is proxied so stars speaking another protocol cannot be idle
+pub async fn forward(
+  req: HttpRequest,
+  payload: web::Payload,
+  table: web::Data<RouteTable>,
+  scaler: web::Data<Scaler>,
+  client: web::Data<awc::Client>,
+) -> Result<HttpResponse, Error> {
+  let host = {
+    let info = req.connection_info();
+    let host = info.host();
+    host.split(':').next().unwrap_or(host).to_string()
+  };
+
+  let route = table
+    .find(&host)
+    .ok_or_else(|| error::ErrorNotFound("Star not found"))?;
+
+  scaler.touch(&route.deployment);
+
+  scaler
+    .wake(&route.deployment)
+    .await
+    .map_err(|err| match err {
+      ScaleError::Stopped => error::ErrorServiceUnavailable("Star is stopped"),
+      err => {
+        warn!("Error waking {}: {}", route.deployment, err);
+        error::ErrorServiceUnavailable("Star is not available")
+      }
+    })?;
+
+  let path = req
+    .uri()
+    .path_and_query()
+    .map(|path| path.as_str())
+    .unwrap_or("/");
+  let url = format!("http://{}:{}{}", route.service, route.port, path);
+
+  let forwarded_req = client
+    .request_from(url, req.head())
+    .no_decompress()
+    .insert_header(("x-forwarded-host", host.as_str()));
+
+  let forwarded_req = match req.head().peer_addr {
+    Some(addr) => forwarded_req.insert_header(("x-forwarded-for", addr.ip().to_string())),
+    None => forwarded_req,
+  };
+
+  let res = forwarded_req
+    .send_stream(payload)
+    .await
+    .map_err(error::ErrorBadGateway)?;
+
+  scaler.touch(&route.deployment);
+
+  let mut client_res = HttpResponse::build(res.status());
+
+  for (name, value) in res.headers().iter().filter(|(h, _)| *h != "connection") {
+    client_res.insert_header((name.clone(), value.clone()));
+  }
+
+  Ok(client_res.streaming(res))
+}
@@ -0,0 +1,83 @@
+use futures_util::StreamExt;
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::{
+  runtime::{watcher, WatchStreamExt},
+  Api, Client,
+};
+use log::{info, warn};
+use serde::Deserialize;
+use std::sync::{Arc, RwLock};
+
+/// config map written by the api with one entry for each idle star
+const ROUTES_CONFIG_MAP: &str = "gws-activator-routes";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Route {
+  pub hosts: Vec<String>,
+  pub service: String,
+  pub port: u16,
+  pub deployment: String,
+  /// minutes without requests before the star is scaled to zero
+  pub idle_timeout: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct RouteTable {
+  routes: RwLock<Vec<Route>>,
+}
+
+impl RouteTable {
+  pub fn find(&self, host: &str) -> Option<Route> {
+    self
+      .routes
+      .read()
+      .expect("route table lock poisoned")
+      .iter()
+      .find(|route| route.hosts.iter().any(|h| h == host))
+      .cloned()
+  }
+
+  pub fn all(&self) -> Vec<Route> {
+    self
+      .routes
+      .read()
+      .expect("route table lock poisoned")
+      .clone()
+  }
+
+  fn load(&self, config_map: ConfigMap) {
+    let routes = config_map
+      .data
+      .unwrap_or_default()
+      .into_iter()
+      .filter_map(|(key, value)| match serde_json::from_str(&value) {
+        Ok(route) => Some(route),
+        Err(err) => {
+          warn!("Invalid route {}: {}", key, err);
+          None
+        }
+      })
+      .collect::<Vec<Route>>();
+
+    info!("Loaded {} routes", routes.len());
+
+    *self.routes.write().expect("route table lock poisoned") = routes;
+  }
+}
+
+pub async fn watch(client: Client, namespace: String, table: Arc<RouteTable>) {
+  let api: Api<ConfigMap> = Api::namespaced(client, &namespace);
+  let config = watcher::Config::default().fields(&format!("metadata.name={}", ROUTES_CONFIG_MAP));
+
+  let mut stream = watcher(api, config)
+    .default_backoff()
+    .applied_objects()
+    .boxed();
+
+  while let Some(config_map) = stream.next().await {
+    match config_map {
+      Ok(config_map) => table.load(config_map),
+      Err(err) => warn!("Error watching routes: {}", err),
+    }
+  }
+}
@@ -0,0 +1,164 @@
+use derive_more::{Display, Error, From};
+use k8s_openapi::api::apps::v1::Deployment;
+use kube::{
+  api::{Patch, PatchParams},
+  runtime::wait::{self, await_condition},
+  Api, Client,
+};
+use log::{info, warn};
+use serde_json::json;
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+
+use crate::routes::RouteTable;
+
+/// marks a deployment scaled to zero by the activator, a star stopped by the user has no annotation
+const IDLE_ANNOTATION: &str = "gws.io/idle";
+const WAKE_TIMEOUT: Duration = Duration::from_secs(120);
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Display, Error, From)]
+pub enum ScaleError {
+  #[display(fmt = "star is stopped")]
+  Stopped,
+  #[display(fmt = "star not ready in time")]
+  Timeout,
+  #[display(fmt = "{}", _0)]
+  Kube(kube::Error),
+  #[display(fmt = "{}", _0)]
+  Wait(wait::Error),
+}
+
+pub struct Scaler {
+  deploy: Api<Deployment>,
+  activity: Mutex<HashMap<String, Instant>>,
+  started: Instant,
+}
+
+impl Scaler {
+  pub fn new(client: Client, namespace: &str) -> Self {
+    Self {
+      deploy: Api::namespaced(client, namespace),
+      activity: Mutex::new(HashMap::new()),
+      started: Instant::now(),
+    }
+  }
+
+  pub fn touch(&self, deployment: &str) {
+    self
+      .activity
+      .lock()
+      .expect("activity lock poisoned")
+      .insert(deployment.to_string(), Instant::now());
+  }
+
+  fn idle_for(&self, deployment: &str) -> Duration {
+    self
+      .activity
+      .lock()
+      .expect("activity lock poisoned")
+      .get(deployment)
+      .unwrap_or(&self.started)
+      .elapsed()
+  }
+
+  /// scale up an idle deployment and wait until it can serve requests
+  pub async fn wake(&self, deployment: &str) -> Result<(), ScaleError> {
+    let deploy = self.deploy.get(deployment).await?;
+
+    if replicas(&deploy) == 0 {
+      let idle = deploy
+        .metadata
+        .annotations
+        .as_ref()
+        .is_some_and(|annotations| annotations.contains_key(IDLE_ANNOTATION));
+
+      if !idle {
+        return Err(ScaleError::Stopped);
+      }
+
+      info!("Waking {}", deployment);
+
+      self.scale(deployment, 1, false).await?;
+    }
+
+    let available = |deploy: Option<&Deployment>| {
+      deploy
+        .and_then(|deploy| deploy.status.as_ref())
+        .and_then(|status| status.available_replicas)
+        .is_some_and(|available| available > 0)
+    };
+
+    actix_web::rt::time::timeout(
+      WAKE_TIMEOUT,
+      await_condition(self.deploy.clone(), deployment, available),
+    )
+    .await
+    .map_err(|_| ScaleError::Timeout)??;
+
+    Ok(())
+  }
+
+  async fn sleep(&self, deployment: &str) -> Result<(), ScaleError> {
+    let deploy = self.deploy.get(deployment).await?;
+
+    if replicas(&deploy) > 0 {
+      info!("Scaling idle {} to zero", deployment);
+
+      self.scale(deployment, 0, true).await?;
+    }
+
+    Ok(())
+  }
+
+  async fn scale(&self, deployment: &str, replicas: i32, idle: bool) -> Result<(), ScaleError> {
+    let patch = json!({
+      "metadata": {
+        "annotations": {
+          IDLE_ANNOTATION: if idle { Some("true") } else { None }
+        }
+      },
+      "spec": {
+        "replicas": replicas
+      }
+    });
+
+    let _ = self
+      .deploy
+      .patch(deployment, &PatchParams::default(), &Patch::Merge(patch))
+      .await?;
+
+    Ok(())
+  }
+}
+
+fn replicas(deploy: &Deployment) -> i32 {
+  deploy
+    .spec
+    .as_ref()
+    .and_then(|spec| spec.replicas)
+    .unwrap_or(1)
+}
+
+pub async fn reap_idle(scaler: Arc<Scaler>, table: Arc<RouteTable>) {
+  let mut interval = actix_web::rt::time::interval(REAP_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    for route in table.all() {
+      let timeout = Duration::from_secs(route.idle_timeout * 60);
+
+      if scaler.idle_for(&route.deployment) < timeout {
+        continue;
+      }
+
+      if let Err(err) = scaler.sleep(&route.deployment).await {
+        warn!("Error scaling {} to zero: {}", route.deployment, err);
+      }
+    }
+  }
+}
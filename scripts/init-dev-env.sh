@@ -1,5 +1,9 @@
+# create the registry receiving the images built from the stars source,
+# its config sets REGISTRY_STORAGE_DELETE_ENABLED=true so the unused images can be collected
+k3d registry create gws-registry --port 5000 -v "$(pwd)/scripts/registry-config.yml:/etc/docker/registry/config.yml"
+
 # create k3d cluster
-k3d cluster create $CLUSTER_NAME -p "${CLUSTER_HTTP}:80@loadbalancer" -p "${CLUSTER_HTTPS}:443@loadbalancer"
+k3d cluster create $CLUSTER_NAME -p "${CLUSTER_HTTP}:80@loadbalancer" -p "${CLUSTER_HTTPS}:443@loadbalancer" --registry-use k3d-gws-registry:5000
 echo "export KUBECONFIG=\"$(k3d kubeconfig write gws)\"" >> ~/.bashrc
 
 kubectl apply -f k8s/coredns.yaml
@@ -15,6 +19,10 @@ helm repo update
 helm upgrade --install kubernetes-replicator mittwald/kubernetes-replicator
 kubectl apply -f k8s/stars-cert-src.yaml
 
+# build the activator in front of the idle stars
+docker build -t gws-activator:latest -f crates/activator/Dockerfile .
+k3d image import gws-activator:latest -c $CLUSTER_NAME
+
 # install k8s dashboard
 helm repo add kubernetes-dashboard https://kubernetes.github.io/dashboard/
 helm upgrade --install kubernetes-dashboard kubernetes-dashboard/kubernetes-dashboard --create-namespace --namespace kubernetes-dashboard
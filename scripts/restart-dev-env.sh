@@ -2,7 +2,7 @@
 k3d cluster delete $CLUSTER_NAME
 
 # create k3d cluster 
-k3d cluster create $CLUSTER_NAME -p "${CLUSTER_HTTP}:80@loadbalancer" -p "${CLUSTER_HTTPS}:443@loadbalancer"
+k3d cluster create $CLUSTER_NAME -p "${CLUSTER_HTTP}:80@loadbalancer" -p "${CLUSTER_HTTPS}:443@loadbalancer" --registry-use k3d-gws-registry:5000
 k3d kubeconfig write gws
 
 kubectl apply -f k8s/coredns.yaml
@@ -15,6 +15,10 @@ kubectl apply -f k8s/http-to-https-middleware.yaml
 helm upgrade --install kubernetes-replicator mittwald/kubernetes-replicator
 kubectl apply -f k8s/stars-cert-src.yaml
 
+# build the activator in front of the idle stars
+docker build -t gws-activator:latest -f crates/activator/Dockerfile .
+k3d image import gws-activator:latest -c $CLUSTER_NAME
+
 helm upgrade --install kubernetes-dashboard kubernetes-dashboard/kubernetes-dashboard --create-namespace --namespace kubernetes-dashboard
 
 kubectl create sa kube-ds-admin -n kube-system